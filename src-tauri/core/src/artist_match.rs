@@ -0,0 +1,267 @@
+//! Recognising artists under the names timetables actually use: "KROMA",
+//! "Kroma" and "DJ Kroma (live)" are one artist.
+//!
+//! Names are compared by their match key (lowercase, accents folded, "DJ"
+//! prefix and performance notes such as "(live)" or "DJ set" dropped), and
+//! against the artist's aliases as well as its name. Near misses are found
+//! by edit distance between keys.
+
+use crate::models::{Artist, ArtistMatch, ArtistMatchKind};
+
+// Trailing words that describe the performance rather than the artist
+const PERFORMANCE_SUFFIXES: &[&[&str]] = &[&["dj", "set"], &["live", "set"], &["hybrid", "set"], &["live"]];
+
+// The form names are compared in: "DJ Kroma (Live)" and "kroma" give "kroma"
+pub fn match_key(name: &str) -> String {
+    let lowered = name.to_lowercase();
+    // Bracketed notes: "(live)", "[b2b set]"
+    let mut depth = 0usize;
+    let mut text = String::with_capacity(lowered.len());
+    for c in lowered.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => fold_char(c, &mut text),
+            _ => {}
+        }
+    }
+
+    let mut words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+    if words.len() > 1 && words[0] == "dj" {
+        words.remove(0);
+    }
+    if let Some(suffix) = PERFORMANCE_SUFFIXES.iter().find(|s| words.len() > s.len() && words.ends_with(s)) {
+        words.truncate(words.len() - suffix.len());
+    }
+    words.join(" ")
+}
+
+// The artist named in `text`, trying in turn: the same key as an artist's
+// name or alias, a name or alias appearing in it as whole words (so "KROMA
+// b2b Nyx" finds KROMA, the longest such name winning), and a single
+// closest name within a few typos. Artists need their aliases filled in.
+pub fn match_artist<'a>(artists: &'a [Artist], text: &str) -> Option<(&'a Artist, ArtistMatchKind)> {
+    let key = match_key(text);
+    if key.is_empty() {
+        return None;
+    }
+    let names: Vec<(&Artist, String, bool)> = artists
+        .iter()
+        .flat_map(|a| {
+            std::iter::once((a, match_key(&a.name), false)).chain(a.aliases.iter().map(move |alias| (a, match_key(alias), true)))
+        })
+        .filter(|(_, name, _)| !name.is_empty())
+        .collect();
+
+    if let Some((artist, _, is_alias)) = names.iter().find(|(_, name, _)| *name == key) {
+        return Some((artist, if *is_alias { ArtistMatchKind::Alias } else { ArtistMatchKind::Exact }));
+    }
+
+    let padded = format!(" {} ", key);
+    let contained = names
+        .iter()
+        .filter(|(_, name, _)| padded.contains(&format!(" {} ", name)))
+        .max_by_key(|(_, name, _)| name.len());
+    if let Some((artist, _, _)) = contained {
+        return Some((artist, ArtistMatchKind::Contained));
+    }
+
+    let allowed = allowed_distance(&key);
+    let mut best: Option<(&Artist, usize)> = None;
+    let mut tied = false;
+    for (artist, name, _) in &names {
+        let distance = edit_distance(&key, name);
+        if distance > allowed {
+            continue;
+        }
+        match best {
+            Some((current, d)) if distance == d && current.id != artist.id => tied = true,
+            Some((_, d)) if distance >= d => {}
+            _ => {
+                best = Some((artist, distance));
+                tied = false;
+            }
+        }
+    }
+    // Two artists equally close is a guess, not a match
+    best.filter(|_| !tied).map(|(artist, _)| (artist, ArtistMatchKind::Fuzzy))
+}
+
+// Every artist that could be meant by `text`, best first: exact and alias
+// matches, names contained in it, then names within a few typos
+pub fn rank_artists(artists: &[Artist], text: &str, limit: usize) -> Vec<ArtistMatch> {
+    let key = match_key(text);
+    if key.is_empty() {
+        return Vec::new();
+    }
+    let padded = format!(" {} ", key);
+    let allowed = allowed_distance(&key).max(2);
+
+    let mut matches: Vec<ArtistMatch> = artists
+        .iter()
+        .filter_map(|artist| {
+            let names = std::iter::once((artist.name.as_str(), false)).chain(artist.aliases.iter().map(|a| (a.as_str(), true)));
+            names
+                .filter_map(|(name, is_alias)| {
+                    let name_key = match_key(name);
+                    if name_key.is_empty() {
+                        return None;
+                    }
+                    let distance = edit_distance(&key, &name_key);
+                    let kind = if distance == 0 && is_alias {
+                        ArtistMatchKind::Alias
+                    } else if distance == 0 {
+                        ArtistMatchKind::Exact
+                    } else if padded.contains(&format!(" {} ", name_key)) || format!(" {} ", name_key).contains(&padded) {
+                        ArtistMatchKind::Contained
+                    } else if distance <= allowed {
+                        ArtistMatchKind::Fuzzy
+                    } else {
+                        return None;
+                    };
+                    Some((kind, distance, name))
+                })
+                .min_by_key(|(kind, distance, _)| (*kind, *distance))
+                .map(|(kind, distance, name)| ArtistMatch {
+                    artist: artist.clone(),
+                    kind,
+                    matched_name: name.to_string(),
+                    distance: distance as u32,
+                })
+        })
+        .collect();
+    matches.sort_by(|a, b| (a.kind, a.distance, &a.artist.name).cmp(&(b.kind, b.distance, &b.artist.name)));
+    matches.truncate(limit);
+    matches
+}
+
+// Levenshtein distance in characters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+// Typos tolerated for a key of this length: none for short names, where one
+// letter often is the difference between two artists
+fn allowed_distance(key: &str) -> usize {
+    match key.chars().count() {
+        0..=4 => 0,
+        5..=8 => 1,
+        _ => 2,
+    }
+}
+
+// Lowercase letters with their accent removed, so "Nyxéa" keys as "nyxea"
+fn fold_char(c: char, out: &mut String) {
+    let folded = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        'þ' => "th",
+        _ => {
+            out.push(c);
+            return;
+        }
+    };
+    out.push_str(folded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artist(name: &str, aliases: &[&str]) -> Artist {
+        Artist {
+            id: name.to_string(),
+            name: name.to_string(),
+            vjtools_id: None,
+            created_at: 0,
+            updated_at: 0,
+            sync_status: "new".to_string(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            profile: Default::default(),
+        }
+    }
+
+    #[test]
+    fn keys_ignore_case_accents_prefixes_and_notes() {
+        assert_eq!(match_key("DJ Kroma (live)"), "kroma");
+        assert_eq!(match_key("KROMA"), "kroma");
+        assert_eq!(match_key("Kroma Live"), "kroma");
+        assert_eq!(match_key("Nyxéa [DJ set]"), "nyxea");
+        assert_eq!(match_key("Björk DJ Set"), "bjork");
+        // A name that is only the prefix or the note stays as it is
+        assert_eq!(match_key("DJ"), "dj");
+        assert_eq!(match_key("Live"), "live");
+        assert_eq!(edit_distance("kroma", "kromma"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn matches_by_name_alias_containment_and_typos() {
+        let artists = [
+            artist("KROMA", &["Chromatic Kroma"]),
+            artist("Nyx", &[]),
+            artist("Nyx Collective", &[]),
+            artist("Solaris", &[]),
+            artist("Solarix", &[]),
+        ];
+        let found = |text: &str| match_artist(&artists, text).map(|(a, kind)| (a.name.as_str(), kind));
+
+        assert_eq!(found("DJ Kroma (live)"), Some(("KROMA", ArtistMatchKind::Exact)));
+        assert_eq!(found("chromatic kroma"), Some(("KROMA", ArtistMatchKind::Alias)));
+        assert_eq!(found("Nyx Collective b2b Guest"), Some(("Nyx Collective", ArtistMatchKind::Contained)));
+        assert_eq!(found("Kromma"), Some(("KROMA", ArtistMatchKind::Fuzzy)));
+        // Equally close to two artists
+        assert_eq!(found("Solarim"), None);
+        // Too short to allow a typo
+        assert_eq!(found("Nix"), None);
+
+        let ranked = rank_artists(&artists, "solari", 10);
+        let names: Vec<_> = ranked.iter().map(|m| m.artist.name.as_str()).collect();
+        assert_eq!(names, ["Solaris", "Solarix"]);
+        assert!(ranked.iter().all(|m| m.kind == ArtistMatchKind::Fuzzy && m.distance == 1));
+        assert_eq!(rank_artists(&artists, "kroma", 1)[0].kind, ArtistMatchKind::Exact);
+    }
+
+    #[test]
+    fn matches_artists_by_name() {
+        let artists = [artist("KROMA", &[]), artist("Nyx", &[]), artist("Nyx Collective", &[])];
+        let name = |text: &str| match_artist(&artists, text).map(|(a, _)| a.name.as_str());
+
+        assert_eq!(name("kroma"), Some("KROMA"));
+        assert_eq!(name("KROMA b2b Somebody"), Some("KROMA"));
+        assert_eq!(name("Nyx Collective (live)"), Some("Nyx Collective"));
+        assert_eq!(name("Kromatic"), None);
+        assert_eq!(name(""), None);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::error::AppResult;
+use crate::migrations;
+use rusqlite::Connection;
+use std::path::Path;
+
+// Opens (or creates) the database file and brings its schema up to date.
+// An existing database is backed up before any migration touches it.
+pub fn open(db_path: &Path) -> AppResult<Connection> {
+    let mut conn = Connection::open(db_path)?;
+    configure(&conn)?;
+
+    let version = migrations::run_migrations(&mut conn, db_path)?;
+    println!("Database schema at version {}", version);
+
+    Ok(conn)
+}
+
+// Fresh, fully migrated database that lives only as long as the connection.
+// Used by the test suite.
+pub fn open_in_memory() -> rusqlite::Result<Connection> {
+    let mut conn = Connection::open_in_memory()?;
+    configure(&conn)?;
+    migrations::apply_pending(&mut conn)?;
+    Ok(conn)
+}
+
+fn configure(conn: &Connection) -> rusqlite::Result<()> {
+    // Enable Foreign Keys
+    conn.execute("PRAGMA foreign_keys = ON;", [])?;
+    Ok(())
+}
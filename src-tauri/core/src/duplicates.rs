@@ -0,0 +1,257 @@
+//! Content and perceptual hashes of logo files, and grouping logos that are
+//! the same asset: byte-identical copies, or images that only differ by a
+//! re-export or resize.
+
+use crate::error::{AppError, AppResult};
+use crate::library::{media_kind, LibraryFile};
+use crate::models::{DuplicateGroup, DuplicateKind, Logo, MediaKind};
+use crate::repo::LogoRepo;
+use crate::thumbnail::{self, source_key};
+use image::imageops::FilterType;
+use image::GrayImage;
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+// How many of the 64 bits two perceptual hashes may differ in and still
+// count as the same image
+pub const SIMILAR_DISTANCE: u32 = 6;
+
+// A logo whose hashes need (re)computing
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashJob {
+    pub logo_id: String,
+    pub source: PathBuf,
+    pub key: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileHashes {
+    pub content: String,
+    // None for videos and images that couldn't be decoded
+    pub perceptual: Option<String>,
+}
+
+// Logos whose file changed since it was last hashed, or was never hashed.
+// Logos whose file is missing are skipped.
+pub fn pending(conn: &Connection) -> AppResult<Vec<HashJob>> {
+    let logos = LogoRepo::new(conn).list()?;
+    let jobs = logos
+        .into_iter()
+        .filter(|logo| !logo.missing)
+        .filter_map(|logo| {
+            let source = PathBuf::from(&logo.file_path);
+            let key = source_key(&LibraryFile::read(&source).ok()?);
+            (logo.hash_key.as_deref() != Some(key.as_str())).then_some(HashJob {
+                logo_id: logo.id,
+                source,
+                key,
+            })
+        })
+        .collect();
+    Ok(jobs)
+}
+
+pub fn compute(job: &HashJob) -> AppResult<FileHashes> {
+    let content = content_hash(&job.source)?;
+    let perceptual = match media_kind(&job.source) {
+        Some(MediaKind::Image) => perceptual_hash(&job.source).ok().map(|hash| format!("{:016x}", hash)),
+        _ => None,
+    };
+    Ok(FileHashes { content, perceptual })
+}
+
+pub fn store(conn: &Connection, job: &HashJob, hashes: &FileHashes) -> AppResult<()> {
+    LogoRepo::new(conn).set_hashes(&job.logo_id, Some(&hashes.content), hashes.perceptual.as_deref(), &job.key)
+}
+
+// Hex SHA-256 of the file
+pub fn content_hash(path: &Path) -> AppResult<String> {
+    let context = format!("Failed to hash {}", path.display());
+    let mut file = File::open(path).map_err(|e| AppError::io(&context, e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| AppError::io(&context, e))?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// 64-bit difference hash: each bit says whether a pixel of a 9x8 grayscale
+// version is brighter than its right neighbour. Transparent areas count as
+// black, so the same logo on different transparent backgrounds matches.
+pub fn perceptual_hash(path: &Path) -> AppResult<u64> {
+    let image = thumbnail::render(path, 64)?;
+    let gray = GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let luma = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+        image::Luma([(luma * a as u32 / 255) as u8])
+    });
+    let small = image::imageops::resize(&gray, 9, 8, FilterType::Triangle);
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y).0[0] > small.get_pixel(x + 1, y).0[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Ok(hash)
+}
+
+// Number of differing bits
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+// Groups of logos with the same content hash, then groups of images whose
+// perceptual hashes are at most `max_distance` apart. A set of copies of
+// one file is only reported as identical, not again as similar.
+pub fn find_duplicates(conn: &Connection, max_distance: u32) -> AppResult<Vec<DuplicateGroup>> {
+    let mut logos = LogoRepo::new(conn).list()?;
+    logos.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+    let mut groups = Vec::new();
+
+    let mut by_content: HashMap<&str, Vec<&Logo>> = HashMap::new();
+    for logo in &logos {
+        if let Some(hash) = logo.content_hash.as_deref() {
+            by_content.entry(hash).or_default().push(logo);
+        }
+    }
+    let mut identical: Vec<Vec<&Logo>> = by_content.into_values().filter(|g| g.len() > 1).collect();
+    identical.sort_by(|a, b| (a[0].created_at, &a[0].id).cmp(&(b[0].created_at, &b[0].id)));
+    groups.extend(identical.into_iter().map(|g| group(DuplicateKind::Identical, g)));
+
+    // Union-find over images that are close enough
+    let images: Vec<(&Logo, u64)> = logos
+        .iter()
+        .filter_map(|logo| {
+            let hash = u64::from_str_radix(logo.perceptual_hash.as_deref()?, 16).ok()?;
+            Some((logo, hash))
+        })
+        .collect();
+    let mut parent: Vec<usize> = (0..images.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..images.len() {
+        for j in i + 1..images.len() {
+            if distance(images[i].1, images[j].1) <= max_distance {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[b.max(a)] = a.min(b);
+            }
+        }
+    }
+    let mut similar: Vec<Vec<&Logo>> = Vec::new();
+    let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+    for (i, (logo, _)) in images.iter().enumerate() {
+        let r = root(&mut parent, i);
+        let index = *group_of_root.entry(r).or_insert_with(|| {
+            similar.push(Vec::new());
+            similar.len() - 1
+        });
+        similar[index].push(*logo);
+    }
+    groups.extend(
+        similar
+            .into_iter()
+            .filter(|g| g.iter().any(|logo| logo.content_hash != g[0].content_hash))
+            .map(|g| group(DuplicateKind::Similar, g)),
+    );
+    Ok(groups)
+}
+
+fn group(kind: DuplicateKind, logos: Vec<&Logo>) -> DuplicateGroup {
+    DuplicateGroup {
+        kind,
+        logos: logos.into_iter().cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use image::{Rgba, RgbaImage};
+
+    // A logo-like image: a bright block on the left, dark on the right
+    fn write_logo(path: &Path, width: u32, height: u32) {
+        RgbaImage::from_fn(width, height, |x, y| {
+            if x < width / 3 || y < height / 4 {
+                Rgba([250, 250, 250, 255])
+            } else {
+                Rgba([20, 20, 40, 255])
+            }
+        })
+        .save(path)
+        .unwrap();
+    }
+
+    fn hash_all(conn: &Connection) {
+        for job in pending(conn).unwrap() {
+            store(conn, &job, &compute(&job).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn resized_images_hash_alike() {
+        let dir = tempfile::tempdir().unwrap();
+        let (big, small, other) = (dir.path().join("big.png"), dir.path().join("small.png"), dir.path().join("other.png"));
+        write_logo(&big, 800, 400);
+        write_logo(&small, 200, 100);
+        RgbaImage::from_fn(200, 100, |x, _| Rgba([(x % 2 * 255) as u8, 0, 0, 255])).save(&other).unwrap();
+
+        let (big, small, other) = (perceptual_hash(&big).unwrap(), perceptual_hash(&small).unwrap(), perceptual_hash(&other).unwrap());
+        assert!(distance(big, small) <= SIMILAR_DISTANCE);
+        assert!(distance(big, other) > SIMILAR_DISTANCE);
+    }
+
+    #[test]
+    fn reports_identical_and_similar_groups() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let original = dir.path().join("kroma.png");
+        write_logo(&original, 400, 200);
+        std::fs::copy(&original, dir.path().join("kroma copy.png")).unwrap();
+        write_logo(&dir.path().join("kroma small.png"), 100, 50);
+        std::fs::write(dir.path().join("clip.mp4"), b"not really a video").unwrap();
+        for name in ["kroma.png", "kroma copy.png", "kroma small.png", "clip.mp4"] {
+            repo.add(name, &dir.path().join(name).to_string_lossy(), None).unwrap();
+        }
+
+        hash_all(&conn);
+        assert!(pending(&conn).unwrap().is_empty());
+
+        let groups = find_duplicates(&conn, SIMILAR_DISTANCE).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].kind, DuplicateKind::Identical);
+        let mut names: Vec<_> = groups[0].logos.iter().map(|l| l.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["kroma copy.png", "kroma.png"]);
+        assert_eq!(groups[1].kind, DuplicateKind::Similar);
+        assert_eq!(groups[1].logos.len(), 3);
+    }
+
+    #[test]
+    fn copies_alone_are_not_reported_as_similar() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LogoRepo::new(&conn);
+        write_logo(&dir.path().join("a.png"), 64, 64);
+        std::fs::copy(dir.path().join("a.png"), dir.path().join("b.png")).unwrap();
+        for name in ["a.png", "b.png"] {
+            repo.add(name, &dir.path().join(name).to_string_lossy(), None).unwrap();
+        }
+
+        hash_all(&conn);
+        let groups = find_duplicates(&conn, SIMILAR_DISTANCE).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].kind, DuplicateKind::Identical);
+    }
+}
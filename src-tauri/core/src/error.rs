@@ -0,0 +1,156 @@
+use serde::Serialize;
+use std::fmt;
+
+// What went wrong, so the frontend can branch on it instead of parsing the
+// message. Serialized in snake_case, e.g. "not_found".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    // The database connection hasn't been opened (yet)
+    DatabaseUnavailable,
+    // Any other SQLite failure
+    Database,
+    // The entity named in `entity_id` doesn't exist (or was deleted)
+    NotFound,
+    // A UNIQUE value is already taken; `entity_id` points at the existing row
+    Duplicate,
+    // An argument was rejected; `field` names it
+    InvalidInput,
+    // The operation clashes with the current state (e.g. server already running)
+    Conflict,
+    // Filesystem access failed
+    Io,
+    // Reading or writing a config file failed
+    Config,
+    // Window/runtime failures that the user can't do much about
+    Internal,
+}
+
+// Error returned by every command. Serialized as
+// `{ kind, message, field?, entity_id? }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<String>,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            field: None,
+            entity_id: None,
+        }
+    }
+
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    pub fn with_entity_id(mut self, entity_id: impl Into<String>) -> Self {
+        self.entity_id = Some(entity_id.into());
+        self
+    }
+
+    pub fn database_unavailable() -> Self {
+        Self::new(ErrorKind::DatabaseUnavailable, "Database connection not available")
+    }
+
+    // `entity` is a human readable name such as "Artist"
+    pub fn not_found(entity: &str, id: &str) -> Self {
+        Self::new(ErrorKind::NotFound, format!("{} '{}' not found", entity, id)).with_entity_id(id)
+    }
+
+    pub fn duplicate(field: &str, existing_id: &str, message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Duplicate, message)
+            .with_field(field)
+            .with_entity_id(existing_id)
+    }
+
+    pub fn invalid(field: &str, message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message).with_field(field)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Conflict, message)
+    }
+
+    pub fn io(context: &str, e: std::io::Error) -> Self {
+        Self::new(ErrorKind::Io, format!("{}: {}", context, e))
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Config, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+
+    // Prefixes the message, keeping kind, field and id
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::new(ErrorKind::Database, e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        Self::new(ErrorKind::Io, e.to_string())
+    }
+}
+
+// True for UNIQUE / FOREIGN KEY / NOT NULL failures
+pub(crate) fn is_constraint_violation(e: &rusqlite::Error) -> bool {
+    e.sqlite_error_code() == Some(rusqlite::ErrorCode::ConstraintViolation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_kind_in_snake_case_and_skips_empty_fields() {
+        let json = serde_json::to_value(AppError::database_unavailable()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "database_unavailable", "message": "Database connection not available" })
+        );
+
+        let json = serde_json::to_value(AppError::duplicate("file_path", "logo-1", "taken")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "duplicate", "message": "taken", "field": "file_path", "entity_id": "logo-1" })
+        );
+    }
+
+    #[test]
+    fn context_keeps_details() {
+        let e = AppError::not_found("Logo", "l1").context("Failed to delete logo");
+        assert_eq!(e.kind, ErrorKind::NotFound);
+        assert_eq!(e.entity_id.as_deref(), Some("l1"));
+        assert_eq!(e.message, "Failed to delete logo: Logo 'l1' not found");
+    }
+}
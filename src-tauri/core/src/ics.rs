@@ -0,0 +1,417 @@
+//! iCalendar (.ics) support for the schedule: reading VEVENTs into
+//! [`SourceEvent`]s for the import preview, and writing the schedule back out
+//! as a calendar.
+//!
+//! All times in the schedule are local wall-clock time. Incoming UTC and
+//! TZID times are converted to local time; outgoing times are written as
+//! "floating" local times.
+
+use crate::error::{AppError, AppResult};
+use crate::import::{self, SourceEvent};
+use crate::models::{ImportPreview, ScheduleItem, SkippedEntry};
+use crate::repo::ScheduleRepo;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use ical::parser::ical::component::IcalEvent;
+use ical::property::Property;
+use ical::IcalParser;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+const ICS_DATE_TIME: &str = "%Y%m%dT%H%M%S";
+
+// Lines longer than this (in bytes) are folded, as RFC 5545 asks
+const MAX_LINE_BYTES: usize = 75;
+
+// Ends the UID of exported events that weren't imported from a calendar
+const OWN_UID_SUFFIX: &str = "@vj-event-sync";
+
+// Reads an .ics file and previews importing it (see `import::preview`)
+pub fn preview_file(conn: &Connection, path: &Path) -> AppResult<ImportPreview> {
+    let file = File::open(path).map_err(|e| AppError::io(&format!("Failed to open {}", path.display()), e))?;
+    let (events, skipped) = parse_events(BufReader::new(file))?;
+    import::preview(conn, events, skipped)
+}
+
+// Writes the whole schedule to `path` as an .ics file
+pub fn export_file(conn: &Connection, path: &Path) -> AppResult<()> {
+    let repo = ScheduleRepo::new(conn);
+    let schedule = repo.list()?;
+    let source_uids = repo.ids_by_source_uid()?.into_iter().map(|(uid, id)| (id, uid)).collect();
+    std::fs::write(path, export_events(&schedule, &source_uids, Utc::now()))
+        .map_err(|e| AppError::io(&format!("Failed to write {}", path.display()), e))
+}
+
+// Reads every VEVENT of every calendar in `reader`. Events that can't be
+// used (no start, all-day, ...) are returned as skipped entries instead of
+// failing the whole file.
+pub fn parse_events<R: BufRead>(reader: R) -> AppResult<(Vec<SourceEvent>, Vec<SkippedEntry>)> {
+    let mut events = Vec::new();
+    let mut skipped = Vec::new();
+    let mut index = 0;
+
+    for calendar in IcalParser::new(reader) {
+        let calendar = calendar.map_err(|e| AppError::invalid("file", format!("Not a valid iCalendar file: {}", e)))?;
+        for vevent in &calendar.events {
+            index += 1;
+            match parse_event(vevent) {
+                Ok(event) => events.push(event),
+                Err(reason) => {
+                    let summary = property(vevent, "SUMMARY").and_then(|p| p.value.as_deref()).map(unescape);
+                    skipped.push(SkippedEntry {
+                        location: match summary {
+                            Some(summary) => format!("VEVENT {} '{}'", index, summary),
+                            None => format!("VEVENT {}", index),
+                        },
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok((events, skipped))
+}
+
+fn parse_event(vevent: &IcalEvent) -> Result<SourceEvent, String> {
+    if property(vevent, "STATUS").and_then(|p| p.value.as_deref()) == Some("CANCELLED") {
+        return Err("Event is cancelled".to_string());
+    }
+
+    let start = property(vevent, "DTSTART").ok_or("Event has no start time")?;
+    let starts_at = parse_time(start)?;
+
+    let ends_at = match (property(vevent, "DTEND"), property(vevent, "DURATION")) {
+        (Some(end), _) => Some(parse_time(end)?),
+        (None, Some(duration)) => {
+            let value = duration.value.as_deref().unwrap_or_default();
+            Some(starts_at + parse_duration(value).ok_or_else(|| format!("Unreadable duration '{}'", value))?)
+        }
+        (None, None) => None,
+    };
+    // A zero-length event is a marker, not a set with a known end
+    let ends_at = ends_at.filter(|end| *end != starts_at);
+    if ends_at.is_some_and(|end| end < starts_at) {
+        return Err("Event ends before it starts".to_string());
+    }
+
+    let name = property(vevent, "SUMMARY")
+        .and_then(|p| p.value.as_deref())
+        .map(|v| unescape(v).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .ok_or("Event has no title")?;
+
+    Ok(SourceEvent {
+        uid: property(vevent, "UID").and_then(|p| p.value.clone()),
+        starts_at,
+        ends_at,
+        name,
+        // Our own exports carry the event type as the first category
+        event_type: property(vevent, "CATEGORIES")
+            .and_then(|p| p.value.as_deref())
+            .map(|v| unescape(first_list_value(v)).trim().to_string())
+            .filter(|v| !v.is_empty()),
+        artist: None,
+    })
+}
+
+fn property<'a>(vevent: &'a IcalEvent, name: &str) -> Option<&'a Property> {
+    vevent.properties.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+fn param<'a>(property: &'a Property, name: &str) -> Option<&'a str> {
+    property
+        .params
+        .as_ref()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, values)| values.first())
+        .map(String::as_str)
+}
+
+// DTSTART / DTEND as local time. Handles UTC ("...Z"), TZID and floating
+// times; all-day dates are refused since the schedule needs set times.
+fn parse_time(property: &Property) -> Result<NaiveDateTime, String> {
+    let value = property.value.as_deref().unwrap_or_default().trim();
+    if param(property, "VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE"))
+        || NaiveDate::parse_from_str(value, "%Y%m%d").is_ok()
+    {
+        return Err("All-day events can't be imported".to_string());
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, ICS_DATE_TIME).map_err(|_| unreadable(value))?;
+        return Ok(Utc.from_utc_datetime(&naive).with_timezone(&Local).naive_local());
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, ICS_DATE_TIME).map_err(|_| unreadable(value))?;
+    // Unknown zone names (e.g. Windows ones) are treated as local time
+    match param(property, "TZID").and_then(|tz| tz.parse::<chrono_tz::Tz>().ok()) {
+        Some(tz) => tz
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.with_timezone(&Local).naive_local())
+            .ok_or_else(|| unreadable(value)),
+        None => Ok(naive),
+    }
+}
+
+fn unreadable(value: &str) -> String {
+    format!("Unreadable time '{}'", value)
+}
+
+// RFC 5545 durations such as "PT1H30M" or "P1DT2H"
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    let mut any = false;
+
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' if number.is_empty() => in_time = true,
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                any = true;
+                total += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if !any || !number.is_empty() {
+        return None;
+    }
+    Some(if negative { -total } else { total })
+}
+
+// TEXT values escape backslashes, commas, semicolons and newlines
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+// The first value of a comma-separated list such as CATEGORIES, still
+// escaped; escaped commas ("\,") belong to the value
+fn first_list_value(value: &str) -> &str {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            ',' if !escaped => return &value[..i],
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    value
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// The schedule as an iCalendar document. `source_uids` maps event ids to
+// the UID they were imported with, so a round trip keeps the promoter's UIDs.
+pub fn export_events(
+    schedule: &[ScheduleItem],
+    source_uids: &HashMap<String, String>,
+    now: DateTime<Utc>,
+) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//VJ.Tools//VJ Event Sync//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for item in schedule {
+        let uid = source_uids
+            .get(&item.id)
+            .cloned()
+            .unwrap_or_else(|| format!("{}{}", item.id, OWN_UID_SUFFIX));
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", uid));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", item.starts_at.format(ICS_DATE_TIME)));
+        if let Some(end) = item.ends_at {
+            lines.push(format!("DTEND:{}", end.format(ICS_DATE_TIME)));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&item.name)));
+        lines.push(format!("CATEGORIES:{}", escape(&item.event_type)));
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in lines {
+        fold_line(&line, &mut out);
+    }
+    out
+}
+
+// The event id behind a UID `export_events` made up, so importing our own
+// export finds the events it came from
+pub fn own_event_id(uid: &str) -> Option<&str> {
+    uid.strip_suffix(OWN_UID_SUFFIX).filter(|id| !id.is_empty())
+}
+
+// Writes `line` with CRLF endings, continuing long lines on the next line
+// after a single space. Never splits inside a UTF-8 character.
+fn fold_line(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_BYTES {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap().and_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn parse(ics: &str) -> (Vec<SourceEvent>, Vec<SkippedEntry>) {
+        parse_events(ics.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn reads_floating_and_duration_events() {
+        let (events, skipped) = parse(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+             BEGIN:VEVENT\r\nUID:set-1\r\nDTSTART:20261017T233000\r\nDTEND:20261018T010000\r\n\
+             SUMMARY:KROMA b2b Nyx\\, live\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nDTSTART:20261018T010000\r\nDURATION:PT1H30M\r\nSUMMARY:Closing\r\n\
+             CATEGORIES:special,other\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nDTSTART:20261018T023000\r\nSUMMARY:Raffle\r\n\
+             CATEGORIES:talk\\, q&a\\\\,other\r\nEND:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        );
+        assert!(skipped.is_empty());
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].uid.as_deref(), Some("set-1"));
+        assert_eq!(events[0].name, "KROMA b2b Nyx, live");
+        assert_eq!(events[0].starts_at, at(17, 23, 30));
+        assert_eq!(events[0].ends_at, Some(at(18, 1, 0)));
+        assert_eq!(events[0].event_type, None);
+        assert_eq!(events[1].ends_at, Some(at(18, 2, 30)));
+        assert_eq!(events[1].event_type.as_deref(), Some("special"));
+        assert_eq!(events[2].event_type.as_deref(), Some("talk, q&a\\"));
+    }
+
+    #[test]
+    fn converts_utc_and_tzid_times_to_local() {
+        let (events, _) = parse(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\nDTSTART:20261017T210000Z\r\nSUMMARY:UTC\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nDTSTART;TZID=Europe/Berlin:20261017T230000\r\nSUMMARY:Berlin\r\nEND:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        );
+        let utc = Utc.from_utc_datetime(&at(17, 21, 0)).with_timezone(&Local).naive_local();
+        assert_eq!(events[0].starts_at, utc);
+        // 23:00 in Berlin (UTC+2 in October) is 21:00 UTC
+        assert_eq!(events[1].starts_at, utc);
+    }
+
+    #[test]
+    fn unusable_events_are_skipped_with_a_reason() {
+        let (events, skipped) = parse(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20261017\r\nSUMMARY:Festival day\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nSUMMARY:No start\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nDTSTART:20261017T220000\r\nSTATUS:CANCELLED\r\nSUMMARY:Gone\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nDTSTART:20261017T220000\r\nSUMMARY:Kept\r\nEND:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "Kept");
+        assert_eq!(skipped.len(), 3);
+        assert_eq!(skipped[0].location, "VEVENT 1 'Festival day'");
+        assert_eq!(skipped[0].reason, "All-day events can't be imported");
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("P1DT2H"), Some(Duration::hours(26)));
+        assert_eq!(parse_duration("P1W"), Some(Duration::weeks(1)));
+        assert_eq!(parse_duration("PT"), None);
+        assert_eq!(parse_duration("1H"), None);
+        assert_eq!(parse_duration("P1H"), None);
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let schedule = vec![
+            ScheduleItem {
+                id: "e1".to_string(),
+                starts_at: at(17, 23, 30),
+                ends_at: Some(at(18, 1, 0)),
+                name: "KROMA; live, with guests".to_string(),
+                event_type: "dj_set".to_string(),
+                linked_logo_id: None,
+                priority: 0,
+            },
+            ScheduleItem {
+                id: "e2".to_string(),
+                starts_at: at(18, 1, 0),
+                ends_at: None,
+                name: "Closing".repeat(20),
+                event_type: "special".to_string(),
+                linked_logo_id: None,
+                priority: 0,
+            },
+        ];
+        let uids = HashMap::from([("e1".to_string(), "promoter-uid".to_string())]);
+        let ics = export_events(&schedule, &uids, Utc::now());
+        assert!(ics.lines().all(|line| line.len() <= MAX_LINE_BYTES + 1));
+
+        let (events, skipped) = parse(&ics);
+        assert!(skipped.is_empty());
+        assert_eq!(events[0].uid.as_deref(), Some("promoter-uid"));
+        assert_eq!(events[0].name, schedule[0].name);
+        assert_eq!(events[0].ends_at, schedule[0].ends_at);
+        assert_eq!(events[0].event_type.as_deref(), Some("dj_set"));
+        assert_eq!(events[1].uid.as_deref(), Some("e2@vj-event-sync"));
+        assert_eq!(events[1].name, schedule[1].name);
+        assert_eq!(events[1].ends_at, None);
+    }
+}
@@ -0,0 +1,355 @@
+//! Bringing events from outside (calendar files, spreadsheets) into the
+//! schedule.
+//!
+//! Imports run in two steps. `preview` works out what each incoming event
+//! would do - add, update or nothing - and which artist and logo it belongs
+//! to, without writing anything. `apply` then writes the previewed events
+//! (possibly filtered by the user) in one transaction.
+
+use crate::artist_match::{match_artist, match_key};
+use crate::error::AppResult;
+use crate::ics;
+use crate::models::{
+    ImportAction, ImportPreview, ImportSummary, ImportedEvent, ScheduleConflict, ScheduleEventInput, ScheduleItem,
+    SkippedEntry,
+};
+use crate::repo::{ArtistRepo, LogoRepo, ScheduleRepo};
+use crate::schedule::find_conflicts;
+use chrono::NaiveDateTime;
+use rusqlite::Connection;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+// Event type given to imported events when the source doesn't say
+pub const DEFAULT_IMPORT_EVENT_TYPE: &str = "dj_set";
+
+// An event as read from the source, before it's matched against the database
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceEvent {
+    pub uid: Option<String>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: Option<NaiveDateTime>,
+    pub name: String,
+    pub event_type: Option<String>,
+    // Artist named by the source itself (e.g. a timetable's artist column).
+    // When unset the artist is looked for in `name`.
+    pub artist: Option<String>,
+}
+
+// Matches `events` against the current schedule and artists. An event
+// replaces the one imported earlier with the same UID, or the one it was
+// exported from; without a UID, an event with the same start and name counts
+// as the same event.
+pub fn preview(conn: &Connection, events: Vec<SourceEvent>, skipped: Vec<SkippedEntry>) -> AppResult<ImportPreview> {
+    let schedule_repo = ScheduleRepo::new(conn);
+    let schedule = schedule_repo.list()?;
+    let ids_by_uid = schedule_repo.ids_by_source_uid()?;
+    let artists = ArtistRepo::new(conn).list()?;
+    let logos = LogoRepo::new(conn);
+
+    let mut previewed = Vec::with_capacity(events.len());
+    for event in events {
+        let existing = match &event.uid {
+            Some(uid) => ids_by_uid
+                .get(uid)
+                .map(String::as_str)
+                .or_else(|| ics::own_event_id(uid))
+                .and_then(|id| schedule.iter().find(|item| item.id == id)),
+            None => schedule
+                .iter()
+                .find(|item| item.starts_at == event.starts_at && item.name.eq_ignore_ascii_case(&event.name)),
+        };
+
+        let matched = match_artist(&artists, event.artist.as_deref().unwrap_or(&event.name));
+        let artist = matched.map(|(artist, _)| artist);
+        // An artist the source names explicitly but we don't know yet
+        let new_artist = match (artist, &event.artist) {
+            (None, Some(name)) if !name.trim().is_empty() => Some(name.trim().to_string()),
+            _ => None,
+        };
+        // The artist's preferred logo, else the first one linked to it
+        let matched_logo = match artist {
+            Some(artist) => match &artist.profile.preferred_logo_id {
+                Some(logo_id) => Some(logo_id.clone()),
+                None => logos.list_for_artist(&artist.id)?.into_iter().next().map(|logo| logo.id),
+            },
+            None => None,
+        };
+
+        let source_type = event.event_type.clone();
+        let mut imported = ImportedEvent {
+            source_uid: event.uid,
+            action: ImportAction::Add,
+            existing_id: None,
+            starts_at: event.starts_at,
+            ends_at: event.ends_at,
+            name: event.name,
+            event_type: event.event_type.unwrap_or_else(|| DEFAULT_IMPORT_EVENT_TYPE.to_string()),
+            artist_id: artist.map(|a| a.id.clone()),
+            artist_name: artist.map(|a| a.name.clone()).or(new_artist.clone()),
+            artist_match: matched.map(|(_, kind)| kind),
+            create_artist: new_artist.is_some(),
+            linked_logo_id: matched_logo,
+            priority: 0,
+        };
+
+        // Keep what was set by hand on the existing event
+        if let Some(existing) = existing {
+            imported.existing_id = Some(existing.id.clone());
+            imported.priority = existing.priority;
+            if source_type.is_none() {
+                imported.event_type = existing.event_type.clone();
+            }
+            if imported.linked_logo_id.is_none() {
+                imported.linked_logo_id = existing.linked_logo_id.clone();
+            }
+            imported.action = if is_unchanged(existing, &imported) {
+                ImportAction::Unchanged
+            } else {
+                ImportAction::Update
+            };
+        }
+        previewed.push(imported);
+    }
+
+    let conflicts = import_conflicts(&schedule, &previewed);
+    Ok(ImportPreview {
+        events: previewed,
+        skipped,
+        conflicts,
+    })
+}
+
+// Writes previewed events, adding any new artists they name. Runs in a
+// transaction, so if any event is rejected (e.g. by strict conflict mode)
+// nothing is imported.
+pub fn apply(conn: &Connection, events: &[ImportedEvent]) -> AppResult<ImportSummary> {
+    let tx = conn.unchecked_transaction()?;
+    let repo = ScheduleRepo::new(conn);
+    let artists = ArtistRepo::new(conn);
+    let mut summary = ImportSummary::default();
+    // Match key -> id, so an artist playing twice is only added once, even
+    // when spelled differently
+    let mut created: HashMap<String, String> = HashMap::new();
+
+    for event in events {
+        if let (true, None, Some(name)) = (event.create_artist, &event.artist_id, &event.artist_name) {
+            if let Entry::Vacant(entry) = created.entry(match_key(name)) {
+                let id = artists.add(name).map_err(|e| e.context(&format!("Failed to add artist '{}'", name)))?;
+                entry.insert(id);
+                summary.artists_added += 1;
+            }
+        }
+
+        let input = to_input(event);
+        let context = format!("Failed to import '{}'", event.name);
+
+        match (event.action, &event.existing_id) {
+            (ImportAction::Unchanged, _) => summary.unchanged += 1,
+            (ImportAction::Update, Some(id)) => {
+                repo.update(id, &input).map_err(|e| e.context(&context))?;
+                summary.updated += 1;
+            }
+            _ => {
+                let id = repo.add(&input).map_err(|e| e.context(&context))?;
+                repo.set_source_uid(&id, event.source_uid.as_deref())?;
+                summary.added += 1;
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(summary)
+}
+
+fn to_input(event: &ImportedEvent) -> ScheduleEventInput {
+    ScheduleEventInput {
+        starts_at: event.starts_at,
+        ends_at: event.ends_at,
+        name: event.name.clone(),
+        event_type: event.event_type.clone(),
+        linked_logo_id: event.linked_logo_id.clone(),
+        priority: event.priority,
+    }
+}
+
+fn is_unchanged(existing: &ScheduleItem, imported: &ImportedEvent) -> bool {
+    existing.starts_at == imported.starts_at
+        && existing.ends_at == imported.ends_at
+        && existing.name == imported.name
+        && existing.event_type == imported.event_type
+        && existing.linked_logo_id == imported.linked_logo_id
+}
+
+// Overlaps between the incoming events and the schedule as it would be
+// after the import
+fn import_conflicts(schedule: &[ScheduleItem], events: &[ImportedEvent]) -> Vec<ScheduleConflict> {
+    let mut merged: Vec<ScheduleItem> = schedule
+        .iter()
+        .filter(|item| !events.iter().any(|e| e.existing_id.as_deref() == Some(item.id.as_str())))
+        .cloned()
+        .collect();
+    let mut incoming_ids = Vec::with_capacity(events.len());
+    for (index, event) in events.iter().enumerate() {
+        let id = event.existing_id.clone().unwrap_or_else(|| format!("import:{}", index));
+        merged.push(to_input(event).to_item(&id));
+        incoming_ids.push(id);
+    }
+
+    find_conflicts(&merged)
+        .into_iter()
+        .filter(|c| incoming_ids.contains(&c.first_id) || incoming_ids.contains(&c.second_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::models::ArtistMatchKind;
+    use chrono::{Duration, NaiveDate};
+
+    fn at(h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn source(uid: Option<&str>, start: NaiveDateTime, minutes: i64, name: &str) -> SourceEvent {
+        SourceEvent {
+            uid: uid.map(str::to_string),
+            starts_at: start,
+            ends_at: Some(start + Duration::minutes(minutes)),
+            name: name.to_string(),
+            event_type: None,
+            artist: None,
+        }
+    }
+
+    #[test]
+    fn spellings_of_a_known_artist_match_it() {
+        let conn = open_in_memory().unwrap();
+        let artists = ArtistRepo::new(&conn);
+        let kroma = artists.add("KROMA").unwrap();
+        artists.add_alias(&kroma, "Chromatic").unwrap();
+
+        let events = vec![
+            source(None, at(20, 0), 60, "DJ Kroma (live)"),
+            source(None, at(21, 0), 60, "Chromatic"),
+            source(None, at(22, 0), 60, "Kromma"),
+        ];
+        let preview = preview(&conn, events, vec![]).unwrap();
+        let matched: Vec<_> = preview.events.iter().map(|e| (e.artist_id.as_deref(), e.artist_match)).collect();
+        assert_eq!(
+            matched,
+            [
+                (Some(kroma.as_str()), Some(ArtistMatchKind::Exact)),
+                (Some(kroma.as_str()), Some(ArtistMatchKind::Alias)),
+                (Some(kroma.as_str()), Some(ArtistMatchKind::Fuzzy)),
+            ]
+        );
+    }
+
+    #[test]
+    fn preview_links_logo_and_apply_writes_events() {
+        let conn = open_in_memory().unwrap();
+        let artist = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        let logo = LogoRepo::new(&conn).add("Kroma", "/logos/kroma.png", None).unwrap();
+        LogoRepo::new(&conn).link_artist(&artist, &logo, false).unwrap();
+
+        let preview = preview(
+            &conn,
+            vec![source(Some("uid-1"), at(23, 0), 60, "KROMA (live)"), source(None, at(22, 0), 60, "Warmup")],
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(preview.events[0].action, ImportAction::Add);
+        assert_eq!(preview.events[0].artist_id.as_deref(), Some(artist.as_str()));
+        assert_eq!(preview.events[0].linked_logo_id.as_deref(), Some(logo.as_str()));
+        assert_eq!(preview.events[1].artist_id, None);
+        // Nothing written yet
+        assert!(ScheduleRepo::new(&conn).list().unwrap().is_empty());
+
+        let summary = apply(&conn, &preview.events).unwrap();
+        assert_eq!(summary, ImportSummary { added: 2, ..Default::default() });
+        let schedule = ScheduleRepo::new(&conn).list().unwrap();
+        assert_eq!(schedule[1].name, "KROMA (live)");
+        assert_eq!(schedule[1].linked_logo_id.as_deref(), Some(logo.as_str()));
+    }
+
+    #[test]
+    fn reimport_updates_by_uid_and_skips_unchanged() {
+        let conn = open_in_memory().unwrap();
+        let first = vec![source(Some("uid-1"), at(23, 0), 60, "KROMA"), source(None, at(22, 0), 60, "Warmup")];
+        apply(&conn, &preview(&conn, first, vec![]).unwrap().events).unwrap();
+
+        // The promoter moved KROMA by half an hour
+        let second = vec![source(Some("uid-1"), at(23, 30), 60, "KROMA"), source(None, at(22, 0), 60, "Warmup")];
+        let preview = preview(&conn, second, vec![]).unwrap();
+        assert_eq!(preview.events[0].action, ImportAction::Update);
+        assert_eq!(preview.events[1].action, ImportAction::Unchanged);
+
+        let summary = apply(&conn, &preview.events).unwrap();
+        assert_eq!(summary, ImportSummary { updated: 1, unchanged: 1, ..Default::default() });
+        let schedule = ScheduleRepo::new(&conn).list().unwrap();
+        assert_eq!(schedule.len(), 2);
+        assert_eq!(schedule[1].starts_at, at(23, 30));
+    }
+
+    #[test]
+    fn reimporting_our_own_export_changes_nothing() {
+        let conn = open_in_memory().unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        repo.add(&ScheduleEventInput {
+            ends_at: Some(at(23, 59)),
+            ..ScheduleEventInput::new(at(23, 0), "Headliner", "special")
+        })
+        .unwrap();
+        apply(&conn, &preview(&conn, vec![source(Some("uid-1"), at(22, 0), 60, "Warmup")], vec![]).unwrap().events)
+            .unwrap();
+
+        let source_uids = repo.ids_by_source_uid().unwrap().into_iter().map(|(uid, id)| (id, uid)).collect();
+        let exported = ics::export_events(&repo.list().unwrap(), &source_uids, chrono::Utc::now());
+        let (events, _) = ics::parse_events(exported.as_bytes()).unwrap();
+        let preview = preview(&conn, events, vec![]).unwrap();
+        assert!(preview.events.iter().all(|e| e.action == ImportAction::Unchanged));
+
+        apply(&conn, &preview.events).unwrap();
+        assert_eq!(repo.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn preview_reports_conflicts_with_the_schedule() {
+        let conn = open_in_memory().unwrap();
+        ScheduleRepo::new(&conn)
+            .add(&ScheduleEventInput {
+                ends_at: Some(at(23, 59)),
+                ..ScheduleEventInput::new(at(23, 0), "Headliner", "dj_set")
+            })
+            .unwrap();
+
+        let preview = preview(&conn, vec![source(None, at(23, 30), 15, "Sponsor")], vec![]).unwrap();
+        assert_eq!(preview.conflicts.len(), 1);
+        assert_eq!(preview.conflicts[0].second_id, "import:0");
+    }
+
+    #[test]
+    fn named_artists_that_are_unknown_get_created_once() {
+        let conn = open_in_memory().unwrap();
+        ArtistRepo::new(&conn).add("KROMA").unwrap();
+        let named = |start, artist: &str| SourceEvent {
+            artist: Some(artist.to_string()),
+            ..source(None, start, 60, artist)
+        };
+
+        let preview = preview(&conn, vec![named(at(20, 0), "Nyx"), named(at(22, 0), "kroma"), named(at(23, 0), "Nyx")], vec![])
+            .unwrap();
+        assert!(preview.events[0].create_artist);
+        assert_eq!(preview.events[0].artist_name.as_deref(), Some("Nyx"));
+        assert!(!preview.events[1].create_artist);
+        assert_eq!(preview.events[1].artist_name.as_deref(), Some("KROMA"));
+
+        let summary = apply(&conn, &preview.events).unwrap();
+        assert_eq!(summary.artists_added, 1);
+        let names: Vec<String> = ArtistRepo::new(&conn).list().unwrap().into_iter().map(|a| a.name).collect();
+        assert_eq!(names, ["KROMA", "Nyx"]);
+    }
+}
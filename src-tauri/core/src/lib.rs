@@ -0,0 +1,33 @@
+//! Database and domain logic for the VJ.Tools backend.
+//!
+//! Nothing in here depends on Tauri, so it can be unit-tested against an
+//! in-memory SQLite database and reused from other front ends (e.g. a CLI).
+//! The Tauri crate only wraps these calls in `#[tauri::command]` functions.
+
+pub mod artist_match;
+pub mod db;
+pub mod duplicates;
+pub mod error;
+pub mod ics;
+pub mod import;
+pub mod library;
+pub mod media;
+pub mod migrations;
+pub mod models;
+pub mod playback;
+pub mod relink;
+pub mod repo;
+pub mod schedule;
+pub mod search;
+pub mod thumbnail;
+pub mod timetable;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Simple function to get current timestamp as u64
+pub fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
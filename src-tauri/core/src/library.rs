@@ -0,0 +1,431 @@
+//! The logo library folder: finding the image and video files in it and
+//! keeping the `logos` table in step with what is on disk.
+//!
+//! A scan adds a logo for every new file, flags logos whose file has gone
+//! missing, and recognises a file that was renamed or moved inside the
+//! library (same size and modification time) so its logo keeps its artist
+//! links, cycle entries and schedule events.
+
+use crate::error::{AppError, AppResult};
+use crate::models::{LibraryChanges, LibraryEntry, LibraryUpdate, Logo, MediaKind};
+use crate::repo::LogoRepo;
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "apng", "jpg", "jpeg", "gif", "webp", "svg", "bmp"];
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "webm", "mkv", "avi"];
+
+// Image or video, going by the file extension
+pub fn media_kind(path: &Path) -> Option<MediaKind> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        Some(MediaKind::Image)
+    } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+        Some(MediaKind::Video)
+    } else {
+        None
+    }
+}
+
+// A media file found in the library folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryFile {
+    pub path: PathBuf,
+    pub size: u64,
+    // Unix seconds; None if the filesystem doesn't record it
+    pub modified: Option<u64>,
+}
+
+impl LibraryFile {
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            modified,
+        })
+    }
+
+    fn path_str(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn matches(&self, logo: &Logo) -> bool {
+        logo.file_size == Some(self.size) && logo.file_modified == self.modified
+    }
+}
+
+// Every image and video under `root`, sorted by path. Hidden files and
+// folders are skipped, and symlinked folders aren't followed.
+pub fn scan_dir(root: &Path) -> AppResult<Vec<LibraryFile>> {
+    if !root.is_dir() {
+        return Err(AppError::invalid(
+            "path",
+            format!("Logo library folder '{}' doesn't exist", root.display()),
+        ));
+    }
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // The root has to be readable; an unreadable subfolder is skipped
+            Err(e) if dir == root => return Err(AppError::io("Failed to read logo library folder", e)),
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else { continue };
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if media_kind(&path).is_some() && path.is_file() {
+                // Files may vanish mid-scan; they'll be picked up next time
+                if let Ok(file) = LibraryFile::read(&path) {
+                    files.push(file);
+                }
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+// Brings the logos under `root` in line with the files there. Logos outside
+// the library folder are left alone. Runs in one transaction.
+pub fn sync(conn: &Connection, root: &Path) -> AppResult<LibraryChanges> {
+    let files = scan_dir(root)?;
+    reconcile(conn, root, files, &HashSet::new())
+}
+
+// Files that appeared or changed between two scans, i.e. ones that may
+// still be being copied in
+pub fn unsettled(before: &[LibraryFile], after: &[LibraryFile]) -> HashSet<PathBuf> {
+    after
+        .iter()
+        .filter(|file| !before.contains(file))
+        .map(|file| file.path.clone())
+        .collect()
+}
+
+// The sync step on an already scanned file list. Paths in `pending` exist
+// but aren't finished yet: they are neither added nor treated as missing,
+// and a later pass picks them up.
+pub fn reconcile(conn: &Connection, root: &Path, files: Vec<LibraryFile>, pending: &HashSet<PathBuf>) -> AppResult<LibraryChanges> {
+    let tx = conn.unchecked_transaction()?;
+    let repo = LogoRepo::new(conn);
+    let mut changes = LibraryChanges::default();
+
+    let logos: Vec<Logo> = repo
+        .list()?
+        .into_iter()
+        .filter(|logo| Path::new(&logo.file_path).starts_with(root))
+        .collect();
+    let mut new_files: HashMap<String, LibraryFile> = files
+        .into_iter()
+        .filter(|f| !pending.contains(&f.path))
+        .map(|f| (f.path_str(), f))
+        .collect();
+
+    let mut gone = Vec::new();
+    for logo in &logos {
+        if pending.contains(Path::new(&logo.file_path)) {
+            continue;
+        }
+        match new_files.remove(&logo.file_path) {
+            Some(file) => {
+                if logo.missing || !file.matches(logo) {
+                    repo.set_file_state(&logo.id, Some(file.size), file.modified, false)?;
+                    // First scan of a logo only records its file
+                    if logo.missing || logo.file_size.is_some() {
+                        changes.modified.push(logo.id.clone());
+                    }
+                }
+            }
+            None => gone.push(logo),
+        }
+    }
+
+    for logo in gone {
+        // Only a single unclaimed file with the same size and mtime counts
+        // as the same file under a new name
+        let mut candidates = new_files.values().filter(|f| f.matches(logo) && f.modified.is_some());
+        let renamed_to = match (candidates.next(), candidates.next()) {
+            (Some(file), None) => Some(file.path_str()),
+            _ => None,
+        };
+
+        match renamed_to.and_then(|path| new_files.remove(&path)) {
+            Some(file) => {
+                let path = file.path_str();
+                // Keep a name the user chose; follow the file name otherwise
+                let name = if Some(logo.name.as_str()) == file_stem(Path::new(&logo.file_path)).as_deref() {
+                    file_stem(&file.path).unwrap_or_else(|| logo.name.clone())
+                } else {
+                    logo.name.clone()
+                };
+                repo.update(&logo.id, &name, &path, logo.thumbnail_path.as_deref())?;
+                repo.set_file_state(&logo.id, Some(file.size), file.modified, false)?;
+                changes.renamed.push(logo.id.clone());
+            }
+            None if !logo.missing => {
+                repo.set_file_state(&logo.id, logo.file_size, logo.file_modified, true)?;
+                changes.missing.push(logo.id.clone());
+            }
+            None => {}
+        }
+    }
+
+    let mut added: Vec<LibraryFile> = new_files.into_values().collect();
+    added.sort_by(|a, b| a.path.cmp(&b.path));
+    for file in added {
+        let name = file_stem(&file.path).unwrap_or_else(|| file.path_str());
+        let id = repo.add(&name, &file.path_str(), None)?;
+        repo.set_file_state(&id, Some(file.size), file.modified, false)?;
+        changes.added.push(id);
+    }
+
+    tx.commit()?;
+    Ok(changes)
+}
+
+// Every logo, in and outside the library folder, as the gallery shows it
+pub fn entries(conn: &Connection) -> AppResult<Vec<LibraryEntry>> {
+    let logos = LogoRepo::new(conn).list()?;
+    Ok(logos.into_iter().map(to_entry).collect())
+}
+
+// Resolves the ids of a scan into entries for the `library-changed` event
+pub fn describe(conn: &Connection, changes: &LibraryChanges) -> AppResult<LibraryUpdate> {
+    let mut by_id: HashMap<String, LibraryEntry> = entries(conn)?.into_iter().map(|e| (e.id.clone(), e)).collect();
+    let mut take = |ids: &[String]| -> Vec<LibraryEntry> { ids.iter().filter_map(|id| by_id.remove(id)).collect() };
+
+    let added = take(&changes.added);
+    let mut modified = take(&changes.modified);
+    modified.extend(take(&changes.renamed));
+    let removed = take(&changes.missing);
+    Ok(LibraryUpdate { added, modified, removed })
+}
+
+pub fn to_entry(logo: Logo) -> LibraryEntry {
+    LibraryEntry {
+        kind: media_kind(Path::new(&logo.file_path)),
+        id: logo.id,
+        name: logo.name,
+        path: logo.file_path,
+        thumbnail_path: logo.thumbnail_path,
+        linked_djs: logo.linked_djs,
+        missing: logo.missing,
+        media: logo.media,
+    }
+}
+
+// The parts of a stored path, split on both '/' and '\\' so paths written
+// on Windows can be read anywhere
+pub fn path_components(path: &str) -> Vec<&str> {
+    path.split(['/', '\\']).filter(|part| !part.is_empty()).collect()
+}
+
+// Whether a stored logo path is relative to the library root. Absolute
+// paths from any platform ("/logos", "\\nas\logos", "D:\Logos") count as absolute.
+pub fn is_relative(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    let has_drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    !(path.starts_with('/') || path.starts_with('\\') || has_drive)
+}
+
+// How a logo path is written to the database: relative with '/' separators
+// if it lies under `root`, as given otherwise
+pub fn to_stored(path: &str, root: Option<&Path>) -> String {
+    if is_relative(path) {
+        return path_components(path).join("/");
+    }
+    root.and_then(|root| strip_root(path, &root.to_string_lossy()))
+        .map(|parts| parts.join("/"))
+        .unwrap_or_else(|| path.to_string())
+}
+
+// The absolute path of a stored logo path. Relative paths stay as they are
+// while no root is set.
+pub fn resolve(stored: &str, root: Option<&Path>) -> String {
+    match root {
+        Some(root) if is_relative(stored) => path_components(stored)
+            .iter()
+            .fold(root.to_path_buf(), |path, part| path.join(part))
+            .to_string_lossy()
+            .into_owned(),
+        _ => stored.to_string(),
+    }
+}
+
+// The components of `path` below `root`, or None if it isn't inside it
+pub fn strip_root<'a>(path: &'a str, root: &str) -> Option<Vec<&'a str>> {
+    let path = path_components(path);
+    let root = path_components(root);
+    if path.len() <= root.len() || path[..root.len()] != root[..] {
+        return None;
+    }
+    Some(path[root.len()..].to_vec())
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem().map(|s| s.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::repo::ArtistRepo;
+
+    fn write(path: &Path, bytes: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    fn entry<'a>(entries: &'a [LibraryEntry], id: &str) -> &'a LibraryEntry {
+        entries.iter().find(|e| e.id == id).unwrap()
+    }
+
+    #[test]
+    fn recognises_media_by_extension() {
+        assert_eq!(media_kind(Path::new("a/KROMA.PNG")), Some(MediaKind::Image));
+        assert_eq!(media_kind(Path::new("fireburst_intro.mov")), Some(MediaKind::Video));
+        assert_eq!(media_kind(Path::new("notes.txt")), None);
+        assert_eq!(media_kind(Path::new("README")), None);
+    }
+
+    #[test]
+    fn strips_roots_written_on_any_platform() {
+        assert_eq!(strip_root("/media/usb/logos/kroma/a.png", "/media/usb/logos/"), Some(vec!["kroma", "a.png"]));
+        assert_eq!(strip_root("D:\\Logos\\kroma\\a.png", "D:\\Logos"), Some(vec!["kroma", "a.png"]));
+        assert_eq!(strip_root("/media/usb/logos2/a.png", "/media/usb/logos"), None);
+        assert_eq!(strip_root("/media/usb/logos", "/media/usb/logos"), None);
+    }
+
+    #[test]
+    fn stores_paths_under_the_root_relative() {
+        let root = Path::new("/media/usb/logos");
+        assert_eq!(to_stored("/media/usb/logos/kroma/a.png", Some(root)), "kroma/a.png");
+        assert_eq!(to_stored("/elsewhere/a.png", Some(root)), "/elsewhere/a.png");
+        assert_eq!(to_stored("kroma\\a.png", None), "kroma/a.png");
+        assert_eq!(to_stored("/media/usb/logos/a.png", None), "/media/usb/logos/a.png");
+
+        assert!(!is_relative("C:\\Logos\\a.png") && !is_relative("\\\\nas\\logos") && !is_relative("/a.png"));
+        assert_eq!(resolve("kroma/a.png", Some(root)), root.join("kroma").join("a.png").to_string_lossy());
+        assert_eq!(resolve("kroma/a.png", None), "kroma/a.png");
+        assert_eq!(resolve("/elsewhere/a.png", Some(root)), "/elsewhere/a.png");
+    }
+
+    #[test]
+    fn scan_skips_hidden_and_unsupported_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("kroma.png"), b"png");
+        write(&dir.path().join("sets/nyx/loop.mp4"), b"mp4");
+        write(&dir.path().join("notes.txt"), b"txt");
+        write(&dir.path().join(".hidden.png"), b"png");
+        write(&dir.path().join(".trash/old.png"), b"png");
+
+        let files = scan_dir(dir.path()).unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.path.strip_prefix(dir.path()).unwrap().to_path_buf()).collect();
+        assert_eq!(names, [PathBuf::from("kroma.png"), PathBuf::from("sets/nyx/loop.mp4")]);
+        assert!(scan_dir(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn sync_adds_flags_missing_and_restores() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("kroma.png"), b"png");
+        write(&dir.path().join("nyx.webm"), b"webm");
+
+        let changes = sync(&conn, dir.path()).unwrap();
+        assert_eq!(changes.added.len(), 2);
+        assert!(sync(&conn, dir.path()).unwrap().is_empty());
+
+        let entries = entries(&conn).unwrap();
+        let kroma = entries.iter().find(|e| e.name == "kroma").unwrap().clone();
+        assert_eq!(kroma.kind, Some(MediaKind::Image));
+        assert_eq!(entries.iter().find(|e| e.name == "nyx").unwrap().kind, Some(MediaKind::Video));
+
+        fs::remove_file(&kroma.path).unwrap();
+        assert_eq!(sync(&conn, dir.path()).unwrap().missing, std::slice::from_ref(&kroma.id));
+        assert!(entry(&self::entries(&conn).unwrap(), &kroma.id).missing);
+        // Reported once, not on every scan
+        assert!(sync(&conn, dir.path()).unwrap().is_empty());
+
+        write(Path::new(&kroma.path), b"png again");
+        assert_eq!(sync(&conn, dir.path()).unwrap().modified, std::slice::from_ref(&kroma.id));
+        assert!(!entry(&self::entries(&conn).unwrap(), &kroma.id).missing);
+    }
+
+    #[test]
+    fn renamed_files_keep_their_logo_and_links() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("kroma.png"), b"png");
+        let id = sync(&conn, dir.path()).unwrap().added.remove(0);
+        let artist = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        LogoRepo::new(&conn).link_artist(&artist, &id, false).unwrap();
+
+        let moved = dir.path().join("kroma/kroma_2026.png");
+        fs::create_dir_all(moved.parent().unwrap()).unwrap();
+        fs::rename(dir.path().join("kroma.png"), &moved).unwrap();
+
+        let changes = sync(&conn, dir.path()).unwrap();
+        assert_eq!(changes.renamed, std::slice::from_ref(&id));
+        assert!(changes.added.is_empty() && changes.missing.is_empty());
+
+        let entries = entries(&conn).unwrap();
+        let logo = entry(&entries, &id);
+        assert_eq!(logo.path, moved.to_string_lossy());
+        assert_eq!(logo.name, "kroma_2026");
+        assert_eq!(logo.linked_djs, ["KROMA"]);
+    }
+
+    #[test]
+    fn files_still_being_copied_wait_for_a_later_pass() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("kroma.png"), b"png");
+        let kroma = sync(&conn, dir.path()).unwrap().added.remove(0);
+
+        let before = scan_dir(dir.path()).unwrap();
+        write(&dir.path().join("nyx.mov"), b"mo");
+        write(&dir.path().join("kroma.png"), b"png, rewritten");
+        let after = scan_dir(dir.path()).unwrap();
+        let pending = unsettled(&before, &after);
+        assert_eq!(pending.len(), 2);
+
+        // Neither the half-copied file nor the one being overwritten changes
+        assert!(reconcile(&conn, dir.path(), after.clone(), &pending).unwrap().is_empty());
+
+        let changes = reconcile(&conn, dir.path(), after, &HashSet::new()).unwrap();
+        assert_eq!(changes.modified, std::slice::from_ref(&kroma));
+        let update = describe(&conn, &changes).unwrap();
+        assert_eq!(update.added.len(), 1);
+        assert_eq!(update.added[0].name, "nyx");
+        assert_eq!(update.modified[0].id, kroma);
+        assert!(update.removed.is_empty());
+    }
+
+    #[test]
+    fn logos_outside_the_library_are_left_alone() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let external = LogoRepo::new(&conn).add("external", "/elsewhere/external.png", None).unwrap();
+
+        sync(&conn, dir.path()).unwrap();
+        assert!(!entry(&entries(&conn).unwrap(), &external).missing);
+    }
+}
@@ -0,0 +1,497 @@
+//! Probing logo files for what matters before they go on screen: pixel
+//! size, transparency, and the frame count and duration of animations and
+//! videos.
+//!
+//! Raster images go through the image crate's decoders and SVGs through
+//! usvg. MP4 and QuickTime files are probed by walking their box structure
+//! for the video track's sample description and timing; no frames are
+//! decoded, so no codec libraries or GPU are needed.
+
+use crate::error::{AppError, AppResult};
+use crate::library::{media_kind, LibraryFile};
+use crate::models::{MediaInfo, MediaKind};
+use crate::repo::LogoRepo;
+use crate::thumbnail::{parse_svg, source_key, unreadable};
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, Frames, ImageDecoder, ImageReader};
+use rusqlite::Connection;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// Containers whose headers are parsed; other videos only get a format
+const MP4_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov"];
+
+// Largest movie header read into memory. Real ones stay well below a
+// megabyte even for long videos.
+const MAX_MOVIE_HEADER: u64 = 64 * 1024 * 1024;
+
+// A logo whose file needs (re)probing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeJob {
+    pub logo_id: String,
+    pub source: PathBuf,
+    pub key: String,
+}
+
+// Logos whose file changed since it was last probed, or was never probed.
+// Logos whose file is missing are skipped.
+pub fn pending(conn: &Connection) -> AppResult<Vec<ProbeJob>> {
+    let logos = LogoRepo::new(conn).list()?;
+    let jobs = logos
+        .into_iter()
+        .filter(|logo| !logo.missing)
+        .filter_map(|logo| {
+            let source = PathBuf::from(&logo.file_path);
+            let key = source_key(&LibraryFile::read(&source).ok()?);
+            (logo.probe_key.as_deref() != Some(key.as_str())).then_some(ProbeJob {
+                logo_id: logo.id,
+                source,
+                key,
+            })
+        })
+        .collect();
+    Ok(jobs)
+}
+
+// Records the result of probing `job`. A file that couldn't be probed is
+// stored as None, so it isn't retried until it changes.
+pub fn store(conn: &Connection, job: &ProbeJob, media: Option<&MediaInfo>) -> AppResult<()> {
+    LogoRepo::new(conn).set_media(&job.logo_id, media, &job.key)
+}
+
+pub fn probe(path: &Path) -> AppResult<MediaInfo> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    match media_kind(path) {
+        Some(MediaKind::Video) if MP4_EXTENSIONS.contains(&extension.as_str()) => probe_mp4(path),
+        // WebM, MKV, AVI: only the container is known
+        Some(MediaKind::Video) => Ok(MediaInfo {
+            format: extension,
+            ..MediaInfo::default()
+        }),
+        _ if extension == "svg" => probe_svg(path),
+        _ => probe_image(path),
+    }
+}
+
+pub fn aspect_ratio(width: Option<u32>, height: Option<u32>) -> Option<f64> {
+    match (width?, height?) {
+        (_, 0) => None,
+        (width, height) => Some(width as f64 / height as f64),
+    }
+}
+
+fn probe_image(path: &Path) -> AppResult<MediaInfo> {
+    let read_error = |e| AppError::io(&format!("Failed to read {}", path.display()), e);
+    let reader = ImageReader::open(path).and_then(|r| r.with_guessed_format()).map_err(read_error)?;
+    let format = reader.format().ok_or_else(|| unreadable(path, "unknown image format"))?;
+    let open = || File::open(path).map(BufReader::new).map_err(read_error);
+    let bad = |e: image::ImageError| unreadable(path, e);
+
+    match format {
+        image::ImageFormat::Gif => {
+            let decoder = GifDecoder::new(open()?).map_err(bad)?;
+            let mut info = still(&decoder, "gif");
+            // GIFs always decode to RGBA; only the pixels tell whether
+            // they use transparency
+            animate(&mut info, decoder.into_frames(), true, path)?;
+            Ok(info)
+        }
+        image::ImageFormat::Png => {
+            let decoder = PngDecoder::new(open()?).map_err(bad)?;
+            if !decoder.is_apng().map_err(bad)? {
+                return Ok(still(&decoder, "png"));
+            }
+            let mut info = still(&decoder, "apng");
+            animate(&mut info, decoder.apng().map_err(bad)?.into_frames(), false, path)?;
+            Ok(info)
+        }
+        image::ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(open()?).map_err(bad)?;
+            let mut info = still(&decoder, "webp");
+            if decoder.has_animation() {
+                animate(&mut info, decoder.into_frames(), false, path)?;
+            }
+            Ok(info)
+        }
+        _ => {
+            let name = format.extensions_str().first().copied().unwrap_or("image");
+            Ok(still(&reader.into_decoder().map_err(bad)?, name))
+        }
+    }
+}
+
+fn still(decoder: &impl ImageDecoder, format: &str) -> MediaInfo {
+    let (width, height) = decoder.dimensions();
+    MediaInfo {
+        format: format.to_string(),
+        width: Some(width),
+        height: Some(height),
+        aspect_ratio: aspect_ratio(Some(width), Some(height)),
+        has_alpha: Some(decoder.color_type().has_alpha()),
+        ..MediaInfo::default()
+    }
+}
+
+// Counts the frames and adds up their delays; a single frame isn't an
+// animation. With `check_alpha` the pixels decide `has_alpha`.
+fn animate(info: &mut MediaInfo, frames: Frames, check_alpha: bool, path: &Path) -> AppResult<()> {
+    let (mut count, mut duration_ms, mut transparent) = (0u32, 0f64, false);
+    for frame in frames {
+        let frame = frame.map_err(|e| unreadable(path, e))?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        duration_ms += numer as f64 / denom.max(1) as f64;
+        transparent = transparent || (check_alpha && frame.buffer().pixels().any(|p| p.0[3] < 255));
+        count += 1;
+    }
+    if count > 1 {
+        info.frame_count = Some(count);
+        info.duration_ms = Some(duration_ms.round() as u64);
+    }
+    if check_alpha {
+        info.has_alpha = Some(transparent);
+    }
+    Ok(())
+}
+
+fn probe_svg(path: &Path) -> AppResult<MediaInfo> {
+    let tree = parse_svg(path)?;
+    let width = Some(tree.size().width().round() as u32);
+    let height = Some(tree.size().height().round() as u32);
+    Ok(MediaInfo {
+        format: "svg".to_string(),
+        width,
+        height,
+        aspect_ratio: aspect_ratio(width, height),
+        // Everything outside the drawn shapes is transparent
+        has_alpha: Some(true),
+        ..MediaInfo::default()
+    })
+}
+
+fn probe_mp4(path: &Path) -> AppResult<MediaInfo> {
+    let (brand, movie) = read_movie_header(path)?;
+    // QuickTime files from before the ftyp box existed have none
+    let format = match brand {
+        Some(brand) if &brand != b"qt  " => "mp4",
+        _ => "mov",
+    };
+    let mut info = MediaInfo {
+        format: format.to_string(),
+        ..MediaInfo::default()
+    };
+
+    let video = Boxes(&movie).filter(|(kind, _)| kind == b"trak").map(|(_, trak)| trak).find(|trak| {
+        let handler = find_box(trak, &[b"mdia", b"hdlr"]);
+        handler.and_then(|h| h.get(8..12)) == Some(b"vide")
+    });
+    // The video track's own timing, else the whole movie's
+    let usable = |(timescale, duration): &(u32, u64)| *timescale > 0 && *duration > 0;
+    let track_timing = video.and_then(|trak| find_box(trak, &[b"mdia", b"mdhd"])).and_then(timing).filter(usable);
+    let movie_timing = || find_box(&movie, &[b"mvhd"]).and_then(timing).filter(usable);
+    if let Some((timescale, duration)) = track_timing.or_else(movie_timing) {
+        info.duration_ms = Some(duration.saturating_mul(1000) / timescale as u64);
+    }
+    let Some(trak) = video else {
+        return Ok(info);
+    };
+
+    // Display size from the track header (16.16 fixed point), falling back
+    // to the coded size of the sample description
+    let header_size = find_box(trak, &[b"tkhd"]).and_then(|tkhd| {
+        let end = tkhd.len().checked_sub(8)?;
+        Some((be_u32(tkhd, end)? >> 16, be_u32(tkhd, end + 4)? >> 16))
+    });
+    let stbl = find_box(trak, &[b"mdia", b"minf", b"stbl"]);
+    let sample = stbl.and_then(|stbl| find_box(stbl, &[b"stsd"])).and_then(|stsd| Boxes(stsd.get(8..)?).next());
+    if let Some((fourcc, entry)) = sample {
+        info.codec = Some(codec_name(fourcc));
+        let depth = be_u16(entry, 74).unwrap_or(0);
+        info.has_alpha = Some(matches!(&fourcc, b"Hap5" | b"HapM" | b"HapA") || depth == 32);
+        if let (Some(width), Some(height)) = (be_u16(entry, 24), be_u16(entry, 26)) {
+            info.width = Some(width as u32);
+            info.height = Some(height as u32);
+        }
+    }
+    if let Some((width, height)) = header_size.filter(|(w, h)| *w > 0 && *h > 0) {
+        info.width = Some(width);
+        info.height = Some(height);
+    }
+    info.aspect_ratio = aspect_ratio(info.width, info.height);
+
+    // One sample per frame for video tracks
+    info.frame_count = stbl.and_then(|stbl| find_box(stbl, &[b"stts"])).and_then(|stts| {
+        let entries = be_u32(stts, 4)? as usize;
+        (0..entries).try_fold(0u32, |total, i| Some(total.saturating_add(be_u32(stts, 8 + i * 8)?)))
+    });
+    Ok(info)
+}
+
+// The major brand from `ftyp` and the body of the `moov` box. Only the top
+// level is walked; media data is skipped over, wherever it sits.
+fn read_movie_header(path: &Path) -> AppResult<(Option<[u8; 4]>, Vec<u8>)> {
+    let context = format!("Failed to read {}", path.display());
+    let io = |e| AppError::io(&context, e);
+    let malformed = |reason: &str| AppError::invalid("file_path", format!("Can't read video '{}': {}", path.display(), reason));
+    let mut file = File::open(path).map_err(io)?;
+    let length = file.metadata().map_err(io)?.len();
+
+    let (mut offset, mut brand) = (0u64, None);
+    while offset + 8 <= length {
+        file.seek(SeekFrom::Start(offset)).map_err(io)?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header).map_err(io)?;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (header_length, size) = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            1 => {
+                let mut large = [0u8; 8];
+                file.read_exact(&mut large).map_err(io)?;
+                (16, u64::from_be_bytes(large))
+            }
+            0 => (8, length - offset),
+            size => (8, size as u64),
+        };
+        if size < header_length {
+            return Err(malformed("broken box header"));
+        }
+        match &kind {
+            b"ftyp" => {
+                let mut major = [0u8; 4];
+                file.read_exact(&mut major).map_err(io)?;
+                brand = Some(major);
+            }
+            b"moov" => {
+                let body_length = size - header_length;
+                if body_length > MAX_MOVIE_HEADER {
+                    return Err(malformed("movie header is too large"));
+                }
+                let mut body = vec![0u8; body_length as usize];
+                file.read_exact(&mut body).map_err(io)?;
+                return Ok((brand, body));
+            }
+            _ => {}
+        }
+        offset += size;
+    }
+    Err(malformed("no movie header ('moov' box) found"))
+}
+
+// The child boxes of a box body, as (type, body)
+struct Boxes<'a>(&'a [u8]);
+
+impl<'a> Iterator for Boxes<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.0;
+        let kind: [u8; 4] = data.get(4..8)?.try_into().ok()?;
+        let (header, size) = match be_u32(data, 0)? {
+            1 => (16, usize::try_from(be_u64(data, 8)?).ok()?),
+            0 => (8, data.len()),
+            size => (8, size as usize),
+        };
+        if size < header || size > data.len() {
+            self.0 = &[];
+            return None;
+        }
+        self.0 = &data[size..];
+        Some((kind, &data[header..size]))
+    }
+}
+
+// Follows a path of box types down from `body`, taking the first match at
+// each level
+fn find_box<'a>(body: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    path.iter().try_fold(body, |body, kind| Boxes(body).find(|(k, _)| k == *kind).map(|(_, b)| b))
+}
+
+// (timescale, duration) from an `mvhd` or `mdhd` body
+fn timing(body: &[u8]) -> Option<(u32, u64)> {
+    match body.first()? {
+        1 => Some((be_u32(body, 20)?, be_u64(body, 24)?)),
+        _ => Some((be_u32(body, 12)?, be_u32(body, 16)? as u64)),
+    }
+}
+
+// Common VJ codecs by name, anything else by its four-character code
+fn codec_name(fourcc: [u8; 4]) -> String {
+    let name = match &fourcc {
+        b"avc1" | b"avc3" => "h264",
+        b"hvc1" | b"hev1" => "hevc",
+        b"av01" => "av1",
+        b"vp09" => "vp9",
+        b"mp4v" => "mpeg4",
+        b"apch" | b"apcn" | b"apcs" | b"apco" => "prores",
+        b"ap4h" => "prores_4444",
+        b"ap4x" => "prores_4444_xq",
+        b"Hap1" => "hap",
+        b"Hap5" => "hap_alpha",
+        b"HapY" => "hap_q",
+        b"HapM" => "hap_q_alpha",
+        b"HapA" => "hap_alpha_only",
+        b"png " => "png",
+        b"rle " => "qtrle",
+        b"jpeg" => "mjpeg",
+        _ => return String::from_utf8_lossy(&fourcc).trim().to_string(),
+    };
+    name.to_string()
+}
+
+fn be_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn be_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgb, RgbImage, Rgba, RgbaImage};
+    use std::fs;
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut data = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(body);
+        data
+    }
+
+    // Version 0 mvhd/mdhd body
+    fn timing_body(timescale: u32, duration: u32, length: usize) -> Vec<u8> {
+        let mut body = vec![0u8; 12];
+        body.extend(timescale.to_be_bytes());
+        body.extend(duration.to_be_bytes());
+        body.resize(length, 0);
+        body
+    }
+
+    fn track(handler: &[u8; 4], sample: &[u8]) -> Vec<u8> {
+        let mut tkhd = vec![0u8; 76];
+        tkhd.extend((1920u32 << 16).to_be_bytes());
+        tkhd.extend((1080u32 << 16).to_be_bytes());
+        let mut hdlr = vec![0u8; 8];
+        hdlr.extend_from_slice(handler);
+        hdlr.extend([0u8; 13]);
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend(sample);
+        let mut stts = vec![0, 0, 0, 0, 0, 0, 0, 2];
+        for (count, delta) in [(200u32, 1u32), (100, 1)] {
+            stts.extend(count.to_be_bytes());
+            stts.extend(delta.to_be_bytes());
+        }
+        let stbl = [mp4_box(b"stsd", &stsd), mp4_box(b"stts", &stts)].concat();
+        let minf = mp4_box(b"minf", &mp4_box(b"stbl", &stbl));
+        let mdia = [mp4_box(b"mdhd", &timing_body(25, 300, 24)), mp4_box(b"hdlr", &hdlr), minf].concat();
+        mp4_box(b"trak", &[mp4_box(b"tkhd", &tkhd), mp4_box(b"mdia", &mdia)].concat())
+    }
+
+    // A visual sample entry with its coded size and bit depth
+    fn sample_entry(fourcc: &[u8; 4], width: u16, height: u16, depth: u16) -> Vec<u8> {
+        let mut entry = vec![0u8; 78];
+        entry[24..26].copy_from_slice(&width.to_be_bytes());
+        entry[26..28].copy_from_slice(&height.to_be_bytes());
+        entry[74..76].copy_from_slice(&depth.to_be_bytes());
+        mp4_box(fourcc, &entry)
+    }
+
+    #[test]
+    fn reads_video_track_from_quicktime_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fireburst_intro.mov");
+        let sound = track(b"soun", &mp4_box(b"sowt", &[0u8; 28]));
+        let video = track(b"vide", &sample_entry(b"ap4h", 1920, 1080, 32));
+        let moov = [mp4_box(b"mvhd", &timing_body(1000, 12_500, 100)), sound, video].concat();
+        // Media data first, the way cameras and most encoders write it
+        let file = [mp4_box(b"ftyp", b"qt  \0\0\0\0qt  "), mp4_box(b"mdat", &[0u8; 4096]), mp4_box(b"moov", &moov)].concat();
+        fs::write(&path, file).unwrap();
+
+        let info = probe(&path).unwrap();
+        assert_eq!(info.format, "mov");
+        assert_eq!(info.codec.as_deref(), Some("prores_4444"));
+        assert_eq!((info.width, info.height), (Some(1920), Some(1080)));
+        assert_eq!(info.has_alpha, Some(true));
+        assert_eq!(info.frame_count, Some(300));
+        // The video track's own timing wins over the movie's
+        assert_eq!(info.duration_ms, Some(12_000));
+
+        let h264 = dir.path().join("loop.mp4");
+        let moov = [mp4_box(b"mvhd", &timing_body(1000, 4_000, 100)), track(b"vide", &sample_entry(b"avc1", 1280, 720, 24))].concat();
+        fs::write(&h264, [mp4_box(b"ftyp", b"isom\0\0\0\0"), mp4_box(b"moov", &moov)].concat()).unwrap();
+        let info = probe(&h264).unwrap();
+        assert_eq!((info.format.as_str(), info.codec.as_deref(), info.has_alpha), ("mp4", Some("h264"), Some(false)));
+
+        let broken = dir.path().join("broken.mp4");
+        fs::write(&broken, mp4_box(b"mdat", &[0u8; 64])).unwrap();
+        assert!(probe(&broken).is_err());
+
+        let webm = dir.path().join("clip.webm");
+        fs::write(&webm, b"not parsed").unwrap();
+        assert_eq!(probe(&webm).unwrap().format, "webm");
+    }
+
+    #[test]
+    fn reads_images_and_animations() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("wide.png");
+        RgbaImage::from_pixel(40, 20, Rgba([255, 0, 0, 128])).save(&png).unwrap();
+        let info = probe(&png).unwrap();
+        assert_eq!((info.format.as_str(), info.width, info.height), ("png", Some(40), Some(20)));
+        assert_eq!(info.aspect_ratio, Some(2.0));
+        assert_eq!(info.has_alpha, Some(true));
+        assert_eq!(info.frame_count, None);
+
+        let jpeg = dir.path().join("photo.jpg");
+        RgbImage::from_pixel(30, 30, Rgb([0, 0, 255])).save(&jpeg).unwrap();
+        assert_eq!(probe(&jpeg).unwrap().has_alpha, Some(false));
+
+        let gif = dir.path().join("spin.gif");
+        let frames = (0..3u8).map(|i| {
+            let buffer = RgbaImage::from_pixel(16, 8, Rgba([i * 80, 0, 0, 255]));
+            Frame::from_parts(buffer, 0, 0, Delay::from_numer_denom_ms(100, 1))
+        });
+        GifEncoder::new(fs::File::create(&gif).unwrap()).encode_frames(frames).unwrap();
+        let info = probe(&gif).unwrap();
+        assert_eq!((info.format.as_str(), info.frame_count, info.duration_ms), ("gif", Some(3), Some(300)));
+        assert_eq!(info.has_alpha, Some(false));
+
+        let svg = dir.path().join("logo.svg");
+        fs::write(&svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="300" height="100"/>"#).unwrap();
+        let info = probe(&svg).unwrap();
+        assert_eq!((info.width, info.height, info.aspect_ratio), (Some(300), Some(100), Some(3.0)));
+    }
+
+    #[test]
+    fn probes_each_file_version_once() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let source = dir.path().join("kroma.png");
+        RgbaImage::from_pixel(64, 32, Rgba([0, 0, 0, 0])).save(&source).unwrap();
+        let broken = dir.path().join("broken.png");
+        fs::write(&broken, b"not a png").unwrap();
+        let id = repo.add("kroma", &source.to_string_lossy(), None).unwrap();
+        repo.add("broken", &broken.to_string_lossy(), None).unwrap();
+
+        for job in pending(&conn).unwrap() {
+            store(&conn, &job, probe(&job.source).ok().as_ref()).unwrap();
+        }
+        assert!(pending(&conn).unwrap().is_empty());
+
+        let logos = repo.list().unwrap();
+        let logo = logos.iter().find(|l| l.id == id).unwrap();
+        let media = logo.media.as_ref().unwrap();
+        assert_eq!((media.width, media.height, media.has_alpha), (Some(64), Some(32), Some(true)));
+        assert_eq!(logo.sync_status, "new");
+        assert!(logos.iter().find(|l| l.id != id).unwrap().media.is_none());
+    }
+}
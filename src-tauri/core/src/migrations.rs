@@ -0,0 +1,698 @@
+use crate::current_timestamp;
+use crate::error::{AppError, AppResult, ErrorKind};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+// A single schema step. Versions must be contiguous and start at 1; the
+// database records the last applied one in `PRAGMA user_version`.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+// Ordered list of every schema change. Never edit a migration that has
+// shipped - append a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        // Uses IF NOT EXISTS so databases created before versioning existed
+        // (user_version = 0 but tables present) are adopted as-is.
+        sql: "
+            CREATE TABLE IF NOT EXISTS artists (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                vjtools_id TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                sync_status TEXT NOT NULL DEFAULT 'new'
+            );
+
+            CREATE TABLE IF NOT EXISTS logos (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                file_path TEXT NOT NULL UNIQUE, -- Assuming file paths should be unique locally
+                thumbnail_path TEXT,
+                vjtools_id TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                sync_status TEXT NOT NULL DEFAULT 'new'
+            );
+
+            CREATE TABLE IF NOT EXISTS artist_logos (
+                artist_id TEXT NOT NULL,
+                logo_id TEXT NOT NULL,
+                is_local_override INTEGER NOT NULL DEFAULT 0, -- 0=false, 1=true. Default to synced/global link.
+                FOREIGN KEY (artist_id) REFERENCES artists(id) ON DELETE CASCADE,
+                FOREIGN KEY (logo_id) REFERENCES logos(id) ON DELETE CASCADE,
+                PRIMARY KEY (artist_id, logo_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS schedule_events (
+                id TEXT PRIMARY KEY,
+                event_time TEXT NOT NULL, -- Format 'HH:MM'
+                name TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                duration_seconds INTEGER, -- Duration in seconds, NULL if indefinite/until next
+                linked_logo_id TEXT, -- Optional link to a logo
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (linked_logo_id) REFERENCES logos(id) ON DELETE SET NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_schedule_time ON schedule_events (event_time);
+
+            CREATE TABLE IF NOT EXISTS cycle_config (
+                logo_id TEXT NOT NULL PRIMARY KEY,
+                order_index INTEGER NOT NULL UNIQUE, -- Defines the playback order
+                status TEXT NOT NULL DEFAULT 'cycle',
+                FOREIGN KEY (logo_id) REFERENCES logos(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_cycle_order ON cycle_config (order_index);
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "sync status on schedule events",
+        // Schedule events get the same new/modified/deleted tracking as
+        // artists and logos so edits and deletions can be synced.
+        sql: "
+            ALTER TABLE schedule_events ADD COLUMN sync_status TEXT NOT NULL DEFAULT 'new';
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "cycle item durations, transitions and skip flag",
+        sql: "
+            ALTER TABLE cycle_config ADD COLUMN duration_seconds INTEGER; -- NULL = use the cycle default
+            ALTER TABLE cycle_config ADD COLUMN transition_type TEXT NOT NULL DEFAULT 'cut'; -- cut / fade / crossfade
+            ALTER TABLE cycle_config ADD COLUMN transition_ms INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE cycle_config ADD COLUMN skip INTEGER NOT NULL DEFAULT 0;
+
+            -- Single row of cycle-wide settings
+            CREATE TABLE cycle_settings (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                default_duration_seconds INTEGER NOT NULL DEFAULT 10
+            );
+            INSERT INTO cycle_settings (id) VALUES (1);
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "named cycle groups",
+        // The single cycle becomes the "Main" group. cycle_config is rebuilt
+        // because its primary key and order index now include the group.
+        sql: "
+            CREATE TABLE cycle_groups (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            INSERT INTO cycle_groups (id, name, created_at, updated_at)
+                VALUES ('main', 'Main', strftime('%s', 'now'), strftime('%s', 'now'));
+
+            CREATE TABLE cycle_config_new (
+                group_id TEXT NOT NULL,
+                logo_id TEXT NOT NULL,
+                order_index INTEGER NOT NULL,
+                status TEXT NOT NULL DEFAULT 'cycle',
+                duration_seconds INTEGER,
+                transition_type TEXT NOT NULL DEFAULT 'cut',
+                transition_ms INTEGER NOT NULL DEFAULT 0,
+                skip INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (group_id, logo_id),
+                UNIQUE (group_id, order_index),
+                FOREIGN KEY (group_id) REFERENCES cycle_groups(id) ON DELETE CASCADE,
+                FOREIGN KEY (logo_id) REFERENCES logos(id) ON DELETE CASCADE
+            );
+            INSERT INTO cycle_config_new
+                (group_id, logo_id, order_index, status, duration_seconds, transition_type, transition_ms, skip)
+                SELECT 'main', logo_id, order_index, status, duration_seconds, transition_type, transition_ms, skip
+                FROM cycle_config;
+            DROP TABLE cycle_config;
+            ALTER TABLE cycle_config_new RENAME TO cycle_config;
+            CREATE INDEX idx_cycle_logo ON cycle_config (logo_id);
+
+            -- The group the playback engine runs
+            ALTER TABLE cycle_settings ADD COLUMN active_group_id TEXT REFERENCES cycle_groups(id) ON DELETE SET NULL;
+            UPDATE cycle_settings SET active_group_id = 'main';
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "date-aware schedule events",
+        // 'HH:MM' plus a duration becomes a full local start and an optional
+        // end ('YYYY-MM-DD HH:MM:SS', which sorts chronologically). Existing
+        // events are placed on the day of the upgrade; unreadable times fall
+        // back to midnight so no event is lost.
+        sql: "
+            CREATE TABLE schedule_events_new (
+                id TEXT PRIMARY KEY,
+                starts_at TEXT NOT NULL,
+                ends_at TEXT, -- NULL = until the next event starts
+                name TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                linked_logo_id TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                sync_status TEXT NOT NULL DEFAULT 'new',
+                FOREIGN KEY (linked_logo_id) REFERENCES logos(id) ON DELETE SET NULL
+            );
+            INSERT INTO schedule_events_new
+                (id, starts_at, ends_at, name, event_type, linked_logo_id, created_at, updated_at, sync_status)
+                SELECT id, starts_at,
+                       CASE WHEN duration_seconds IS NULL THEN NULL
+                            ELSE datetime(starts_at, '+' || duration_seconds || ' seconds') END,
+                       name, event_type, linked_logo_id, created_at, updated_at, sync_status
+                FROM (
+                    SELECT *, date('now', 'localtime') || ' ' || COALESCE(time(event_time), '00:00:00') AS starts_at
+                    FROM schedule_events
+                );
+            DROP TABLE schedule_events;
+            ALTER TABLE schedule_events_new RENAME TO schedule_events;
+            CREATE INDEX idx_schedule_start ON schedule_events (starts_at);
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "schedule priorities and strict conflict mode",
+        sql: "
+            -- Higher wins when events overlap
+            ALTER TABLE schedule_events ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+
+            -- Single row of schedule-wide settings
+            CREATE TABLE schedule_settings (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                strict_conflicts INTEGER NOT NULL DEFAULT 0 -- 1 = reject overlapping events
+            );
+            INSERT INTO schedule_settings (id) VALUES (1);
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "source uid on imported schedule events",
+        // UID of the calendar entry an event was imported from, so importing
+        // the same calendar again updates events instead of duplicating them
+        sql: "
+            ALTER TABLE schedule_events ADD COLUMN source_uid TEXT;
+            CREATE INDEX idx_schedule_source_uid ON schedule_events (source_uid);
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "logo file size, modification time and missing flag",
+        // Filled in by library scans. Size and mtime let a scan tell a
+        // renamed file from a new one; `missing` marks logos whose file was
+        // gone at the last scan.
+        sql: "
+            ALTER TABLE logos ADD COLUMN file_size INTEGER;
+            ALTER TABLE logos ADD COLUMN file_modified INTEGER;
+            ALTER TABLE logos ADD COLUMN missing INTEGER NOT NULL DEFAULT 0;
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "source key of generated logo thumbnails",
+        // Identifies the version of the file a generated thumbnail was made
+        // from; NULL for thumbnails passed in by the caller
+        sql: "
+            ALTER TABLE logos ADD COLUMN thumbnail_key TEXT;
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "logo content and perceptual hashes",
+        // SHA-256 of the file, a 64-bit difference hash of images (hex) and
+        // the version of the file both were computed from
+        sql: "
+            ALTER TABLE logos ADD COLUMN content_hash TEXT;
+            ALTER TABLE logos ADD COLUMN perceptual_hash TEXT;
+            ALTER TABLE logos ADD COLUMN hash_key TEXT;
+            CREATE INDEX idx_logos_content_hash ON logos (content_hash);
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "logo library root",
+        // Logo paths under the library root are stored relative to it, so
+        // the database can move between machines with the library folder
+        sql: "
+            CREATE TABLE library_settings (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                root TEXT -- NULL until a library folder is chosen
+            );
+            INSERT INTO library_settings (id) VALUES (1);
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "logo media metadata",
+        // What probing the file found, and the version of the file it was
+        // probed from. media_format stays NULL if the file couldn't be read.
+        sql: "
+            ALTER TABLE logos ADD COLUMN media_format TEXT;
+            ALTER TABLE logos ADD COLUMN media_codec TEXT;
+            ALTER TABLE logos ADD COLUMN width INTEGER;
+            ALTER TABLE logos ADD COLUMN height INTEGER;
+            ALTER TABLE logos ADD COLUMN has_alpha INTEGER;
+            ALTER TABLE logos ADD COLUMN frame_count INTEGER;
+            ALTER TABLE logos ADD COLUMN duration_ms INTEGER;
+            ALTER TABLE logos ADD COLUMN probe_key TEXT;
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "logo tags",
+        // Tags are local organisation like cycle groups: no sync status, and
+        // deleted outright. A name is unique within its category, ignoring case.
+        sql: "
+            CREATE TABLE tags (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                category TEXT NOT NULL CHECK (category IN ('dj', 'type', 'source', 'custom')),
+                created_at INTEGER NOT NULL,
+                UNIQUE (category, name COLLATE NOCASE)
+            );
+            CREATE TABLE logo_tags (
+                logo_id TEXT NOT NULL,
+                tag_id TEXT NOT NULL,
+                PRIMARY KEY (logo_id, tag_id),
+                FOREIGN KEY (logo_id) REFERENCES logos(id) ON DELETE CASCADE,
+                FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+            );
+            CREATE INDEX idx_logo_tags_tag ON logo_tags (tag_id);
+        ",
+    },
+    Migration {
+        version: 14,
+        description: "logo full-text search",
+        // One FTS5 row per live logo: its name, file name, linked artist
+        // names and tag names. logo_search_source computes the row; the
+        // triggers rebuild it whenever one of those inputs changes.
+        sql: "
+            CREATE VIRTUAL TABLE logo_search USING fts5(
+                logo_id UNINDEXED, name, file_name, artists, tags,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+
+            CREATE VIEW logo_search_source AS
+            SELECT l.id AS logo_id,
+                   l.name,
+                   -- Everything after the last '/' or '\\'
+                   substr(replace(l.file_path, '\\', '/'),
+                          length(rtrim(replace(l.file_path, '\\', '/'), replace(replace(l.file_path, '\\', '/'), '/', ''))) + 1) AS file_name,
+                   (SELECT group_concat(a.name, ' ') FROM artist_logos al JOIN artists a ON a.id = al.artist_id
+                    WHERE al.logo_id = l.id AND a.sync_status != 'deleted') AS artists,
+                   (SELECT group_concat(t.name, ' ') FROM logo_tags lt JOIN tags t ON t.id = lt.tag_id
+                    WHERE lt.logo_id = l.id) AS tags
+            FROM logos l
+            WHERE l.sync_status != 'deleted';
+
+            INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                SELECT logo_id, name, file_name, artists, tags FROM logo_search_source;
+
+            CREATE TRIGGER logo_search_logo_insert AFTER INSERT ON logos BEGIN
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source WHERE logo_id = NEW.id;
+            END;
+            CREATE TRIGGER logo_search_logo_update AFTER UPDATE OF name, file_path, sync_status ON logos BEGIN
+                DELETE FROM logo_search WHERE logo_id = OLD.id;
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source WHERE logo_id = NEW.id;
+            END;
+            CREATE TRIGGER logo_search_logo_delete AFTER DELETE ON logos BEGIN
+                DELETE FROM logo_search WHERE logo_id = OLD.id;
+            END;
+
+            CREATE TRIGGER logo_search_link_insert AFTER INSERT ON artist_logos BEGIN
+                DELETE FROM logo_search WHERE logo_id = NEW.logo_id;
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source WHERE logo_id = NEW.logo_id;
+            END;
+            CREATE TRIGGER logo_search_link_delete AFTER DELETE ON artist_logos BEGIN
+                DELETE FROM logo_search WHERE logo_id = OLD.logo_id;
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source WHERE logo_id = OLD.logo_id;
+            END;
+            CREATE TRIGGER logo_search_artist_update AFTER UPDATE OF name, sync_status ON artists BEGIN
+                DELETE FROM logo_search WHERE logo_id IN (SELECT logo_id FROM artist_logos WHERE artist_id = NEW.id);
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source
+                    WHERE logo_id IN (SELECT logo_id FROM artist_logos WHERE artist_id = NEW.id);
+            END;
+
+            CREATE TRIGGER logo_search_tag_insert AFTER INSERT ON logo_tags BEGIN
+                DELETE FROM logo_search WHERE logo_id = NEW.logo_id;
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source WHERE logo_id = NEW.logo_id;
+            END;
+            CREATE TRIGGER logo_search_tag_delete AFTER DELETE ON logo_tags BEGIN
+                DELETE FROM logo_search WHERE logo_id = OLD.logo_id;
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source WHERE logo_id = OLD.logo_id;
+            END;
+            CREATE TRIGGER logo_search_tag_update AFTER UPDATE OF name ON tags BEGIN
+                DELETE FROM logo_search WHERE logo_id IN (SELECT logo_id FROM logo_tags WHERE tag_id = NEW.id);
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source
+                    WHERE logo_id IN (SELECT logo_id FROM logo_tags WHERE tag_id = NEW.id);
+            END;
+        ",
+    },
+    Migration {
+        version: 15,
+        description: "artist aliases",
+        // Other names an artist appears under in timetables. Aliases are
+        // local like tags; match_key is the normalised form (see
+        // artist_match::match_key) and belongs to one artist only. Alias
+        // text is searchable, so the search source view is recreated with
+        // it and the index rebuilt.
+        sql: "
+            CREATE TABLE artist_aliases (
+                artist_id TEXT NOT NULL,
+                alias TEXT NOT NULL,
+                match_key TEXT NOT NULL UNIQUE,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (artist_id) REFERENCES artists(id) ON DELETE CASCADE
+            );
+            CREATE INDEX idx_artist_aliases_artist ON artist_aliases (artist_id);
+
+            DROP VIEW logo_search_source;
+            CREATE VIEW logo_search_source AS
+            SELECT l.id AS logo_id,
+                   l.name,
+                   -- Everything after the last '/' or '\\'
+                   substr(replace(l.file_path, '\\', '/'),
+                          length(rtrim(replace(l.file_path, '\\', '/'), replace(replace(l.file_path, '\\', '/'), '/', ''))) + 1) AS file_name,
+                   (SELECT group_concat(names, ' ') FROM (
+                        SELECT a.name AS names FROM artist_logos al JOIN artists a ON a.id = al.artist_id
+                        WHERE al.logo_id = l.id AND a.sync_status != 'deleted'
+                        UNION ALL
+                        SELECT aa.alias FROM artist_logos al JOIN artist_aliases aa ON aa.artist_id = al.artist_id
+                        WHERE al.logo_id = l.id)) AS artists,
+                   (SELECT group_concat(t.name, ' ') FROM logo_tags lt JOIN tags t ON t.id = lt.tag_id
+                    WHERE lt.logo_id = l.id) AS tags
+            FROM logos l
+            WHERE l.sync_status != 'deleted';
+
+            DELETE FROM logo_search;
+            INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                SELECT logo_id, name, file_name, artists, tags FROM logo_search_source;
+
+            CREATE TRIGGER logo_search_alias_insert AFTER INSERT ON artist_aliases BEGIN
+                DELETE FROM logo_search WHERE logo_id IN (SELECT logo_id FROM artist_logos WHERE artist_id = NEW.artist_id);
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source
+                    WHERE logo_id IN (SELECT logo_id FROM artist_logos WHERE artist_id = NEW.artist_id);
+            END;
+            CREATE TRIGGER logo_search_alias_delete AFTER DELETE ON artist_aliases BEGIN
+                DELETE FROM logo_search WHERE logo_id IN (SELECT logo_id FROM artist_logos WHERE artist_id = OLD.artist_id);
+                INSERT INTO logo_search (logo_id, name, file_name, artists, tags)
+                    SELECT logo_id, name, file_name, artists, tags FROM logo_search_source
+                    WHERE logo_id IN (SELECT logo_id FROM artist_logos WHERE artist_id = OLD.artist_id);
+            END;
+        ",
+    },
+    Migration {
+        version: 16,
+        description: "artist profiles",
+        // Profile details shown in intros. Like the name they sync with the
+        // artist, so editing them marks the artist modified. Social handles
+        // are one per platform.
+        sql: "
+            ALTER TABLE artists ADD COLUMN display_name TEXT;
+            ALTER TABLE artists ADD COLUMN pronunciation TEXT;
+            ALTER TABLE artists ADD COLUMN brand_color TEXT; -- '#rrggbb'
+            ALTER TABLE artists ADD COLUMN notes TEXT;
+            ALTER TABLE artists ADD COLUMN preferred_logo_id TEXT REFERENCES logos(id) ON DELETE SET NULL;
+
+            CREATE TABLE artist_socials (
+                artist_id TEXT NOT NULL,
+                platform TEXT NOT NULL, -- lowercase, e.g. 'instagram'
+                handle TEXT NOT NULL,
+                PRIMARY KEY (artist_id, platform),
+                FOREIGN KEY (artist_id) REFERENCES artists(id) ON DELETE CASCADE
+            );
+        ",
+    },
+    Migration {
+        version: 17,
+        description: "offline vj.tools events",
+        // The last copy of each event fetched from vj.tools, so the app
+        // still has them without a network. payload is the event's JSON as
+        // received; the other columns are read out of it for sorting and
+        // display.
+        sql: "
+            CREATE TABLE vjtools_events (
+                id TEXT PRIMARY KEY, -- vj.tools event id
+                title TEXT,
+                starts_at TEXT, -- UTC
+                ends_at TEXT, -- UTC
+                artist_name TEXT,
+                logo TEXT, -- path or URL of the event's first logo
+                payload TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL
+            );
+            CREATE INDEX idx_vjtools_events_starts_at ON vjtools_events (starts_at);
+        ",
+    },
+    Migration {
+        version: 18,
+        description: "offline messages",
+        // DJ-VJ messages: those synced from vj.tools, and those written here
+        // while offline, queued until they can be sent. A queued message has
+        // a local id until vj.tools accepts it and gives it its own.
+        // Conversations are the messages grouped by conversation_id.
+        sql: "
+            CREATE TABLE messages (
+                id TEXT PRIMARY KEY,
+                conversation_id TEXT NOT NULL,
+                sender_id TEXT NOT NULL,
+                content TEXT NOT NULL,
+                sent_at TEXT NOT NULL, -- UTC
+                read_at TEXT, -- UTC; NULL = unread
+                status TEXT NOT NULL DEFAULT 'synced' CHECK (status IN ('synced', 'queued'))
+            );
+            CREATE INDEX idx_messages_conversation ON messages (conversation_id, sent_at);
+        ",
+    },
+];
+
+// Highest schema version this build knows how to work with
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+// Brings the database up to `latest_version()`.
+//
+// Each migration runs in its own transaction together with the
+// `user_version` bump, so a failure leaves the database at the last good
+// version. If anything is about to change on a database that already holds
+// data, a copy is written next to it first (see `backup_database`).
+// Returns the version the database ended up at.
+pub fn run_migrations(conn: &mut Connection, db_path: &Path) -> AppResult<u32> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(AppError::new(
+            ErrorKind::Database,
+            format!(
+                "Database schema version {} is newer than this app supports ({}). Please update the app.",
+                current, latest
+            ),
+        ));
+    }
+
+    if current == latest {
+        return Ok(current);
+    }
+
+    if has_user_tables(conn)? {
+        let backup_path = backup_database(conn, db_path, current)?;
+        println!("Database backed up to {:?} before migrating", backup_path);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        apply(conn, migration)
+            .map_err(|e| AppError::from(e).context(&format!("Migration {} ({}) failed", migration.version, migration.description)))?;
+        println!("Applied migration {}: {}", migration.version, migration.description);
+    }
+
+    Ok(latest)
+}
+
+// Applies every migration newer than the database without taking a backup.
+// Only meant for throwaway databases such as the in-memory test fixtures.
+pub fn apply_pending(conn: &mut Connection) -> rusqlite::Result<u32> {
+    let current = current_version(conn)?;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        apply(conn, migration)?;
+    }
+    current_version(conn)
+}
+
+fn apply(conn: &mut Connection, migration: &Migration) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(migration.sql)?;
+    tx.pragma_update(None, "user_version", migration.version)?;
+    tx.commit()
+}
+
+fn has_user_tables(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+// Writes a consistent snapshot of the open database to
+// `<db file>.v<version>-<unix time>.bak` using `VACUUM INTO`.
+fn backup_database(conn: &Connection, db_path: &Path, version: u32) -> AppResult<PathBuf> {
+    let timestamp = current_timestamp();
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "database.sqlite".to_string());
+    let backup_path = db_path.with_file_name(format!("{}.v{}-{}.bak", file_name, version, timestamp));
+
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy()])
+        .map_err(|e| AppError::from(e).context("Failed to back up database before migrating"))?;
+
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_contiguous_from_one() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as u32 + 1, "{}", migration.description);
+        }
+    }
+
+    #[test]
+    fn fresh_database_is_migrated_without_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vj_local_db.sqlite");
+        let mut conn = Connection::open(&db_path).unwrap();
+
+        assert_eq!(run_migrations(&mut conn, &db_path).unwrap(), latest_version());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn existing_unversioned_database_is_backed_up_and_adopted() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vj_local_db.sqlite");
+        let mut conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE artists (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                vjtools_id TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                sync_status TEXT NOT NULL DEFAULT 'new'
+            );
+            INSERT INTO artists (id, name, created_at, updated_at) VALUES ('a1', 'KROMA', 1, 1);",
+        )
+        .unwrap();
+
+        run_migrations(&mut conn, &db_path).unwrap();
+
+        let name: String = conn.query_row("SELECT name FROM artists WHERE id = 'a1'", [], |r| r.get(0)).unwrap();
+        assert_eq!(name, "KROMA");
+
+        let backups: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let backup = Connection::open(backups[0].path()).unwrap();
+        assert_eq!(current_version(&backup).unwrap(), 0);
+        let count: i64 = backup.query_row("SELECT COUNT(*) FROM artists", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn up_to_date_database_is_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vj_local_db.sqlite");
+        let mut conn = Connection::open(&db_path).unwrap();
+        run_migrations(&mut conn, &db_path).unwrap();
+        conn.execute("INSERT INTO artists (id, name, created_at, updated_at) VALUES ('a1', 'KROMA', 1, 1)", []).unwrap();
+
+        run_migrations(&mut conn, &db_path).unwrap();
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn newer_database_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vj_local_db.sqlite");
+        let mut conn = Connection::open(&db_path).unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+
+        let err = run_migrations(&mut conn, &db_path).unwrap_err();
+        assert!(err.to_string().contains("newer than this app supports"));
+        assert_eq!(current_version(&conn).unwrap(), latest_version() + 1);
+    }
+
+    #[test]
+    fn existing_logos_are_indexed_for_search() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= 13) {
+            apply(&mut conn, migration).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO logos (id, name, file_path, created_at, updated_at) VALUES ('l1', 'Kroma', 'D:\\Logos\\kroma_neon.png', 1, 1)",
+            [],
+        )
+        .unwrap();
+
+        apply_pending(&mut conn).unwrap();
+
+        let file_name: String = conn
+            .query_row("SELECT file_name FROM logo_search WHERE logo_search MATCH 'neon*'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(file_name, "kroma_neon.png");
+    }
+
+    #[test]
+    fn schedule_events_get_a_start_date_and_end() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= 4) {
+            apply(&mut conn, migration).unwrap();
+        }
+        conn.execute_batch(
+            "INSERT INTO schedule_events (id, event_time, name, event_type, duration_seconds, created_at, updated_at)
+             VALUES ('e1', '23:30', 'KROMA', 'dj_set', 3600, 1, 1),
+                    ('e2', 'soon', 'Doors', 'special', NULL, 1, 1);",
+        )
+        .unwrap();
+
+        apply_pending(&mut conn).unwrap();
+
+        let (starts_at, ends_at): (String, Option<String>) = conn
+            .query_row("SELECT starts_at, ends_at FROM schedule_events WHERE id = 'e1'", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert!(starts_at.ends_with(" 23:30:00"), "{}", starts_at);
+        assert!(ends_at.unwrap().ends_with(" 00:30:00"));
+        let (starts_at, ends_at): (String, Option<String>) = conn
+            .query_row("SELECT starts_at, ends_at FROM schedule_events WHERE id = 'e2'", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert!(starts_at.ends_with(" 00:00:00"));
+        assert_eq!(ends_at, None);
+    }
+}
@@ -0,0 +1,696 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+// Define the Artist struct
+#[derive(Debug, Serialize, Clone, Deserialize, PartialEq)]
+pub struct Artist {
+    pub id: String,
+    pub name: String,
+    pub vjtools_id: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub sync_status: String,
+    // Other names the artist appears under, e.g. "DJ Kroma" for KROMA
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    // Sent as fields of the artist itself
+    #[serde(flatten)]
+    pub profile: ArtistProfile,
+}
+
+// What the VJ knows about an artist beyond its name. Every field is
+// optional; an empty profile is the default.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ArtistProfile {
+    // Name to put on screen when it differs from the timetable name
+    pub display_name: Option<String>,
+    // How to say the name, for announcements
+    pub pronunciation: Option<String>,
+    // '#rrggbb'
+    pub brand_color: Option<String>,
+    // Logo picked automatically when the artist is scheduled
+    pub preferred_logo_id: Option<String>,
+    // One handle per platform, by platform
+    pub socials: Vec<SocialHandle>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SocialHandle {
+    // Lowercase platform name, e.g. "instagram"
+    pub platform: String,
+    pub handle: String,
+}
+
+// How a name was recognised as an artist, best first
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtistMatchKind {
+    // Same name once case, accents, a "DJ" prefix and notes like "(live)"
+    // are ignored
+    Exact,
+    // Same as one of the artist's aliases
+    Alias,
+    // The artist's name or alias appears in it, as in "KROMA b2b Nyx"
+    Contained,
+    // Within a few typos of the name or an alias
+    Fuzzy,
+}
+
+// An artist that a name could refer to
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ArtistMatch {
+    pub artist: Artist,
+    pub kind: ArtistMatchKind,
+    // The artist's name or alias that matched
+    pub matched_name: String,
+    // Edit distance between the two names' match keys
+    pub distance: u32,
+}
+
+// Logo row plus the names of the artists it is linked to
+#[derive(Debug, Serialize, Clone, Deserialize)]
+pub struct Logo {
+    pub id: String,
+    pub name: String,
+    pub file_path: String,
+    pub thumbnail_path: Option<String>,
+    pub vjtools_id: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub sync_status: String,
+    // Size in bytes and modification time (Unix seconds) as of the last
+    // library scan
+    pub file_size: Option<u64>,
+    pub file_modified: Option<u64>,
+    // The file wasn't found at the last library scan
+    #[serde(default)]
+    pub missing: bool,
+    // Version of the file the generated thumbnail was made from; None if
+    // `thumbnail_path` was set by hand
+    #[serde(default)]
+    pub thumbnail_key: Option<String>,
+    // SHA-256 of the file, and a difference hash (hex) for images
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    #[serde(default)]
+    pub perceptual_hash: Option<String>,
+    // Version of the file the hashes were computed from
+    #[serde(default)]
+    pub hash_key: Option<String>,
+    // What probing the file found; None until it has been probed, or if it
+    // couldn't be read
+    #[serde(default)]
+    pub media: Option<MediaInfo>,
+    // Version of the file `media` was probed from
+    #[serde(default)]
+    pub probe_key: Option<String>,
+    #[serde(default)]
+    pub linked_djs: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+}
+
+// What a tag says about a logo (Concept: DJ name, logo type, source).
+// Categories are filtered separately, so "sponsor" and "imported" combine.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TagCategory {
+    // The DJ or act a logo belongs to
+    Dj,
+    // sponsor, intro, loop, ...
+    Type,
+    // uploaded, imported, generated, ...
+    Source,
+    #[default]
+    Custom,
+}
+
+impl TagCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TagCategory::Dj => "dj",
+            TagCategory::Type => "type",
+            TagCategory::Source => "source",
+            TagCategory::Custom => "custom",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "dj" => Some(TagCategory::Dj),
+            "type" => Some(TagCategory::Type),
+            "source" => Some(TagCategory::Source),
+            "custom" => Some(TagCategory::Custom),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub category: TagCategory,
+}
+
+// A tag to put on logos, created if it doesn't exist yet
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TagInput {
+    pub name: String,
+    #[serde(default)]
+    pub category: TagCategory,
+}
+
+// A logo library search. Every filter is optional; an empty search lists
+// every logo by name.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct LogoSearch {
+    // Words to find in logo names, file names, linked artist names and
+    // tags. Each word matches as a prefix, and all of them must match.
+    pub query: String,
+    // Only logos linked to this artist
+    pub artist_id: Option<String>,
+    // Any of the selected tags of each category, as in `list_tagged`
+    pub tag_ids: Vec<String>,
+    // In (or not in) any cycle group
+    pub in_cycle: Option<bool>,
+    // Linked (or not) to a schedule event
+    pub scheduled: Option<bool>,
+    // Only logos without artist links, cycle entries or schedule events
+    pub unused: bool,
+    pub missing: Option<bool>,
+    pub sort: LogoSort,
+    // Reverses the sort: worst match, Z-A, or newest first
+    pub descending: bool,
+    pub offset: u32,
+    // Page size; 100 if not given, at most 1000
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogoSort {
+    // Best match first; by name when there are no search words
+    #[default]
+    Relevance,
+    Name,
+    Created,
+    Updated,
+}
+
+// One page of search results and the number of matches in all pages
+#[derive(Debug, Serialize, Clone)]
+pub struct LogoSearchPage {
+    pub logos: Vec<Logo>,
+    pub total: u32,
+    pub offset: u32,
+}
+
+// What kind of media a logo file is, going by its extension
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Image,
+    Video,
+}
+
+// Dimensions, transparency and timing of a logo file. Fields the format
+// doesn't have, or that couldn't be read, are None.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct MediaInfo {
+    // "png", "gif", "webp", "svg", "mp4", "mov", ...
+    pub format: String,
+    // Video codec, e.g. "h264", "hevc", "prores_4444", "hap_alpha"
+    pub codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    // Width divided by height
+    pub aspect_ratio: Option<f64>,
+    pub has_alpha: Option<bool>,
+    // Animated images and videos only
+    pub frame_count: Option<u32>,
+    pub duration_ms: Option<u64>,
+}
+
+// A link between an artist and a logo. Local overrides were made on this
+// machine and win over what vj.tools says about the artist's logos.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ArtistLogoLink {
+    pub artist_id: String,
+    pub artist_name: String,
+    pub logo_id: String,
+    pub logo_name: String,
+    pub is_local_override: bool,
+}
+
+// What `LogoRepo::apply_remote_links` changed
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RemoteLinkChanges {
+    pub added: usize,
+    pub removed: usize,
+    // Local overrides left as they were
+    pub overrides_kept: usize,
+}
+
+// A logo as the gallery shows it
+#[derive(Debug, Serialize, Clone)]
+pub struct LibraryEntry {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    // None for files the library doesn't recognise (added by hand)
+    pub kind: Option<MediaKind>,
+    pub thumbnail_path: Option<String>,
+    pub linked_djs: Vec<String>,
+    pub missing: bool,
+    pub media: Option<MediaInfo>,
+}
+
+// Logo ids touched by a library scan
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct LibraryChanges {
+    pub added: Vec<String>,
+    // Size or modification time changed, or a missing file came back
+    pub modified: Vec<String>,
+    // File moved or renamed inside the library; the logo keeps its links
+    pub renamed: Vec<String>,
+    // File no longer found; the logo stays and is flagged `missing`
+    pub missing: Vec<String>,
+}
+
+impl LibraryChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.renamed.is_empty() && self.missing.is_empty()
+    }
+}
+
+// Payload of the `library-changed` event. Renamed logos are listed as
+// modified and logos whose file went missing as removed.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct LibraryUpdate {
+    pub added: Vec<LibraryEntry>,
+    pub modified: Vec<LibraryEntry>,
+    pub removed: Vec<LibraryEntry>,
+}
+
+// Logos that are the same file, or look the same
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKind {
+    // Byte-for-byte identical
+    Identical,
+    // Images whose perceptual hashes are close, e.g. a re-export or resize
+    Similar,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    // Oldest first, the usual pick to keep
+    pub logos: Vec<Logo>,
+}
+
+// A logo whose path a relink rewrote
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RelinkedLogo {
+    pub id: String,
+    pub name: String,
+    pub old_path: String,
+    pub new_path: String,
+}
+
+// A logo a relink couldn't find a file for, and why
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct UnresolvedLogo {
+    pub id: String,
+    pub name: String,
+    pub file_path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RelinkReport {
+    pub relinked: Vec<RelinkedLogo>,
+    pub unresolved: Vec<UnresolvedLogo>,
+}
+
+// Where a logo stands in the cycle (Concept: 🌀 / 🔒 / ❎)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CycleStatus {
+    // In cycle and editable
+    #[default]
+    Cycle,
+    // Plays, but pinned in place (e.g. held for a scheduled override)
+    Locked,
+    // Kept in the list but never shown
+    Inactive,
+}
+
+impl CycleStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CycleStatus::Cycle => "cycle",
+            CycleStatus::Locked => "locked",
+            CycleStatus::Inactive => "inactive",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "cycle" => Some(CycleStatus::Cycle),
+            "locked" => Some(CycleStatus::Locked),
+            "inactive" => Some(CycleStatus::Inactive),
+            _ => None,
+        }
+    }
+}
+
+// How a cycle logo comes on screen
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionType {
+    #[default]
+    Cut,
+    Fade,
+    Crossfade,
+}
+
+impl TransitionType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TransitionType::Cut => "cut",
+            TransitionType::Fade => "fade",
+            TransitionType::Crossfade => "crossfade",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "cut" => Some(TransitionType::Cut),
+            "fade" => Some(TransitionType::Fade),
+            "crossfade" => Some(TransitionType::Crossfade),
+            _ => None,
+        }
+    }
+}
+
+// Stores a text enum as its `as_str()` name and reads it back with `parse()`
+macro_rules! sql_text_enum {
+    ($ty:ident) => {
+        impl ToSql for $ty {
+            fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+                Ok(ToSqlOutput::from(self.as_str()))
+            }
+        }
+
+        impl FromSql for $ty {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                let text = value.as_str()?;
+                $ty::parse(text)
+                    .ok_or_else(|| FromSqlError::Other(format!("unknown {} '{}'", stringify!($ty), text).into()))
+            }
+        }
+    };
+}
+
+sql_text_enum!(CycleStatus);
+sql_text_enum!(TransitionType);
+sql_text_enum!(TagCategory);
+sql_text_enum!(MessageStatus);
+
+// Struct for Cycle Bar items
+#[derive(Debug, Serialize, Clone, Deserialize)]
+pub struct CycleItem {
+    pub id: String,
+    pub logo_id: String,
+    pub name: String,
+    pub status: CycleStatus,
+    pub order_index: u32,
+    // How long the logo stays up; None uses the cycle's default duration
+    pub duration_seconds: Option<u32>,
+    // Transition into this logo. It runs at the start of the item and
+    // counts towards its duration.
+    pub transition_type: TransitionType,
+    pub transition_ms: u32,
+    // Temporarily left out of playback without losing its place
+    pub skip: bool,
+}
+
+impl CycleItem {
+    // Whether the playback engine should show this item
+    pub fn is_playable(&self) -> bool {
+        self.status != CycleStatus::Inactive && !self.skip
+    }
+}
+
+// Per-item settings sent by the frontend when saving the cycle. Everything
+// but `logo_id` is optional and falls back to the defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CycleItemConfig {
+    pub logo_id: String,
+    #[serde(default)]
+    pub status: CycleStatus,
+    #[serde(default)]
+    pub duration_seconds: Option<u32>,
+    #[serde(default)]
+    pub transition_type: TransitionType,
+    #[serde(default)]
+    pub transition_ms: u32,
+    #[serde(default)]
+    pub skip: bool,
+}
+
+impl CycleItemConfig {
+    pub fn new(logo_id: impl Into<String>) -> Self {
+        Self {
+            logo_id: logo_id.into(),
+            ..Self::default()
+        }
+    }
+}
+
+// A named, saved cycle ("Main Room", "After Hours"). Exactly one group is
+// active at a time and that's the one the playback engine runs.
+#[derive(Debug, Serialize, Clone, Deserialize)]
+pub struct CycleGroup {
+    pub id: String,
+    pub name: String,
+    pub is_active: bool,
+    pub item_count: u32,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+// Settings shared by every item in the cycle
+#[derive(Debug, Serialize, Clone, Deserialize, PartialEq, Eq)]
+pub struct CycleSettings {
+    pub default_duration_seconds: u32,
+}
+
+// Struct for Schedule Feed items. Times are local wall-clock time.
+#[derive(Debug, Serialize, Clone, Deserialize)]
+pub struct ScheduleItem {
+    pub id: String,
+    pub starts_at: NaiveDateTime,
+    // None = runs until the next event starts
+    pub ends_at: Option<NaiveDateTime>,
+    pub name: String,
+    pub event_type: String,
+    pub linked_logo_id: Option<String>,
+    // Decides which event is shown when events overlap; higher wins
+    pub priority: i32,
+}
+
+// Editable fields of a schedule event, as passed to `ScheduleRepo::add` and
+// `ScheduleRepo::update`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleEventInput {
+    pub starts_at: NaiveDateTime,
+    pub ends_at: Option<NaiveDateTime>,
+    pub name: String,
+    pub event_type: String,
+    pub linked_logo_id: Option<String>,
+    pub priority: i32,
+}
+
+impl ScheduleEventInput {
+    pub fn new(starts_at: NaiveDateTime, name: impl Into<String>, event_type: impl Into<String>) -> Self {
+        Self {
+            starts_at,
+            ends_at: None,
+            name: name.into(),
+            event_type: event_type.into(),
+            linked_logo_id: None,
+            priority: 0,
+        }
+    }
+
+    // The event as it will be listed once stored under `id`
+    pub fn to_item(&self, id: &str) -> ScheduleItem {
+        ScheduleItem {
+            id: id.to_string(),
+            starts_at: self.starts_at,
+            ends_at: self.ends_at,
+            name: self.name.clone(),
+            event_type: self.event_type.clone(),
+            linked_logo_id: self.linked_logo_id.clone(),
+            priority: self.priority,
+        }
+    }
+}
+
+// Two events whose times overlap. `overlap_end` is None when the overlap
+// runs on with no end (an open-ended final event).
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ScheduleConflict {
+    pub first_id: String,
+    pub first_name: String,
+    pub second_id: String,
+    pub second_name: String,
+    pub overlap_start: NaiveDateTime,
+    pub overlap_end: Option<NaiveDateTime>,
+    // The event the playback engine will show during the overlap; None when
+    // they start together with the same priority
+    pub winner_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Deserialize, PartialEq, Eq)]
+pub struct ScheduleSettings {
+    // Reject new or edited events that overlap an existing one
+    pub strict_conflicts: bool,
+}
+
+// What an import would do with one incoming event
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    Add,
+    // Replaces the event in `existing_id`
+    Update,
+    // Already in the schedule as-is
+    Unchanged,
+}
+
+// One event of an import preview. Sent back unchanged (or filtered) to
+// apply the import.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImportedEvent {
+    // UID of the calendar entry, when the source has one
+    pub source_uid: Option<String>,
+    pub action: ImportAction,
+    pub existing_id: Option<String>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: Option<NaiveDateTime>,
+    pub name: String,
+    pub event_type: String,
+    // Artist recognised in the event name, and the logo that comes with it
+    pub artist_id: Option<String>,
+    pub artist_name: Option<String>,
+    // How `artist_id` was recognised
+    #[serde(default)]
+    pub artist_match: Option<ArtistMatchKind>,
+    // `artist_name` isn't a known artist yet and is added when importing
+    #[serde(default)]
+    pub create_artist: bool,
+    pub linked_logo_id: Option<String>,
+    pub priority: i32,
+}
+
+// An entry of the source that couldn't be imported
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SkippedEntry {
+    // Where in the source, e.g. "VEVENT 3" or "row 12"
+    pub location: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ImportPreview {
+    pub events: Vec<ImportedEvent>,
+    pub skipped: Vec<SkippedEntry>,
+    // Overlaps the import would create. Incoming events are identified by
+    // their `existing_id`, or "import:<index into events>" for new ones.
+    pub conflicts: Vec<ScheduleConflict>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, Default)]
+pub struct ImportSummary {
+    pub added: u32,
+    pub updated: u32,
+    pub unchanged: u32,
+    pub artists_added: u32,
+}
+
+// An event from vj.tools as stored for offline use
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct StoredEvent {
+    pub id: String,
+    pub title: Option<String>,
+    // UTC, read from whichever format vj.tools sent; None if it sent no
+    // readable time (the payload still has what it sent)
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub artist_name: Option<String>,
+    // Path or URL of the event's first logo
+    pub logo: Option<String>,
+    // The event exactly as received
+    pub payload: serde_json::Value,
+    // When it was last fetched
+    pub fetched_at: u64,
+}
+
+// Where a stored message stands with vj.tools
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageStatus {
+    // Came from, or has been delivered to, vj.tools
+    Synced,
+    // Written here and waiting to be sent
+    Queued,
+}
+
+impl MessageStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageStatus::Synced => "synced",
+            MessageStatus::Queued => "queued",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "synced" => Some(MessageStatus::Synced),
+            "queued" => Some(MessageStatus::Queued),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct StoredMessage {
+    pub id: String,
+    pub conversation_id: String,
+    pub sender_id: String,
+    pub content: String,
+    pub sent_at: DateTime<Utc>,
+    // None while unread
+    pub read_at: Option<DateTime<Utc>>,
+    pub status: MessageStatus,
+}
+
+// A conversation as seen from its stored messages
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub last_message_at: DateTime<Utc>,
+    pub message_count: u32,
+    // Synced messages nobody has marked read
+    pub unread_count: u32,
+    // Messages waiting to be sent
+    pub queued_count: u32,
+}
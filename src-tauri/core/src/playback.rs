@@ -0,0 +1,715 @@
+//! Decides what is on screen at any instant.
+//!
+//! Two layers, as described in the Concept:
+//! - the cycle layer loops through `cycle_config` forever, one logo at a time
+//! - the schedule layer overrides it while a scheduled event with a linked
+//!   logo is running; when that event ends the cycle resumes where it paused
+//!
+//! The engine never reads the system time itself. Everything goes through a
+//! [`Clock`], so tests can step time forward deterministically. Schedule
+//! events are matched against local wall-clock time; the cycle is timed with
+//! a monotonic clock so a DST change or a corrected system clock doesn't
+//! freeze or skip it.
+
+use crate::error::AppResult;
+use crate::models::{CycleItem, ScheduleItem, TransitionType};
+use crate::repo::{CycleRepo, ScheduleRepo};
+use crate::schedule::{self, occurrences, Occurrence};
+use chrono::{Duration, Local, NaiveDateTime};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+// How long a cycle logo stays up when neither it nor the cycle settings
+// say otherwise
+pub const DEFAULT_CYCLE_ITEM_SECONDS: u32 = 10;
+
+pub trait Clock: Send {
+    // Current local wall-clock time
+    fn now(&self) -> NaiveDateTime;
+    // Time since some fixed point that only ever moves forward, whatever
+    // happens to the wall clock. Only differences between readings mean
+    // anything.
+    fn monotonic(&self) -> Duration;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    fn monotonic(&self) -> Duration {
+        static START: OnceLock<Instant> = OnceLock::new();
+        Duration::milliseconds(START.get_or_init(Instant::now).elapsed().as_millis() as i64)
+    }
+}
+
+// Clock that only moves when told to. Clones share the same time.
+#[derive(Clone)]
+pub struct ManualClock(Arc<Mutex<(NaiveDateTime, Duration)>>);
+
+impl ManualClock {
+    pub fn new(start: NaiveDateTime) -> Self {
+        Self(Arc::new(Mutex::new((start, Duration::zero()))))
+    }
+
+    // Moves the wall clock to `now`. Monotonic time follows moves forward
+    // only: a move back is the wall clock being turned back, as at the end
+    // of DST, while real time goes on.
+    pub fn set(&self, now: NaiveDateTime) {
+        let mut time = self.0.lock().unwrap();
+        let (wall, monotonic) = &mut *time;
+        *monotonic += (now - *wall).max(Duration::zero());
+        *wall = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut time = self.0.lock().unwrap();
+        time.0 += by;
+        time.1 += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> NaiveDateTime {
+        self.0.lock().unwrap().0
+    }
+
+    fn monotonic(&self) -> Duration {
+        self.0.lock().unwrap().1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layer {
+    Cycle,
+    Schedule,
+}
+
+// Payload of the `now-showing` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NowShowing {
+    pub layer: Layer,
+    pub logo_id: String,
+    pub name: String,
+    pub schedule_event_id: Option<String>,
+    pub started_at: NaiveDateTime,
+    // None for open-ended scheduled events
+    pub duration_ms: Option<u64>,
+    pub elapsed_ms: u64,
+    // 0.0..=1.0, None when the duration is unknown
+    pub progress: Option<f64>,
+    // How the logo came on screen; always a cut for scheduled events
+    pub transition_type: TransitionType,
+    pub transition_ms: u32,
+}
+
+// Payload of the `up-next` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UpNext {
+    pub layer: Layer,
+    pub logo_id: String,
+    pub name: String,
+    pub schedule_event_id: Option<String>,
+    pub starts_at: NaiveDateTime,
+    pub starts_in_ms: u64,
+    pub transition_type: TransitionType,
+    pub transition_ms: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlaybackSnapshot {
+    pub now: NaiveDateTime,
+    pub now_showing: Option<NowShowing>,
+    pub up_next: Option<UpNext>,
+}
+
+// Position in the cycle layer
+struct CycleCursor {
+    index: usize,
+    // Monotonic time the current item came on screen
+    started_at: Duration,
+    // Set while a scheduled event has the screen; how far into the current
+    // item we were when it got paused
+    paused_elapsed: Option<Duration>,
+}
+
+// The looping layer: the playable cycle items and where we are in them
+struct CycleLayer {
+    items: Vec<CycleItem>,
+    cursor: Option<CycleCursor>,
+    // Used by items without their own duration
+    default_duration: Duration,
+}
+
+impl CycleLayer {
+    fn item_duration(&self, index: usize) -> Duration {
+        self.items
+            .get(index)
+            .and_then(|item| item.duration_seconds)
+            .map(|secs| Duration::seconds(secs as i64))
+            .unwrap_or(self.default_duration)
+    }
+
+    fn length(&self) -> Duration {
+        (0..self.items.len()).fold(Duration::zero(), |total, i| total + self.item_duration(i))
+    }
+
+    // Swaps in new items, staying on the current logo if it's still there
+    fn replace_items(&mut self, items: Vec<CycleItem>) {
+        if let Some(cursor) = self.cursor.as_mut() {
+            let current = self.items.get(cursor.index).map(|item| item.logo_id.clone());
+            match current.and_then(|id| items.iter().position(|item| item.logo_id == id)) {
+                Some(index) => cursor.index = index,
+                None => self.cursor = None,
+            }
+        }
+        self.items = items;
+    }
+
+    // Freezes the cycle as it was at `at` (the start of the overriding
+    // event), even if the first tick during the event comes later
+    fn pause(&mut self, at: Duration) {
+        if self.cursor.as_ref().is_some_and(|c| c.paused_elapsed.is_some()) {
+            return;
+        }
+        self.advance(at);
+        if let Some(cursor) = self.cursor.as_mut() {
+            cursor.paused_elapsed = Some((at - cursor.started_at).max(Duration::zero()));
+        }
+    }
+
+    fn advance(&mut self, now: Duration) {
+        if self.items.is_empty() {
+            self.cursor = None;
+            return;
+        }
+
+        let mut cursor = self.cursor.take().unwrap_or(CycleCursor {
+            index: 0,
+            started_at: now,
+            paused_elapsed: None,
+        });
+
+        // Resume exactly where the schedule layer interrupted us
+        if let Some(elapsed) = cursor.paused_elapsed.take() {
+            cursor.started_at = now - elapsed;
+        }
+        if cursor.index >= self.items.len() {
+            cursor.index = 0;
+        }
+
+        // Skip whole loops first so a long gap (e.g. sleep) stays cheap
+        let length = self.length();
+        if length > Duration::zero() && now - cursor.started_at > length {
+            let loops = (now - cursor.started_at).num_milliseconds() / length.num_milliseconds();
+            cursor.started_at += length * loops as i32;
+        }
+        loop {
+            let duration = self.item_duration(cursor.index);
+            if duration <= Duration::zero() || now < cursor.started_at + duration {
+                break;
+            }
+            cursor.started_at += duration;
+            cursor.index = (cursor.index + 1) % self.items.len();
+        }
+        self.cursor = Some(cursor);
+    }
+
+    fn showing(&self, now: NaiveDateTime, monotonic: Duration) -> Option<NowShowing> {
+        let cursor = self.cursor.as_ref()?;
+        let item = self.items.get(cursor.index)?;
+        let duration = self.item_duration(cursor.index);
+        let elapsed = monotonic - cursor.started_at;
+
+        Some(NowShowing {
+            layer: Layer::Cycle,
+            logo_id: item.logo_id.clone(),
+            name: item.name.clone(),
+            schedule_event_id: None,
+            started_at: now - elapsed,
+            duration_ms: Some(duration.num_milliseconds() as u64),
+            elapsed_ms: elapsed.num_milliseconds().max(0) as u64,
+            progress: Some(ratio(elapsed, duration)),
+            transition_type: item.transition_type,
+            transition_ms: item.transition_ms,
+        })
+    }
+
+    // When the current item is due to end, on the wall clock
+    fn current_end(&self, now: NaiveDateTime, monotonic: Duration) -> Option<NaiveDateTime> {
+        self.cursor
+            .as_ref()
+            .map(|c| now + (c.started_at + self.item_duration(c.index) - monotonic))
+    }
+
+    // The item that takes over at `at`: the paused one when resuming after
+    // the schedule layer, otherwise the one after the current item
+    fn next_item(&self, resuming: bool, at: NaiveDateTime, now: NaiveDateTime) -> Option<UpNext> {
+        let cursor = self.cursor.as_ref()?;
+        let index = if resuming {
+            cursor.index
+        } else {
+            (cursor.index + 1) % self.items.len()
+        };
+        let item = self.items.get(index)?;
+        Some(UpNext {
+            layer: Layer::Cycle,
+            logo_id: item.logo_id.clone(),
+            name: item.name.clone(),
+            schedule_event_id: None,
+            starts_at: at,
+            starts_in_ms: (at - now).num_milliseconds().max(0) as u64,
+            transition_type: item.transition_type,
+            transition_ms: item.transition_ms,
+        })
+    }
+}
+
+pub struct PlaybackEngine<C: Clock> {
+    clock: C,
+    cycle: CycleLayer,
+    schedule: Vec<ScheduleItem>,
+}
+
+impl<C: Clock> PlaybackEngine<C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            cycle: CycleLayer {
+                items: Vec::new(),
+                cursor: None,
+                default_duration: Duration::seconds(DEFAULT_CYCLE_ITEM_SECONDS as i64),
+            },
+            schedule: Vec::new(),
+        }
+    }
+
+    // Re-reads the cycle and schedule from the database
+    pub fn load(&mut self, conn: &Connection) -> AppResult<()> {
+        let cycle_repo = CycleRepo::new(conn);
+        let cycle = cycle_repo.list()?;
+        let settings = cycle_repo.settings()?;
+        let schedule = ScheduleRepo::new(conn).list()?;
+        self.set_default_duration(settings.default_duration_seconds);
+        self.set_data(cycle, schedule);
+        Ok(())
+    }
+
+    pub fn set_default_duration(&mut self, seconds: u32) {
+        self.cycle.default_duration = Duration::seconds(seconds as i64);
+    }
+
+    // Replaces the data the engine works from. Inactive and skipped items
+    // are dropped. The cycle keeps its position if the logo currently up is
+    // still part of it.
+    pub fn set_data(&mut self, cycle: Vec<CycleItem>, schedule: Vec<ScheduleItem>) {
+        self.cycle
+            .replace_items(cycle.into_iter().filter(CycleItem::is_playable).collect());
+        self.schedule = schedule;
+    }
+
+    // Advances to the clock's current time and reports what's showing
+    pub fn tick(&mut self) -> PlaybackSnapshot {
+        let now = self.clock.now();
+        let monotonic = self.clock.monotonic();
+        let occurrences = occurrences(&self.schedule);
+        let active = active_occurrence(&occurrences, now);
+
+        let now_showing = match active {
+            Some(occurrence) => {
+                self.cycle.pause(monotonic - (now - occurrence.start));
+                Some(showing_from_occurrence(occurrence, now))
+            }
+            None => {
+                self.cycle.advance(monotonic);
+                self.cycle.showing(now, monotonic)
+            }
+        };
+
+        let up_next = up_next(&self.cycle, &occurrences, active, now, monotonic);
+
+        PlaybackSnapshot { now, now_showing, up_next }
+    }
+}
+
+fn up_next(
+    cycle: &CycleLayer,
+    occurrences: &[Occurrence],
+    active: Option<&Occurrence>,
+    now: NaiveDateTime,
+    monotonic: Duration,
+) -> Option<UpNext> {
+    // The next event that will actually take the screen from the active one
+    let next_event = occurrences.iter().find(|o| {
+        o.start > now
+            && o.item.linked_logo_id.is_some()
+            && active.is_none_or(|a| {
+                o.item.priority >= a.item.priority || a.end.is_some_and(|end| o.start >= end)
+            })
+    });
+
+    // When whatever is on screen now ends, if known
+    let current_end = match active {
+        Some(occurrence) => occurrence.end,
+        None => cycle.current_end(now, monotonic),
+    };
+
+    if let Some(event) = next_event {
+        if current_end.is_none_or(|end| event.start <= end) {
+            return Some(up_next_from_occurrence(event, now));
+        }
+    }
+
+    cycle.next_item(active.is_some(), current_end?, now)
+}
+
+// The running event with a logo; if several overlap the highest priority
+// wins, then the latest start
+fn active_occurrence<'a, 'b>(occurrences: &'b [Occurrence<'a>], now: NaiveDateTime) -> Option<&'b Occurrence<'a>> {
+    schedule::winner(
+        occurrences
+            .iter()
+            .filter(|o| o.item.linked_logo_id.is_some())
+            .filter(|o| o.start <= now && o.end.is_none_or(|end| now < end)),
+    )
+}
+
+fn showing_from_occurrence(occurrence: &Occurrence, now: NaiveDateTime) -> NowShowing {
+    let elapsed = now - occurrence.start;
+    let duration = occurrence.end.map(|end| end - occurrence.start);
+    NowShowing {
+        layer: Layer::Schedule,
+        logo_id: occurrence.item.linked_logo_id.clone().unwrap_or_default(),
+        name: occurrence.item.name.clone(),
+        schedule_event_id: Some(occurrence.item.id.clone()),
+        started_at: occurrence.start,
+        duration_ms: duration.map(|d| d.num_milliseconds() as u64),
+        elapsed_ms: elapsed.num_milliseconds().max(0) as u64,
+        progress: duration.map(|d| ratio(elapsed, d)),
+        transition_type: TransitionType::Cut,
+        transition_ms: 0,
+    }
+}
+
+fn up_next_from_occurrence(occurrence: &Occurrence, now: NaiveDateTime) -> UpNext {
+    UpNext {
+        layer: Layer::Schedule,
+        logo_id: occurrence.item.linked_logo_id.clone().unwrap_or_default(),
+        name: occurrence.item.name.clone(),
+        schedule_event_id: Some(occurrence.item.id.clone()),
+        starts_at: occurrence.start,
+        starts_in_ms: (occurrence.start - now).num_milliseconds().max(0) as u64,
+        transition_type: TransitionType::Cut,
+        transition_ms: 0,
+    }
+}
+
+fn ratio(elapsed: Duration, total: Duration) -> f64 {
+    if total <= Duration::zero() {
+        return 1.0;
+    }
+    (elapsed.num_milliseconds() as f64 / total.num_milliseconds() as f64).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CycleItemConfig, CycleStatus};
+    use chrono::NaiveDate;
+
+    fn at(h: u32, m: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(h, m, s).unwrap()
+    }
+
+    fn cycle(names: &[&str]) -> Vec<CycleItem> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| CycleItem {
+                id: n.to_string(),
+                logo_id: n.to_string(),
+                name: n.to_string(),
+                status: CycleStatus::Cycle,
+                order_index: i as u32,
+                duration_seconds: None,
+                transition_type: TransitionType::Cut,
+                transition_ms: 0,
+                skip: false,
+            })
+            .collect()
+    }
+
+    fn event(id: &str, start: NaiveDateTime, duration_seconds: Option<i64>, logo: Option<&str>) -> ScheduleItem {
+        ScheduleItem {
+            id: id.to_string(),
+            starts_at: start,
+            ends_at: duration_seconds.map(|secs| start + Duration::seconds(secs)),
+            name: id.to_string(),
+            event_type: "dj_set".to_string(),
+            linked_logo_id: logo.map(str::to_string),
+            priority: 0,
+        }
+    }
+
+    fn engine(start: NaiveDateTime) -> (PlaybackEngine<ManualClock>, ManualClock) {
+        let clock = ManualClock::new(start);
+        (PlaybackEngine::new(clock.clone()), clock)
+    }
+
+    fn showing(engine: &mut PlaybackEngine<ManualClock>) -> Option<String> {
+        engine.tick().now_showing.map(|n| n.logo_id)
+    }
+
+    #[test]
+    fn nothing_to_show_without_data() {
+        let (mut engine, _) = engine(at(22, 0, 0));
+        let snapshot = engine.tick();
+        assert_eq!(snapshot.now_showing, None);
+        assert_eq!(snapshot.up_next, None);
+    }
+
+    #[test]
+    fn cycle_loops_through_items() {
+        let (mut engine, clock) = engine(at(22, 0, 0));
+        engine.set_data(cycle(&["a", "b", "c"]), vec![]);
+
+        assert_eq!(showing(&mut engine).as_deref(), Some("a"));
+        clock.advance(Duration::seconds(5));
+        let snapshot = engine.tick();
+        let now = snapshot.now_showing.unwrap();
+        assert_eq!(now.layer, Layer::Cycle);
+        assert_eq!(now.elapsed_ms, 5_000);
+        assert_eq!(now.progress, Some(0.5));
+        assert_eq!(snapshot.up_next.unwrap().logo_id, "b");
+
+        clock.advance(Duration::seconds(5));
+        assert_eq!(showing(&mut engine).as_deref(), Some("b"));
+        clock.advance(Duration::seconds(20));
+        assert_eq!(showing(&mut engine).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn long_gap_lands_on_the_right_item() {
+        let (mut engine, clock) = engine(at(22, 0, 0));
+        engine.set_data(cycle(&["a", "b", "c"]), vec![]);
+        engine.tick();
+
+        // 10 full loops (300s) plus 15s
+        clock.advance(Duration::seconds(315));
+        let now = engine.tick().now_showing.unwrap();
+        assert_eq!(now.logo_id, "b");
+        assert_eq!(now.elapsed_ms, 5_000);
+    }
+
+    #[test]
+    fn scheduled_event_overrides_cycle_and_cycle_resumes() {
+        let (mut engine, clock) = engine(at(22, 59, 56));
+        engine.set_data(cycle(&["a", "b"]), vec![event("kroma", at(23, 0, 0), Some(60), Some("kroma-logo"))]);
+
+        // 4s into "a"; the event starts before "a" would end
+        let snapshot = engine.tick();
+        assert_eq!(snapshot.now_showing.unwrap().logo_id, "a");
+        let next = snapshot.up_next.unwrap();
+        assert_eq!(next.layer, Layer::Schedule);
+        assert_eq!(next.starts_in_ms, 4_000);
+
+        clock.set(at(23, 0, 30));
+        let snapshot = engine.tick();
+        let now = snapshot.now_showing.unwrap();
+        assert_eq!(now.layer, Layer::Schedule);
+        assert_eq!(now.logo_id, "kroma-logo");
+        assert_eq!(now.schedule_event_id.as_deref(), Some("kroma"));
+        assert_eq!(now.progress, Some(0.5));
+        // "a" comes back when the set ends
+        let next = snapshot.up_next.unwrap();
+        assert_eq!(next.logo_id, "a");
+        assert_eq!(next.starts_at, at(23, 1, 0));
+
+        // Back on "a" with the 4s it had already shown
+        clock.set(at(23, 1, 0));
+        let now = engine.tick().now_showing.unwrap();
+        assert_eq!(now.layer, Layer::Cycle);
+        assert_eq!(now.logo_id, "a");
+        assert_eq!(now.elapsed_ms, 4_000);
+    }
+
+    #[test]
+    fn events_without_logo_do_not_override() {
+        let (mut engine, _) = engine(at(23, 0, 30));
+        engine.set_data(cycle(&["a"]), vec![event("doors", at(23, 0, 0), Some(600), None)]);
+        assert_eq!(engine.tick().now_showing.unwrap().layer, Layer::Cycle);
+    }
+
+    #[test]
+    fn open_ended_event_runs_until_the_next_one() {
+        let (mut engine, clock) = engine(at(22, 30, 0));
+        engine.set_data(
+            cycle(&["a"]),
+            vec![
+                event("warmup", at(22, 0, 0), None, Some("warmup-logo")),
+                event("headliner", at(23, 0, 0), Some(3600), Some("headliner-logo")),
+            ],
+        );
+
+        let snapshot = engine.tick();
+        let now = snapshot.now_showing.unwrap();
+        assert_eq!(now.logo_id, "warmup-logo");
+        assert_eq!(now.duration_ms, Some(3_600_000));
+        assert_eq!(snapshot.up_next.unwrap().logo_id, "headliner-logo");
+
+        clock.set(at(23, 10, 0));
+        assert_eq!(showing(&mut engine).as_deref(), Some("headliner-logo"));
+    }
+
+    #[test]
+    fn event_runs_past_midnight() {
+        let (mut engine, _) = engine(at(0, 15, 0) + Duration::days(1));
+        engine.set_data(cycle(&["a"]), vec![event("late", at(23, 30, 0), Some(3600), Some("late-logo"))]);
+
+        let now = engine.tick().now_showing.unwrap();
+        assert_eq!(now.logo_id, "late-logo");
+        assert_eq!(now.elapsed_ms, 45 * 60 * 1000);
+    }
+
+    #[test]
+    fn reload_keeps_position_of_current_logo() {
+        let (mut engine, clock) = engine(at(22, 0, 0));
+        engine.set_data(cycle(&["a", "b", "c"]), vec![]);
+        engine.tick();
+        clock.advance(Duration::seconds(12));
+        assert_eq!(showing(&mut engine).as_deref(), Some("b"));
+
+        engine.set_data(cycle(&["c", "b"]), vec![]);
+        let now = engine.tick().now_showing.unwrap();
+        assert_eq!(now.logo_id, "b");
+        assert_eq!(now.elapsed_ms, 2_000);
+
+        // Current logo removed: start over from the top
+        engine.set_data(cycle(&["c"]), vec![]);
+        assert_eq!(showing(&mut engine).as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn loads_from_database() {
+        let conn = crate::db::open_in_memory().unwrap();
+        let logos = crate::repo::LogoRepo::new(&conn);
+        let a = logos.add("A", "/logos/a.png", None).unwrap();
+        let b = logos.add("B", "/logos/b.png", None).unwrap();
+        CycleRepo::new(&conn).set(&[b.clone(), a]).unwrap();
+
+        let (mut engine, _) = engine(at(22, 0, 0));
+        engine.load(&conn).unwrap();
+        assert_eq!(showing(&mut engine), Some(b));
+    }
+
+    #[test]
+    fn items_use_their_own_duration_and_transition() {
+        let (mut engine, clock) = engine(at(22, 0, 0));
+        let mut items = cycle(&["a", "b"]);
+        items[0].duration_seconds = Some(30);
+        items[1].transition_type = TransitionType::Fade;
+        items[1].transition_ms = 800;
+        engine.set_data(items, vec![]);
+
+        let snapshot = engine.tick();
+        assert_eq!(snapshot.now_showing.unwrap().duration_ms, Some(30_000));
+        let next = snapshot.up_next.unwrap();
+        assert_eq!(next.starts_at, at(22, 0, 30));
+        assert_eq!(next.transition_type, TransitionType::Fade);
+
+        clock.advance(Duration::seconds(35));
+        let now = engine.tick().now_showing.unwrap();
+        assert_eq!(now.logo_id, "b");
+        assert_eq!(now.duration_ms, Some(10_000));
+        assert_eq!(now.transition_ms, 800);
+    }
+
+    #[test]
+    fn inactive_and_skipped_items_are_not_shown() {
+        let (mut engine, clock) = engine(at(22, 0, 0));
+        let mut items = cycle(&["a", "b", "c"]);
+        items[1].status = CycleStatus::Inactive;
+        items[2].skip = true;
+        engine.set_data(items, vec![]);
+
+        assert_eq!(showing(&mut engine).as_deref(), Some("a"));
+        clock.advance(Duration::seconds(15));
+        assert_eq!(showing(&mut engine).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn default_duration_comes_from_settings() {
+        let conn = crate::db::open_in_memory().unwrap();
+        let logos = crate::repo::LogoRepo::new(&conn);
+        let a = logos.add("A", "/logos/a.png", None).unwrap();
+        let b = logos.add("B", "/logos/b.png", None).unwrap();
+        let repo = CycleRepo::new(&conn);
+        repo.set_items(&[
+            CycleItemConfig::new(&a),
+            CycleItemConfig {
+                duration_seconds: Some(5),
+                ..CycleItemConfig::new(&b)
+            },
+        ])
+        .unwrap();
+        repo.set_default_duration(20).unwrap();
+
+        let (mut engine, clock) = engine(at(22, 0, 0));
+        engine.load(&conn).unwrap();
+        assert_eq!(engine.tick().now_showing.unwrap().duration_ms, Some(20_000));
+        clock.advance(Duration::seconds(22));
+        let now = engine.tick().now_showing.unwrap();
+        assert_eq!(now.logo_id, b);
+        assert_eq!(now.duration_ms, Some(5_000));
+    }
+
+    #[test]
+    fn higher_priority_event_wins_an_overlap() {
+        let (mut engine, clock) = engine(at(23, 10, 0));
+        let mut sponsor = event("sponsor", at(23, 15, 0), Some(300), Some("sponsor-logo"));
+        sponsor.priority = -1;
+        let mut stinger = event("stinger", at(23, 20, 0), Some(60), Some("stinger-logo"));
+        stinger.priority = 5;
+        engine.set_data(
+            cycle(&["a"]),
+            vec![event("kroma", at(23, 0, 0), Some(3600), Some("kroma-logo")), sponsor, stinger],
+        );
+
+        // The low-priority sponsor slot never takes over, so it isn't up next
+        let snapshot = engine.tick();
+        assert_eq!(snapshot.now_showing.unwrap().logo_id, "kroma-logo");
+        assert_eq!(snapshot.up_next.unwrap().logo_id, "stinger-logo");
+
+        clock.set(at(23, 16, 0));
+        assert_eq!(showing(&mut engine).as_deref(), Some("kroma-logo"));
+        clock.set(at(23, 20, 30));
+        assert_eq!(showing(&mut engine).as_deref(), Some("stinger-logo"));
+        clock.set(at(23, 21, 0));
+        assert_eq!(showing(&mut engine).as_deref(), Some("kroma-logo"));
+    }
+
+    #[test]
+    fn cycle_keeps_going_when_the_wall_clock_turns_back() {
+        // 03:00 summer time becomes 02:00 again on 25 October 2026
+        let start = NaiveDate::from_ymd_opt(2026, 10, 25).unwrap().and_hms_opt(2, 59, 55).unwrap();
+        let (mut engine, clock) = engine(start);
+        engine.set_data(cycle(&["a", "b", "c"]), vec![]);
+        assert_eq!(showing(&mut engine).as_deref(), Some("a"));
+
+        clock.advance(Duration::seconds(5));
+        clock.set(start + Duration::seconds(5) - Duration::hours(1));
+        let now = engine.tick().now_showing.unwrap();
+        assert_eq!((now.logo_id.as_str(), now.elapsed_ms), ("a", 5_000));
+        assert_eq!(now.started_at, start - Duration::hours(1));
+
+        clock.advance(Duration::seconds(6));
+        assert_eq!(showing(&mut engine).as_deref(), Some("b"));
+        clock.advance(Duration::seconds(10));
+        assert_eq!(showing(&mut engine).as_deref(), Some("c"));
+    }
+}
@@ -0,0 +1,318 @@
+//! Finding logos whose files are gone, and pointing them at the files' new
+//! location: either by swapping one root folder for another (the library
+//! moved to another drive or machine) or by searching a folder for files
+//! with the same content or name.
+
+use crate::duplicates::content_hash;
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::library::{path_components, scan_dir, strip_root, LibraryFile};
+use crate::models::{Logo, RelinkReport, RelinkedLogo, UnresolvedLogo};
+use crate::repo::LogoRepo;
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// Checks every logo's file and updates the `missing` flags. Returns the
+// logos whose file is missing.
+pub fn check_files(conn: &Connection) -> AppResult<Vec<Logo>> {
+    let checked = stat_files(LogoRepo::new(conn).list()?);
+    record_missing(conn, checked)
+}
+
+// Pairs each logo with whether its file is there. Only touches the disk, so
+// callers can run it without holding the database.
+pub fn stat_files(logos: Vec<Logo>) -> Vec<(Logo, bool)> {
+    logos
+        .into_iter()
+        .map(|logo| {
+            let exists = Path::new(&logo.file_path).is_file();
+            (logo, exists)
+        })
+        .collect()
+}
+
+// Writes what `stat_files` found to the `missing` flags, in one
+// transaction. Returns the logos whose file is missing.
+pub fn record_missing(conn: &Connection, checked: Vec<(Logo, bool)>) -> AppResult<Vec<Logo>> {
+    let tx = conn.unchecked_transaction()?;
+    let repo = LogoRepo::new(conn);
+    let mut missing = Vec::new();
+
+    for (mut logo, exists) in checked {
+        if logo.missing == exists {
+            match repo.set_missing(&logo.id, !exists) {
+                // Deleted since it was listed
+                Err(e) if e.kind == ErrorKind::NotFound => continue,
+                result => result?,
+            }
+        }
+        if !exists {
+            logo.missing = true;
+            missing.push(logo);
+        }
+    }
+
+    tx.commit()?;
+    Ok(missing)
+}
+
+// Rewrites every logo stored under `old_root` to the same place under
+// `new_root`. Logos whose file isn't there are reported and left as they
+// were. Runs in one transaction.
+pub fn relink_root(conn: &Connection, old_root: &str, new_root: &Path) -> AppResult<RelinkReport> {
+    if path_components(old_root).is_empty() {
+        return Err(AppError::invalid("old_root", "Old folder can't be empty"));
+    }
+    if !new_root.is_dir() {
+        return Err(AppError::invalid(
+            "new_root",
+            format!("Folder '{}' doesn't exist", new_root.display()),
+        ));
+    }
+    let tx = conn.unchecked_transaction()?;
+    let repo = LogoRepo::new(conn);
+    let mut report = RelinkReport::default();
+
+    for logo in repo.list()? {
+        let Some(parts) = strip_root(&logo.file_path, old_root) else {
+            continue;
+        };
+        let new_path = parts.iter().fold(new_root.to_path_buf(), |path, part| path.join(part));
+        if new_path.is_file() {
+            relink(&repo, &logo, &new_path, &mut report)?;
+        } else {
+            let reason = format!("'{}' doesn't exist", new_path.display());
+            report.unresolved.push(unresolved(&logo, reason));
+        }
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
+// Looks in `folder` (and below) for the file of every logo whose file is
+// missing: first a file with the same content, then one with the same
+// name. A name that matches several files is reported instead of guessed.
+// Runs in one transaction.
+pub fn relink_search(conn: &Connection, folder: &Path) -> AppResult<RelinkReport> {
+    let files = scan_dir(folder).map_err(|e| e.with_field("folder"))?;
+    let tx = conn.unchecked_transaction()?;
+    let repo = LogoRepo::new(conn);
+    let mut report = RelinkReport::default();
+    let mut finder = Finder {
+        files,
+        claimed: HashSet::new(),
+        hashes: HashMap::new(),
+    };
+
+    for logo in repo.list()? {
+        if Path::new(&logo.file_path).is_file() {
+            continue;
+        }
+        match finder.find(&logo) {
+            Ok(path) => {
+                finder.claimed.insert(path.clone());
+                relink(&repo, &logo, &path, &mut report)?;
+            }
+            Err(reason) => report.unresolved.push(unresolved(&logo, reason)),
+        }
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
+struct Finder {
+    files: Vec<LibraryFile>,
+    // Files already given to a logo in this run
+    claimed: HashSet<PathBuf>,
+    // Content hashes computed so far, so each file is read at most once
+    hashes: HashMap<PathBuf, String>,
+}
+
+impl Finder {
+    fn find(&mut self, logo: &Logo) -> Result<PathBuf, String> {
+        if let Some(path) = self.find_by_content(logo) {
+            return Ok(path);
+        }
+        let name = path_components(&logo.file_path).last().copied().unwrap_or_default();
+        let matches: Vec<&LibraryFile> = self
+            .unclaimed()
+            .filter(|f| f.path.file_name().is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(name)))
+            .collect();
+        match matches.as_slice() {
+            [file] => Ok(file.path.clone()),
+            [] => Err(format!("No file named '{}' found", name)),
+            many => Err(format!("{} files named '{}' found", many.len(), name)),
+        }
+    }
+
+    fn find_by_content(&mut self, logo: &Logo) -> Option<PathBuf> {
+        let wanted = logo.content_hash.as_deref()?;
+        // Only files of the right size can match, if the size is known
+        let candidates: Vec<PathBuf> = self
+            .unclaimed()
+            .filter(|f| logo.file_size.is_none_or(|size| size == f.size))
+            .map(|f| f.path.clone())
+            .collect();
+        candidates.into_iter().find(|path| {
+            if !self.hashes.contains_key(path) {
+                let Ok(hash) = content_hash(path) else { return false };
+                self.hashes.insert(path.clone(), hash);
+            }
+            self.hashes[path] == wanted
+        })
+    }
+
+    fn unclaimed(&self) -> impl Iterator<Item = &LibraryFile> {
+        self.files.iter().filter(|f| !self.claimed.contains(&f.path))
+    }
+}
+
+// Points `logo` at `new_path`. If a library scan already picked the file up
+// as a logo of its own, that one is merged into `logo`, which keeps its
+// artist links, cycle entries and schedule events.
+fn relink(repo: &LogoRepo, logo: &Logo, new_path: &Path, report: &mut RelinkReport) -> AppResult<()> {
+    let path = new_path.to_string_lossy().into_owned();
+    if let Some(other) = repo.find_id_by_path(&path)? {
+        if other == logo.id {
+            return Ok(());
+        }
+        repo.merge_into(&logo.id, &[other])?;
+    }
+    repo.update(&logo.id, &logo.name, &path, logo.thumbnail_path.as_deref())?;
+    match LibraryFile::read(new_path) {
+        Ok(file) => repo.set_file_state(&logo.id, Some(file.size), file.modified, false)?,
+        Err(_) => repo.set_missing(&logo.id, false)?,
+    }
+    report.relinked.push(RelinkedLogo {
+        id: logo.id.clone(),
+        name: logo.name.clone(),
+        old_path: logo.file_path.clone(),
+        new_path: path,
+    });
+    Ok(())
+}
+
+fn unresolved(logo: &Logo, reason: String) -> UnresolvedLogo {
+    UnresolvedLogo {
+        id: logo.id.clone(),
+        name: logo.name.clone(),
+        file_path: logo.file_path.clone(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::duplicates;
+    use crate::repo::ArtistRepo;
+    use std::fs;
+
+    fn write(path: &Path, bytes: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    fn path_str(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn check_flags_missing_files() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LogoRepo::new(&conn);
+        write(&dir.path().join("here.png"), b"png");
+        repo.add("here", &path_str(&dir.path().join("here.png")), None).unwrap();
+        let gone = repo.add("gone", "/nowhere/gone.png", None).unwrap();
+
+        let missing = check_files(&conn).unwrap();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].id, gone);
+        assert!(repo.list().unwrap().iter().find(|l| l.id == gone).unwrap().missing);
+
+        // A logo deleted between the check and the write is left out
+        let also_gone = repo.add("also gone", "/nowhere/also_gone.png", None).unwrap();
+        let checked = stat_files(repo.list().unwrap());
+        repo.delete(&also_gone).unwrap();
+        let missing = record_missing(&conn, checked).unwrap();
+        assert_eq!(missing.iter().map(|l| l.id.as_str()).collect::<Vec<_>>(), [gone.as_str()]);
+    }
+
+    #[test]
+    fn relinks_a_moved_root_and_reports_the_rest() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let kroma = repo.add("kroma", "D:\\Logos\\kroma\\kroma.png", None).unwrap();
+        let nyx = repo.add("nyx", "D:\\Logos\\nyx.png", None).unwrap();
+        repo.add("external", "/elsewhere/external.png", None).unwrap();
+        write(&dir.path().join("kroma/kroma.png"), b"png");
+
+        let report = relink_root(&conn, "D:\\Logos", dir.path()).unwrap();
+        assert_eq!(report.relinked.len(), 1);
+        assert_eq!(report.relinked[0].id, kroma);
+        assert_eq!(report.relinked[0].new_path, path_str(&dir.path().join("kroma/kroma.png")));
+        assert_eq!(report.unresolved.len(), 1);
+        assert_eq!(report.unresolved[0].id, nyx);
+
+        let err = relink_root(&conn, "D:\\Logos", &dir.path().join("missing")).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("new_root"));
+    }
+
+    #[test]
+    fn search_matches_by_content_then_by_name() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LogoRepo::new(&conn);
+
+        // Hashed while it still existed, then renamed on the way over
+        let old = dir.path().join("old/kroma.png");
+        write(&old, b"kroma logo");
+        let kroma = repo.add("kroma", &path_str(&old), None).unwrap();
+        for job in duplicates::pending(&conn).unwrap() {
+            duplicates::store(&conn, &job, &duplicates::compute(&job).unwrap()).unwrap();
+        }
+        fs::remove_file(&old).unwrap();
+        write(&dir.path().join("usb/KROMA_final.png"), b"kroma logo");
+
+        repo.add("nyx", "/old/nyx.png", None).unwrap();
+        write(&dir.path().join("usb/sets/NYX.png"), b"nyx logo");
+        repo.add("echo", "/old/echo.png", None).unwrap();
+        write(&dir.path().join("usb/a/echo.png"), b"echo a");
+        write(&dir.path().join("usb/b/echo.png"), b"echo b");
+
+        let report = relink_search(&conn, &dir.path().join("usb")).unwrap();
+        let relinked: HashMap<&str, &str> = report.relinked.iter().map(|r| (r.name.as_str(), r.new_path.as_str())).collect();
+        assert_eq!(relinked["kroma"], path_str(&dir.path().join("usb/KROMA_final.png")));
+        assert_eq!(relinked["nyx"], path_str(&dir.path().join("usb/sets/NYX.png")));
+        assert_eq!(report.unresolved.len(), 1);
+        assert_eq!(report.unresolved[0].reason, "2 files named 'echo.png' found");
+        assert!(!repo.list().unwrap().iter().find(|l| l.id == kroma).unwrap().missing);
+    }
+
+    #[test]
+    fn relinking_onto_a_scanned_copy_keeps_the_original_links() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let artist = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        let original = repo.add("Kroma", "/old/kroma.png", None).unwrap();
+        repo.link_artist(&artist, &original, false).unwrap();
+        let new_path = dir.path().join("kroma.png");
+        write(&new_path, b"png");
+        repo.add("kroma", &path_str(&new_path), None).unwrap();
+
+        let report = relink_root(&conn, "/old", dir.path()).unwrap();
+        assert_eq!(report.relinked.len(), 1);
+
+        let logos = repo.list().unwrap();
+        assert_eq!(logos.len(), 1);
+        assert_eq!(logos[0].id, original);
+        assert_eq!(logos[0].file_path, path_str(&new_path));
+        assert_eq!(logos[0].linked_djs, ["KROMA"]);
+    }
+}
@@ -0,0 +1,567 @@
+use super::{ensure_exists, expect_one, KEEP_OVERRIDE, MARK_MODIFIED};
+use crate::artist_match::match_key;
+use crate::current_timestamp;
+use crate::error::{AppError, AppResult};
+use crate::models::{Artist, ArtistProfile, SocialHandle};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub struct ArtistRepo<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> ArtistRepo<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    // Inserts a new artist and returns its generated id
+    pub fn add(&self, name: &str) -> AppResult<String> {
+        let new_id = Uuid::new_v4().to_string();
+        let now = current_timestamp();
+
+        self.conn.execute(
+            "INSERT INTO artists (id, name, created_at, updated_at, sync_status) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![new_id, name, now, now, "new"],
+        )?;
+        Ok(new_id)
+    }
+
+    // All artists that haven't been deleted, alphabetically
+    pub fn list(&self) -> AppResult<Vec<Artist>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, vjtools_id, created_at, updated_at, sync_status,
+                    display_name, pronunciation, brand_color, preferred_logo_id, notes
+             FROM artists WHERE sync_status != 'deleted' ORDER BY name ASC",
+        )?;
+        let mut artists: Vec<Artist> = stmt.query_map([], map_artist)?.collect::<rusqlite::Result<_>>()?;
+        let mut aliases = self.aliases_by_artist()?;
+        let mut socials = self.socials_by_artist()?;
+        for artist in &mut artists {
+            artist.aliases = aliases.remove(&artist.id).unwrap_or_default();
+            artist.profile.socials = socials.remove(&artist.id).unwrap_or_default();
+        }
+        Ok(artists)
+    }
+
+    pub fn update(&self, id: &str, name: &str) -> AppResult<()> {
+        let changed = self.conn.execute(
+            &format!(
+                "UPDATE artists SET name = ?2, updated_at = ?3, {} WHERE id = ?1 AND sync_status != 'deleted'",
+                MARK_MODIFIED
+            ),
+            params![id, name, current_timestamp()],
+        )?;
+        expect_one(changed, "Artist", id)
+    }
+
+    // Replaces the artist's profile. Blank text fields are stored as unset;
+    // the brand colour must be '#rgb' or '#rrggbb' and is stored as
+    // '#rrggbb'; the preferred logo must exist; platforms are lowercased
+    // and may each appear once.
+    pub fn update_profile(&self, id: &str, profile: &ArtistProfile) -> AppResult<()> {
+        let brand_color = profile.brand_color.as_deref().and_then(blank_to_none).map(normalize_color).transpose()?;
+        if let Some(logo_id) = &profile.preferred_logo_id {
+            ensure_exists(self.conn, "logos", "Logo", logo_id).map_err(|e| e.with_field("preferred_logo_id"))?;
+        }
+        let mut socials: Vec<SocialHandle> = Vec::with_capacity(profile.socials.len());
+        for social in &profile.socials {
+            let platform = social.platform.trim().to_lowercase();
+            let handle = social.handle.trim();
+            if platform.is_empty() || handle.is_empty() {
+                return Err(AppError::invalid("socials", "Social handles need a platform and a handle"));
+            }
+            if socials.iter().any(|s| s.platform == platform) {
+                return Err(AppError::invalid("socials", format!("More than one {} handle", platform)));
+            }
+            socials.push(SocialHandle { platform, handle: handle.to_string() });
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let changed = self.conn.execute(
+            &format!(
+                "UPDATE artists
+                 SET display_name = ?2, pronunciation = ?3, brand_color = ?4, preferred_logo_id = ?5, notes = ?6,
+                     updated_at = ?7, {}
+                 WHERE id = ?1 AND sync_status != 'deleted'",
+                MARK_MODIFIED
+            ),
+            params![
+                id,
+                profile.display_name.as_deref().and_then(blank_to_none),
+                profile.pronunciation.as_deref().and_then(blank_to_none),
+                brand_color,
+                profile.preferred_logo_id,
+                profile.notes.as_deref().and_then(blank_to_none),
+                current_timestamp()
+            ],
+        )?;
+        expect_one(changed, "Artist", id)?;
+        self.conn.execute("DELETE FROM artist_socials WHERE artist_id = ?1", [id])?;
+        for social in &socials {
+            self.conn.execute(
+                "INSERT INTO artist_socials (artist_id, platform, handle) VALUES (?1, ?2, ?3)",
+                params![id, social.platform, social.handle],
+            )?;
+        }
+        Ok(tx.commit()?)
+    }
+
+    // Deletes an artist with its logo links and aliases. Synced artists stay
+    // behind as a tombstone; their links are removed just like ON DELETE
+    // CASCADE would.
+    pub fn delete(&self, id: &str) -> AppResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.remove(id)?;
+        Ok(tx.commit()?)
+    }
+
+    // Records another name the artist appears under. An alias that is
+    // already the artist's name (ignoring case, accents, "DJ" and the like)
+    // is accepted and not stored; one that names another artist, or is
+    // another artist's alias, is a Duplicate error carrying that artist's id.
+    pub fn add_alias(&self, artist_id: &str, alias: &str) -> AppResult<()> {
+        let alias = alias.trim();
+        let key = match_key(alias);
+        if key.is_empty() {
+            return Err(AppError::invalid("alias", "Alias can't be empty"));
+        }
+        ensure_exists(self.conn, "artists", "Artist", artist_id)?;
+
+        let owner = match self.list()?.into_iter().find(|a| match_key(&a.name) == key) {
+            Some(named) => Some(named.id),
+            None => self
+                .conn
+                .query_row("SELECT artist_id FROM artist_aliases WHERE match_key = ?1", [&key], |row| row.get(0))
+                .optional()?,
+        };
+        match owner {
+            Some(owner) if owner == artist_id => Ok(()),
+            Some(owner) => Err(AppError::duplicate(
+                "alias",
+                &owner,
+                format!("'{}' already refers to another artist", alias),
+            )),
+            None => {
+                self.conn.execute(
+                    "INSERT INTO artist_aliases (artist_id, alias, match_key, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![artist_id, alias, key, current_timestamp()],
+                )?;
+                Ok(())
+            }
+        }
+    }
+
+    // Removes an alias, matched by its match key so any spelling of it works
+    pub fn remove_alias(&self, artist_id: &str, alias: &str) -> AppResult<()> {
+        let changed = self.conn.execute(
+            "DELETE FROM artist_aliases WHERE artist_id = ?1 AND match_key = ?2",
+            params![artist_id, match_key(alias)],
+        )?;
+        expect_one(changed, "Artist alias", alias)
+    }
+
+    // Folds duplicate artists into `keep_id`: their aliases and logo links
+    // move over, their names become aliases, profile details the kept
+    // artist lacks are filled from them, and schedule events named after
+    // them are renamed to the kept artist. The duplicates are then deleted.
+    // Runs in one transaction.
+    pub fn merge(&self, keep_id: &str, duplicate_ids: &[String]) -> AppResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let keep_name = self.live_name(keep_id)?;
+        if duplicate_ids.iter().any(|id| id == keep_id) {
+            return Err(AppError::invalid("duplicate_ids", "An artist can't be merged into itself").with_entity_id(keep_id));
+        }
+        let keep_key = match_key(&keep_name);
+        let now = current_timestamp();
+
+        for id in duplicate_ids {
+            let name = self.live_name(id)?;
+            let key = match_key(&name);
+            self.conn.execute("UPDATE artist_aliases SET artist_id = ?2 WHERE artist_id = ?1", params![id, keep_id])?;
+            if !key.is_empty() && key != keep_key {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO artist_aliases (artist_id, alias, match_key, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![keep_id, name, key, now],
+                )?;
+            }
+            self.conn.execute(
+                &format!(
+                    "INSERT INTO artist_logos (artist_id, logo_id, is_local_override)
+                     SELECT ?2, logo_id, is_local_override FROM artist_logos WHERE artist_id = ?1 {}",
+                    KEEP_OVERRIDE
+                ),
+                params![id, keep_id],
+            )?;
+            self.conn.execute("DELETE FROM artist_logos WHERE artist_id = ?1", [id])?;
+            self.conn.execute(
+                &format!(
+                    "UPDATE artists SET
+                        display_name = COALESCE(display_name, (SELECT display_name FROM artists WHERE id = ?1)),
+                        pronunciation = COALESCE(pronunciation, (SELECT pronunciation FROM artists WHERE id = ?1)),
+                        brand_color = COALESCE(brand_color, (SELECT brand_color FROM artists WHERE id = ?1)),
+                        preferred_logo_id = COALESCE(preferred_logo_id, (SELECT preferred_logo_id FROM artists WHERE id = ?1)),
+                        notes = COALESCE(notes, (SELECT notes FROM artists WHERE id = ?1)),
+                        updated_at = ?3, {}
+                     WHERE id = ?2",
+                    MARK_MODIFIED
+                ),
+                params![id, keep_id, now],
+            )?;
+            self.conn.execute(
+                "INSERT OR IGNORE INTO artist_socials (artist_id, platform, handle)
+                 SELECT ?2, platform, handle FROM artist_socials WHERE artist_id = ?1",
+                params![id, keep_id],
+            )?;
+            if !key.is_empty() && key != keep_key {
+                self.rename_events(&key, &keep_name, now)?;
+            }
+            self.remove(id)?;
+        }
+        Ok(tx.commit()?)
+    }
+
+    // The body of `delete`, for callers that already hold a transaction
+    fn remove(&self, id: &str) -> AppResult<()> {
+        let removed = self.conn.execute("DELETE FROM artists WHERE id = ?1 AND sync_status = 'new'", [id])?;
+        if removed == 0 {
+            let changed = self.conn.execute(
+                "UPDATE artists SET sync_status = 'deleted', updated_at = ?2 WHERE id = ?1 AND sync_status != 'deleted'",
+                params![id, current_timestamp()],
+            )?;
+            expect_one(changed, "Artist", id)?;
+            self.conn.execute("DELETE FROM artist_logos WHERE artist_id = ?1", [id])?;
+            self.conn.execute("DELETE FROM artist_aliases WHERE artist_id = ?1", [id])?;
+            self.conn.execute("DELETE FROM artist_socials WHERE artist_id = ?1", [id])?;
+        }
+        Ok(())
+    }
+
+    // Schedule events refer to artists by name only, so events whose name
+    // has the match key `key` are renamed to `name`
+    fn rename_events(&self, key: &str, name: &str, now: u64) -> AppResult<()> {
+        let mut stmt = self.conn.prepare("SELECT id, name FROM schedule_events WHERE sync_status != 'deleted'")?;
+        let events = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (event_id, _) in events.iter().filter(|(_, event_name)| match_key(event_name) == key) {
+            self.conn.execute(
+                &format!("UPDATE schedule_events SET name = ?2, updated_at = ?3, {} WHERE id = ?1", MARK_MODIFIED),
+                params![event_id, name, now],
+            )?;
+        }
+        Ok(())
+    }
+
+    // Name of a live artist, NotFound for a missing or deleted one
+    fn live_name(&self, id: &str) -> AppResult<String> {
+        self.conn
+            .query_row("SELECT name FROM artists WHERE id = ?1 AND sync_status != 'deleted'", [id], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| AppError::not_found("Artist", id))
+    }
+
+    // artist_id -> its social handles, by platform
+    fn socials_by_artist(&self) -> AppResult<HashMap<String, Vec<SocialHandle>>> {
+        let mut stmt = self.conn.prepare("SELECT artist_id, platform, handle FROM artist_socials ORDER BY platform ASC")?;
+        let mut socials: HashMap<String, Vec<SocialHandle>> = HashMap::new();
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, SocialHandle { platform: row.get(1)?, handle: row.get(2)? }))
+        })?;
+        for row in rows {
+            let (artist_id, social) = row?;
+            socials.entry(artist_id).or_default().push(social);
+        }
+        Ok(socials)
+    }
+
+    // artist_id -> its aliases, alphabetically
+    fn aliases_by_artist(&self) -> AppResult<HashMap<String, Vec<String>>> {
+        let mut stmt = self.conn.prepare("SELECT artist_id, alias FROM artist_aliases ORDER BY alias COLLATE NOCASE ASC")?;
+        let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (artist_id, alias) = row?;
+            aliases.entry(artist_id).or_default().push(alias);
+        }
+        Ok(aliases)
+    }
+}
+
+fn map_artist(row: &Row) -> rusqlite::Result<Artist> {
+    Ok(Artist {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        vjtools_id: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+        sync_status: row.get(5)?,
+        aliases: Vec::new(),
+        profile: ArtistProfile {
+            display_name: row.get(6)?,
+            pronunciation: row.get(7)?,
+            brand_color: row.get(8)?,
+            preferred_logo_id: row.get(9)?,
+            socials: Vec::new(),
+            notes: row.get(10)?,
+        },
+    })
+}
+
+fn blank_to_none(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+// '#rgb' or '#rrggbb' in any case, as '#rrggbb'
+fn normalize_color(color: String) -> AppResult<String> {
+    let hex = color.strip_prefix('#').unwrap_or_default().to_lowercase();
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::invalid("brand_color", format!("'{}' isn't a colour like #ff6600", color)));
+    }
+    match hex.len() {
+        6 => Ok(format!("#{}", hex)),
+        3 => Ok(hex.chars().fold(String::from("#"), |mut out, c| {
+            out.push(c);
+            out.push(c);
+            out
+        })),
+        _ => Err(AppError::invalid("brand_color", format!("'{}' isn't a colour like #ff6600", color))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::error::ErrorKind;
+    use crate::models::ScheduleEventInput;
+    use crate::repo::{LogoRepo, ScheduleRepo};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn add_returns_id_and_marks_new() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+
+        let id = repo.add("KROMA").unwrap();
+        let artists = repo.list().unwrap();
+
+        assert_eq!(artists.len(), 1);
+        assert_eq!(artists[0].id, id);
+        assert_eq!(artists[0].name, "KROMA");
+        assert_eq!(artists[0].sync_status, "new");
+        assert_eq!(artists[0].vjtools_id, None);
+        assert_eq!(artists[0].created_at, artists[0].updated_at);
+    }
+
+    #[test]
+    fn list_is_sorted_by_name() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        repo.add("Zed").unwrap();
+        repo.add("Anna").unwrap();
+        repo.add("Mira").unwrap();
+
+        let names: Vec<String> = repo.list().unwrap().into_iter().map(|a| a.name).collect();
+        assert_eq!(names, vec!["Anna", "Mira", "Zed"]);
+    }
+
+    fn mark_synced(conn: &Connection, id: &str) {
+        conn.execute("UPDATE artists SET sync_status = 'synced', updated_at = 0 WHERE id = ?1", [id]).unwrap();
+    }
+
+    #[test]
+    fn update_keeps_unsynced_artist_new() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        let id = repo.add("Kroma").unwrap();
+
+        repo.update(&id, "KROMA").unwrap();
+
+        let artist = &repo.list().unwrap()[0];
+        assert_eq!(artist.name, "KROMA");
+        assert_eq!(artist.sync_status, "new");
+    }
+
+    #[test]
+    fn update_marks_synced_artist_modified_and_bumps_updated_at() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        let id = repo.add("Kroma").unwrap();
+        mark_synced(&conn, &id);
+
+        repo.update(&id, "KROMA").unwrap();
+
+        let artist = &repo.list().unwrap()[0];
+        assert_eq!(artist.sync_status, "modified");
+        assert!(artist.updated_at > 0);
+    }
+
+    #[test]
+    fn update_unknown_artist_is_not_found() {
+        let conn = open_in_memory().unwrap();
+        let err = ArtistRepo::new(&conn).update("missing", "x").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+        assert_eq!(err.entity_id.as_deref(), Some("missing"));
+    }
+
+    #[test]
+    fn delete_unsynced_artist_removes_row_and_links() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        let id = repo.add("KROMA").unwrap();
+        let logo = LogoRepo::new(&conn).add("Kroma", "/logos/kroma.png", None).unwrap();
+        LogoRepo::new(&conn).link_artist(&id, &logo, false).unwrap();
+
+        repo.delete(&id).unwrap();
+
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM artists", [], |r| r.get(0)).unwrap();
+        assert_eq!(rows, 0);
+        let links: i64 = conn.query_row("SELECT COUNT(*) FROM artist_logos", [], |r| r.get(0)).unwrap();
+        assert_eq!(links, 0);
+    }
+
+    #[test]
+    fn delete_synced_artist_leaves_tombstone_without_links() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        let id = repo.add("KROMA").unwrap();
+        let logo = LogoRepo::new(&conn).add("Kroma", "/logos/kroma.png", None).unwrap();
+        LogoRepo::new(&conn).link_artist(&id, &logo, false).unwrap();
+        mark_synced(&conn, &id);
+
+        repo.delete(&id).unwrap();
+
+        assert!(repo.list().unwrap().is_empty());
+        let status: String = conn.query_row("SELECT sync_status FROM artists WHERE id = ?1", [&id], |r| r.get(0)).unwrap();
+        assert_eq!(status, "deleted");
+        assert!(LogoRepo::new(&conn).list().unwrap()[0].linked_djs.is_empty());
+
+        // Deleted artists can't be edited or deleted again
+        assert!(repo.update(&id, "x").is_err());
+        assert!(repo.delete(&id).is_err());
+    }
+
+    #[test]
+    fn aliases_belong_to_one_artist() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        let kroma = repo.add("KROMA").unwrap();
+        let nyx = repo.add("Nyx").unwrap();
+
+        repo.add_alias(&kroma, " Chromatic ").unwrap();
+        // Its own name, in any spelling, isn't stored
+        repo.add_alias(&kroma, "DJ Kroma (live)").unwrap();
+        repo.add_alias(&kroma, "chromatic").unwrap();
+        assert_eq!(repo.list().unwrap()[0].aliases, ["Chromatic"]);
+
+        let err = repo.add_alias(&nyx, "Chromátic").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Duplicate);
+        assert_eq!(err.entity_id.as_deref(), Some(kroma.as_str()));
+        assert_eq!(repo.add_alias(&nyx, "kroma").unwrap_err().entity_id.as_deref(), Some(kroma.as_str()));
+        assert_eq!(repo.add_alias(&nyx, " () ").unwrap_err().field.as_deref(), Some("alias"));
+
+        repo.remove_alias(&kroma, "CHROMATIC").unwrap();
+        assert!(repo.list().unwrap()[0].aliases.is_empty());
+        assert_eq!(repo.remove_alias(&kroma, "Chromatic").unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn merge_moves_aliases_links_and_schedule_names() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        let logos = LogoRepo::new(&conn);
+        let kroma = repo.add("KROMA").unwrap();
+        let dj_kroma = repo.add("DJ Kromma").unwrap();
+        repo.add_alias(&dj_kroma, "Chromatic").unwrap();
+        repo.update_profile(&dj_kroma, &ArtistProfile { brand_color: Some("#112233".to_string()), ..ArtistProfile::default() }).unwrap();
+        mark_synced(&conn, &dj_kroma);
+        let shared = logos.add("Kroma", "/logos/kroma.png", None).unwrap();
+        let live = logos.add("Kroma live", "/logos/kroma_live.mov", None).unwrap();
+        logos.link_artist(&kroma, &shared, false).unwrap();
+        logos.link_artist(&dj_kroma, &shared, false).unwrap();
+        logos.link_artist(&dj_kroma, &live, true).unwrap();
+        let starts_at = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(23, 0, 0).unwrap();
+        let schedule = ScheduleRepo::new(&conn);
+        schedule.add(&ScheduleEventInput::new(starts_at, "Kromma (live)", "dj_set")).unwrap();
+        schedule.add(&ScheduleEventInput::new(starts_at, "Kromma b2b Nyx", "dj_set")).unwrap();
+
+        assert_eq!(repo.merge(&kroma, std::slice::from_ref(&kroma)).unwrap_err().kind, ErrorKind::InvalidInput);
+        repo.merge(&kroma, std::slice::from_ref(&dj_kroma)).unwrap();
+
+        let artists = repo.list().unwrap();
+        assert_eq!(artists.len(), 1);
+        assert_eq!(artists[0].aliases, ["Chromatic", "DJ Kromma"]);
+        assert_eq!(artists[0].profile.brand_color.as_deref(), Some("#112233"));
+        let status: String = conn.query_row("SELECT sync_status FROM artists WHERE id = ?1", [&dj_kroma], |r| r.get(0)).unwrap();
+        assert_eq!(status, "deleted");
+        let linked: Vec<_> = logos.list_for_artist(&kroma).unwrap().into_iter().map(|l| l.name).collect();
+        assert_eq!(linked, ["Kroma", "Kroma live"]);
+        let override_kept: bool = conn
+            .query_row("SELECT is_local_override FROM artist_logos WHERE logo_id = ?1", [&live], |r| r.get(0))
+            .unwrap();
+        assert!(override_kept);
+        // Only events named after the artist alone are renamed
+        let names: Vec<_> = schedule.list().unwrap().into_iter().map(|e| e.name).collect();
+        assert!(names.contains(&"KROMA".to_string()));
+        assert!(names.contains(&"Kromma b2b Nyx".to_string()));
+
+        assert_eq!(repo.merge(&kroma, &[dj_kroma]).unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn merging_a_placeholder_name_leaves_other_placeholders_alone() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        let kroma = repo.add("KROMA").unwrap();
+        let tba = repo.add("(TBA)").unwrap();
+        let starts_at = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(23, 0, 0).unwrap();
+        let schedule = ScheduleRepo::new(&conn);
+        schedule.add(&ScheduleEventInput::new(starts_at, "(Special guest)", "dj_set")).unwrap();
+
+        repo.merge(&kroma, &[tba]).unwrap();
+        // "(TBA)" has no match key, so it neither becomes an alias nor renames events
+        assert!(repo.list().unwrap()[0].aliases.is_empty());
+        assert_eq!(schedule.list().unwrap()[0].name, "(Special guest)");
+    }
+
+    #[test]
+    fn profile_is_validated_and_listed() {
+        let conn = open_in_memory().unwrap();
+        let repo = ArtistRepo::new(&conn);
+        let id = repo.add("KROMA").unwrap();
+        mark_synced(&conn, &id);
+        let logo = LogoRepo::new(&conn).add("Kroma", "/logos/kroma.png", None).unwrap();
+        let social = |platform: &str, handle: &str| SocialHandle { platform: platform.to_string(), handle: handle.to_string() };
+
+        let profile = ArtistProfile {
+            display_name: Some("Kroma".to_string()),
+            pronunciation: Some("KROH-mah".to_string()),
+            brand_color: Some("#F60".to_string()),
+            preferred_logo_id: Some(logo.clone()),
+            socials: vec![social(" SoundCloud ", "kroma"), social("instagram", "@kroma.music")],
+            notes: Some("  ".to_string()),
+        };
+        repo.update_profile(&id, &profile).unwrap();
+
+        let artist = &repo.list().unwrap()[0];
+        assert_eq!(artist.sync_status, "modified");
+        assert_eq!(artist.profile.brand_color.as_deref(), Some("#ff6600"));
+        assert_eq!(artist.profile.preferred_logo_id.as_deref(), Some(logo.as_str()));
+        assert_eq!(artist.profile.notes, None);
+        assert_eq!(artist.profile.socials, [social("instagram", "@kroma.music"), social("soundcloud", "kroma")]);
+
+        let invalid = |profile: ArtistProfile| repo.update_profile(&id, &profile).unwrap_err().field;
+        assert_eq!(invalid(ArtistProfile { brand_color: Some("orange".to_string()), ..profile.clone() }).as_deref(), Some("brand_color"));
+        assert_eq!(invalid(ArtistProfile { preferred_logo_id: Some("missing".to_string()), ..profile.clone() }).as_deref(), Some("preferred_logo_id"));
+        assert_eq!(invalid(ArtistProfile { socials: vec![social("x", "a"), social("X", "b")], ..profile.clone() }).as_deref(), Some("socials"));
+
+        // Deleting the logo clears it as the preferred one
+        conn.execute("UPDATE logos SET sync_status = 'synced'", []).unwrap();
+        LogoRepo::new(&conn).delete(&logo).unwrap();
+        assert_eq!(repo.list().unwrap()[0].profile.preferred_logo_id, None);
+        assert_eq!(repo.update_profile("missing", &ArtistProfile::default()).unwrap_err().kind, ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::error::{is_constraint_violation, AppError, AppResult};
+use super::CycleGroupRepo;
+use crate::models::{CycleItem, CycleItemConfig, CycleSettings};
+use rusqlite::{params, Connection, Row};
+
+// Items of one cycle group: the active group unless built with `for_group`
+pub struct CycleRepo<'a> {
+    conn: &'a Connection,
+    group_id: Option<&'a str>,
+}
+
+impl<'a> CycleRepo<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn, group_id: None }
+    }
+
+    // Works on `group_id` instead of the active group; None behaves like `new`
+    pub fn for_group(conn: &'a Connection, group_id: Option<&'a str>) -> Self {
+        Self { conn, group_id }
+    }
+
+    // Cycle entries in playback order, joined with the logo name
+    pub fn list(&self) -> AppResult<Vec<CycleItem>> {
+        let Some(group_id) = self.group_id()? else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT cc.logo_id, l.name, cc.status, cc.order_index, cc.duration_seconds,
+                    cc.transition_type, cc.transition_ms, cc.skip
+             FROM cycle_config cc
+             JOIN logos l ON cc.logo_id = l.id
+             WHERE cc.group_id = ?1
+             ORDER BY cc.order_index ASC",
+        )?;
+        let items = stmt.query_map([group_id], map_cycle_item)?.collect::<rusqlite::Result<_>>()?;
+        Ok(items)
+    }
+
+    // Overwrites the whole cycle with `logo_ids` in the given order, all
+    // with default settings
+    pub fn set(&self, logo_ids: &[String]) -> AppResult<()> {
+        let items: Vec<CycleItemConfig> = logo_ids.iter().map(CycleItemConfig::new).collect();
+        self.set_items(&items)
+    }
+
+    // Overwrites the whole cycle with `items` in the given order.
+    // Runs in a transaction so a bad item leaves the previous cycle intact.
+    pub fn set_items(&self, items: &[CycleItemConfig]) -> AppResult<()> {
+        for item in items {
+            validate(item)?;
+        }
+        let group_id = self.require_group_id()?;
+
+        let tx = self.conn.unchecked_transaction()?;
+
+        tx.execute("DELETE FROM cycle_config WHERE group_id = ?1", [&group_id])?;
+        for (index, item) in items.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO cycle_config
+                    (group_id, logo_id, order_index, status, duration_seconds, transition_type, transition_ms, skip)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    group_id,
+                    item.logo_id,
+                    index as u32,
+                    item.status,
+                    item.duration_seconds,
+                    item.transition_type,
+                    item.transition_ms,
+                    item.skip,
+                ],
+            ).map_err(|e| {
+                // Unknown logo (FOREIGN KEY) or the same logo twice (PRIMARY KEY)
+                if is_constraint_violation(&e) {
+                    AppError::invalid("logo_ids", format!("Logo '{}' is unknown or listed twice", item.logo_id))
+                        .with_entity_id(&item.logo_id)
+                } else {
+                    e.into()
+                }
+            })?;
+        }
+
+        Ok(tx.commit()?)
+    }
+
+    // Changes the settings of one item in place, keeping its position
+    pub fn update_item(&self, item: &CycleItemConfig) -> AppResult<()> {
+        validate(item)?;
+        let group_id = self.require_group_id()?;
+        let changed = self.conn.execute(
+            "UPDATE cycle_config
+             SET status = ?1, duration_seconds = ?2, transition_type = ?3, transition_ms = ?4, skip = ?5
+             WHERE logo_id = ?6 AND group_id = ?7",
+            params![
+                item.status,
+                item.duration_seconds,
+                item.transition_type,
+                item.transition_ms,
+                item.skip,
+                item.logo_id,
+                group_id,
+            ],
+        )?;
+        super::expect_one(changed, "Cycle item", &item.logo_id)
+    }
+
+    pub fn settings(&self) -> AppResult<CycleSettings> {
+        let settings = self.conn.query_row(
+            "SELECT default_duration_seconds FROM cycle_settings WHERE id = 1",
+            [],
+            |row| Ok(CycleSettings { default_duration_seconds: row.get(0)? }),
+        )?;
+        Ok(settings)
+    }
+
+    // Duration used by items that don't set their own
+    pub fn set_default_duration(&self, seconds: u32) -> AppResult<()> {
+        if seconds == 0 {
+            return Err(AppError::invalid("default_duration_seconds", "Default duration must be at least one second"));
+        }
+        self.conn.execute(
+            "UPDATE cycle_settings SET default_duration_seconds = ?1 WHERE id = 1",
+            [seconds],
+        )?;
+        Ok(())
+    }
+
+    // The explicit group (which must exist) or else the active one
+    fn group_id(&self) -> AppResult<Option<String>> {
+        let groups = CycleGroupRepo::new(self.conn);
+        match self.group_id {
+            Some(id) => {
+                groups.ensure_exists(id)?;
+                Ok(Some(id.to_string()))
+            }
+            None => groups.active_id(),
+        }
+    }
+
+    fn require_group_id(&self) -> AppResult<String> {
+        self.group_id()?
+            .ok_or_else(|| AppError::conflict("No cycle group is active").with_field("group_id"))
+    }
+}
+
+fn validate(item: &CycleItemConfig) -> AppResult<()> {
+    if item.duration_seconds == Some(0) {
+        return Err(AppError::invalid("duration_seconds", "Duration must be at least one second")
+            .with_entity_id(&item.logo_id));
+    }
+    // The transition is part of the item's time on screen, so it can't be
+    // longer than an explicit duration
+    if let Some(seconds) = item.duration_seconds {
+        if u64::from(item.transition_ms) > u64::from(seconds) * 1000 {
+            return Err(AppError::invalid("transition_ms", "Transition is longer than the item's duration")
+                .with_entity_id(&item.logo_id));
+        }
+    }
+    Ok(())
+}
+
+fn map_cycle_item(row: &Row) -> rusqlite::Result<CycleItem> {
+    let logo_id: String = row.get(0)?;
+    Ok(CycleItem {
+        id: logo_id.clone(), // Use logo_id as the item's unique ID
+        logo_id,
+        name: row.get(1)?,
+        status: row.get(2)?,
+        order_index: row.get(3)?,
+        duration_seconds: row.get(4)?,
+        transition_type: row.get(5)?,
+        transition_ms: row.get(6)?,
+        skip: row.get(7)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::error::ErrorKind;
+    use crate::models::{CycleStatus, TransitionType};
+    use crate::repo::LogoRepo;
+
+    fn logos(conn: &Connection, names: &[&str]) -> Vec<String> {
+        let repo = LogoRepo::new(conn);
+        names
+            .iter()
+            .map(|n| repo.add(n, &format!("/logos/{}.png", n), None).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn set_replaces_cycle_in_order() {
+        let conn = open_in_memory().unwrap();
+        let ids = logos(&conn, &["a", "b", "c"]);
+        let repo = CycleRepo::new(&conn);
+
+        repo.set(&ids).unwrap();
+        repo.set(&[ids[2].clone(), ids[0].clone()]).unwrap();
+
+        let items = repo.list().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].logo_id, ids[2]);
+        assert_eq!(items[0].id, ids[2]);
+        assert_eq!(items[0].name, "c");
+        assert_eq!(items[0].order_index, 0);
+        assert_eq!(items[1].logo_id, ids[0]);
+        assert_eq!(items[1].order_index, 1);
+        assert!(items.iter().all(|i| i.status == CycleStatus::Cycle && i.duration_seconds.is_none() && !i.skip));
+    }
+
+    #[test]
+    fn set_with_unknown_logo_keeps_previous_cycle() {
+        let conn = open_in_memory().unwrap();
+        let ids = logos(&conn, &["a", "b"]);
+        let repo = CycleRepo::new(&conn);
+        repo.set(&ids).unwrap();
+
+        let err = repo.set(&[ids[1].clone(), "missing".to_string()]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidInput);
+        assert_eq!(err.entity_id.as_deref(), Some("missing"));
+
+        let kept: Vec<String> = repo.list().unwrap().into_iter().map(|i| i.logo_id).collect();
+        assert_eq!(kept, ids);
+    }
+
+    #[test]
+    fn deleting_a_logo_removes_it_from_the_cycle() {
+        let conn = open_in_memory().unwrap();
+        let ids = logos(&conn, &["a", "b"]);
+        let repo = CycleRepo::new(&conn);
+        repo.set(&ids).unwrap();
+
+        conn.execute("DELETE FROM logos WHERE id = ?1", [&ids[0]]).unwrap();
+        let items = repo.list().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].logo_id, ids[1]);
+    }
+
+    #[test]
+    fn set_items_round_trips_settings() {
+        let conn = open_in_memory().unwrap();
+        let ids = logos(&conn, &["a", "b"]);
+        let repo = CycleRepo::new(&conn);
+
+        let held = CycleItemConfig {
+            status: CycleStatus::Locked,
+            duration_seconds: Some(30),
+            transition_type: TransitionType::Crossfade,
+            transition_ms: 1500,
+            ..CycleItemConfig::new(&ids[0])
+        };
+        let skipped = CycleItemConfig {
+            skip: true,
+            ..CycleItemConfig::new(&ids[1])
+        };
+        repo.set_items(&[held, skipped]).unwrap();
+
+        let items = repo.list().unwrap();
+        assert_eq!(items[0].status, CycleStatus::Locked);
+        assert_eq!(items[0].duration_seconds, Some(30));
+        assert_eq!(items[0].transition_type, TransitionType::Crossfade);
+        assert_eq!(items[0].transition_ms, 1500);
+        assert!(items[0].is_playable());
+        assert!(items[1].skip);
+        assert!(!items[1].is_playable());
+    }
+
+    #[test]
+    fn rejects_zero_duration_and_overlong_transition() {
+        let conn = open_in_memory().unwrap();
+        let ids = logos(&conn, &["a"]);
+        let repo = CycleRepo::new(&conn);
+        repo.set(&ids).unwrap();
+
+        let zero = CycleItemConfig {
+            duration_seconds: Some(0),
+            ..CycleItemConfig::new(&ids[0])
+        };
+        assert_eq!(repo.update_item(&zero).unwrap_err().field.as_deref(), Some("duration_seconds"));
+
+        let overlong = CycleItemConfig {
+            duration_seconds: Some(2),
+            transition_ms: 2500,
+            ..CycleItemConfig::new(&ids[0])
+        };
+        assert_eq!(repo.set_items(&[overlong]).unwrap_err().field.as_deref(), Some("transition_ms"));
+        assert_eq!(repo.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn update_item_keeps_position() {
+        let conn = open_in_memory().unwrap();
+        let ids = logos(&conn, &["a", "b"]);
+        let repo = CycleRepo::new(&conn);
+        repo.set(&ids).unwrap();
+
+        let inactive = CycleItemConfig {
+            status: CycleStatus::Inactive,
+            ..CycleItemConfig::new(&ids[1])
+        };
+        repo.update_item(&inactive).unwrap();
+
+        let items = repo.list().unwrap();
+        assert_eq!(items[1].logo_id, ids[1]);
+        assert_eq!(items[1].order_index, 1);
+        assert_eq!(items[1].status, CycleStatus::Inactive);
+
+        let err = repo.update_item(&CycleItemConfig::new("missing")).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn default_duration_is_stored() {
+        let conn = open_in_memory().unwrap();
+        let repo = CycleRepo::new(&conn);
+        assert_eq!(repo.settings().unwrap().default_duration_seconds, 10);
+
+        repo.set_default_duration(25).unwrap();
+        assert_eq!(repo.settings().unwrap().default_duration_seconds, 25);
+        assert_eq!(repo.set_default_duration(0).unwrap_err().kind, ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn groups_keep_separate_items() {
+        let conn = open_in_memory().unwrap();
+        let ids = logos(&conn, &["a", "b"]);
+        let after_hours = CycleGroupRepo::new(&conn).create("After Hours").unwrap();
+
+        CycleRepo::new(&conn).set(&ids).unwrap();
+        let scoped = CycleRepo::for_group(&conn, Some(&after_hours));
+        scoped.set(&[ids[1].clone()]).unwrap();
+
+        assert_eq!(CycleRepo::new(&conn).list().unwrap().len(), 2);
+        assert_eq!(scoped.list().unwrap()[0].logo_id, ids[1]);
+
+        let missing = CycleRepo::for_group(&conn, Some("missing"));
+        assert_eq!(missing.list().unwrap_err().kind, ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,213 @@
+use super::expect_one;
+use crate::current_timestamp;
+use crate::error::{is_constraint_violation, AppError, AppResult};
+use crate::models::CycleGroup;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use uuid::Uuid;
+
+// Cycle groups are local show configuration, not synced data, so they are
+// deleted outright instead of being tombstoned.
+pub struct CycleGroupRepo<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> CycleGroupRepo<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    // All groups alphabetically, with the active one flagged
+    pub fn list(&self) -> AppResult<Vec<CycleGroup>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT g.id, g.name,
+                    g.id IS (SELECT active_group_id FROM cycle_settings WHERE id = 1),
+                    (SELECT COUNT(*) FROM cycle_config cc WHERE cc.group_id = g.id),
+                    g.created_at, g.updated_at
+             FROM cycle_groups g
+             ORDER BY g.name ASC",
+        )?;
+        let groups = stmt.query_map([], map_group)?.collect::<rusqlite::Result<_>>()?;
+        Ok(groups)
+    }
+
+    // Creates an empty group and returns its id
+    pub fn create(&self, name: &str) -> AppResult<String> {
+        let name = validate_name(name)?;
+        let new_id = Uuid::new_v4().to_string();
+        let now = current_timestamp();
+
+        self.conn.execute(
+            "INSERT INTO cycle_groups (id, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            params![new_id, name, now, now],
+        ).map_err(|e| self.duplicate_name_or(e, name))?;
+        Ok(new_id)
+    }
+
+    // Copies a group and all of its items under a new name
+    pub fn duplicate(&self, id: &str, name: &str) -> AppResult<String> {
+        self.ensure_exists(id)?;
+        let tx = self.conn.unchecked_transaction()?;
+
+        let new_id = self.create(name)?;
+        tx.execute(
+            "INSERT INTO cycle_config
+                (group_id, logo_id, order_index, status, duration_seconds, transition_type, transition_ms, skip)
+             SELECT ?2, logo_id, order_index, status, duration_seconds, transition_type, transition_ms, skip
+             FROM cycle_config WHERE group_id = ?1",
+            params![id, new_id],
+        )?;
+
+        tx.commit()?;
+        Ok(new_id)
+    }
+
+    pub fn rename(&self, id: &str, name: &str) -> AppResult<()> {
+        let name = validate_name(name)?;
+        let changed = self.conn.execute(
+            "UPDATE cycle_groups SET name = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, name, current_timestamp()],
+        ).map_err(|e| self.duplicate_name_or(e, name))?;
+        expect_one(changed, "Cycle group", id)
+    }
+
+    // Deletes a group and its items. The active group can't be deleted;
+    // activate another one first.
+    pub fn delete(&self, id: &str) -> AppResult<()> {
+        if self.active_id()?.as_deref() == Some(id) {
+            return Err(AppError::conflict("The active cycle group can't be deleted").with_entity_id(id));
+        }
+        let changed = self.conn.execute("DELETE FROM cycle_groups WHERE id = ?1", [id])?;
+        expect_one(changed, "Cycle group", id)
+    }
+
+    // Makes `id` the group the playback engine runs
+    pub fn activate(&self, id: &str) -> AppResult<()> {
+        self.ensure_exists(id)?;
+        self.conn.execute(
+            "UPDATE cycle_settings SET active_group_id = ?1 WHERE id = 1",
+            [id],
+        )?;
+        Ok(())
+    }
+
+    // None only if no group has ever been activated
+    pub fn active_id(&self) -> AppResult<Option<String>> {
+        let id = self.conn.query_row(
+            "SELECT active_group_id FROM cycle_settings WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    pub fn ensure_exists(&self, id: &str) -> AppResult<()> {
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM cycle_groups WHERE id = ?1)",
+            [id],
+            |row| row.get(0),
+        )?;
+        if exists {
+            Ok(())
+        } else {
+            Err(AppError::not_found("Cycle group", id))
+        }
+    }
+
+    fn duplicate_name_or(&self, e: rusqlite::Error, name: &str) -> AppError {
+        if !is_constraint_violation(&e) {
+            return e.into();
+        }
+        let existing: rusqlite::Result<Option<String>> = self.conn.query_row(
+            "SELECT id FROM cycle_groups WHERE name = ?1",
+            [name],
+            |row| row.get(0),
+        ).optional();
+        match existing {
+            Ok(Some(existing_id)) => AppError::duplicate(
+                "name",
+                &existing_id,
+                format!("Cycle group '{}' already exists.", name),
+            ),
+            _ => e.into(),
+        }
+    }
+}
+
+fn validate_name(name: &str) -> AppResult<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid("name", "Cycle group name can't be empty"));
+    }
+    Ok(name)
+}
+
+fn map_group(row: &Row) -> rusqlite::Result<CycleGroup> {
+    Ok(CycleGroup {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        is_active: row.get(2)?,
+        item_count: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::error::ErrorKind;
+    use crate::repo::{CycleRepo, LogoRepo};
+
+    #[test]
+    fn starts_with_the_main_group_active() {
+        let conn = open_in_memory().unwrap();
+        let groups = CycleGroupRepo::new(&conn).list().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "Main");
+        assert!(groups[0].is_active);
+    }
+
+    #[test]
+    fn duplicate_copies_items_and_activate_switches() {
+        let conn = open_in_memory().unwrap();
+        let logo = LogoRepo::new(&conn).add("a", "/logos/a.png", None).unwrap();
+        let repo = CycleGroupRepo::new(&conn);
+        CycleRepo::new(&conn).set(std::slice::from_ref(&logo)).unwrap();
+
+        let copy = repo.duplicate("main", "After Hours").unwrap();
+        repo.activate(&copy).unwrap();
+        assert_eq!(repo.active_id().unwrap().as_deref(), Some(copy.as_str()));
+
+        let groups = repo.list().unwrap();
+        let after_hours = groups.iter().find(|g| g.id == copy).unwrap();
+        assert!(after_hours.is_active);
+        assert_eq!(after_hours.item_count, 1);
+        assert!(!groups.iter().find(|g| g.id == "main").unwrap().is_active);
+    }
+
+    #[test]
+    fn names_must_be_unique_and_non_empty() {
+        let conn = open_in_memory().unwrap();
+        let repo = CycleGroupRepo::new(&conn);
+        let id = repo.create("Main Room").unwrap();
+
+        let err = repo.create("Main Room").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Duplicate);
+        assert_eq!(err.entity_id.as_deref(), Some(id.as_str()));
+        assert_eq!(repo.rename(&id, "  ").unwrap_err().kind, ErrorKind::InvalidInput);
+        assert_eq!(repo.rename("missing", "X").unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn active_group_cannot_be_deleted() {
+        let conn = open_in_memory().unwrap();
+        let repo = CycleGroupRepo::new(&conn);
+        assert_eq!(repo.delete("main").unwrap_err().kind, ErrorKind::Conflict);
+
+        let other = repo.create("After Hours").unwrap();
+        repo.activate(&other).unwrap();
+        repo.delete("main").unwrap();
+        assert_eq!(repo.list().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,918 @@
+use super::{ensure_exists, expect_one, TagRepo, KEEP_OVERRIDE, MARK_MODIFIED};
+use crate::current_timestamp;
+use crate::error::{is_constraint_violation, AppError, AppResult};
+use crate::library::{self, is_relative};
+use crate::models::{ArtistLogoLink, Logo, MediaInfo, RemoteLinkChanges, TagCategory};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub(crate) const LOGO_COLUMNS: &str =
+    "l.id, l.name, l.file_path, l.thumbnail_path, l.vjtools_id, l.created_at, l.updated_at, l.sync_status,
+     l.file_size, l.file_modified, l.missing, l.thumbnail_key, l.content_hash, l.perceptual_hash, l.hash_key,
+     l.media_format, l.media_codec, l.width, l.height, l.has_alpha, l.frame_count, l.duration_ms, l.probe_key";
+
+pub struct LogoRepo<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> LogoRepo<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    // Inserts a new logo and returns its generated id. `file_path` is UNIQUE,
+    // so adding the same file twice fails with a Duplicate error that carries
+    // the existing logo's id. Re-adding the file of a deleted logo brings
+    // that logo back instead.
+    //
+    // Paths under the library root are stored relative to it; every method
+    // takes and returns absolute paths.
+    pub fn add(&self, name: &str, file_path: &str, thumbnail_path: Option<&str>) -> AppResult<String> {
+        let now = current_timestamp();
+        let file_path = &self.to_stored(file_path)?;
+
+        let tombstone: Option<String> = self.conn.query_row(
+            "SELECT id FROM logos WHERE file_path = ?1 AND sync_status = 'deleted'",
+            [file_path],
+            |row| row.get(0),
+        ).optional()?;
+        if let Some(id) = tombstone {
+            self.conn.execute(
+                "UPDATE logos SET name = ?2, thumbnail_path = ?3, thumbnail_key = NULL, updated_at = ?4, sync_status = 'modified' WHERE id = ?1",
+                params![id, name, thumbnail_path, now],
+            )?;
+            return Ok(id);
+        }
+
+        let new_id = Uuid::new_v4().to_string();
+        self.conn.execute(
+            "INSERT INTO logos (id, name, file_path, thumbnail_path, created_at, updated_at, sync_status) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![new_id, name, file_path, thumbnail_path, now, now, "new"],
+        ).map_err(|e| self.duplicate_path_or(e, file_path))?;
+        Ok(new_id)
+    }
+
+    // Id of the logo stored under `file_path`, if any
+    pub fn find_id_by_path(&self, file_path: &str) -> AppResult<Option<String>> {
+        let id = self.conn.query_row(
+            "SELECT id FROM logos WHERE file_path = ?1 AND sync_status != 'deleted'",
+            [self.to_stored(file_path)?],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(id)
+    }
+
+    // All logos that haven't been deleted, alphabetically, with
+    // `linked_djs` filled in
+    pub fn list(&self) -> AppResult<Vec<Logo>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM logos l WHERE l.sync_status != 'deleted' ORDER BY l.name ASC",
+            LOGO_COLUMNS
+        ))?;
+        let mut logos = stmt.query_map([], map_logo)?.collect::<rusqlite::Result<Vec<Logo>>>()?;
+        self.hydrate(&mut logos)?;
+        Ok(logos)
+    }
+
+    // Fills in what `map_logo` leaves out: absolute paths, linked artist
+    // names and tags
+    pub(crate) fn hydrate(&self, logos: &mut [Logo]) -> AppResult<()> {
+        self.resolve_paths(logos)?;
+        let links_map = self.linked_artist_names()?;
+        let mut tags_map = TagRepo::new(self.conn).by_logo()?;
+        for logo in logos.iter_mut() {
+            if let Some(djs) = links_map.get(&logo.id) {
+                logo.linked_djs = djs.clone();
+            }
+            logo.tags = tags_map.remove(&logo.id).unwrap_or_default();
+        }
+        Ok(())
+    }
+
+    // Logos carrying the selected tags. Within a category any of the
+    // selected tags will do; across categories all must match, so "sponsor
+    // or intro, and imported" is one call. No tags selects every logo.
+    pub fn list_tagged(&self, tag_ids: &[String]) -> AppResult<Vec<Logo>> {
+        let categories: HashMap<String, TagCategory> =
+            TagRepo::new(self.conn).list(None)?.into_iter().map(|t| (t.id, t.category)).collect();
+        let mut wanted: HashMap<TagCategory, HashSet<&str>> = HashMap::new();
+        for id in tag_ids {
+            let category = categories.get(id).ok_or_else(|| AppError::not_found("Tag", id))?;
+            wanted.entry(*category).or_default().insert(id);
+        }
+
+        let mut logos = self.list()?;
+        logos.retain(|logo| {
+            wanted
+                .values()
+                .all(|ids| logo.tags.iter().any(|tag| ids.contains(tag.id.as_str())))
+        });
+        Ok(logos)
+    }
+
+    // Replaces the editable fields of a logo. Passing a different
+    // thumbnail marks it as set by hand, so it isn't regenerated.
+    pub fn update(&self, id: &str, name: &str, file_path: &str, thumbnail_path: Option<&str>) -> AppResult<()> {
+        let stored_path = self.to_stored(file_path)?;
+        let changed = self.conn.execute(
+            &format!(
+                "UPDATE logos SET name = ?2, file_path = ?3, thumbnail_path = ?4,
+                     thumbnail_key = CASE WHEN thumbnail_path IS ?4 THEN thumbnail_key END,
+                     updated_at = ?5, {}
+                 WHERE id = ?1 AND sync_status != 'deleted'",
+                MARK_MODIFIED
+            ),
+            params![id, name, stored_path, thumbnail_path, current_timestamp()],
+        ).map_err(|e| self.duplicate_path_or(e, file_path))?;
+        expect_one(changed, "Logo", id)
+    }
+
+    // The logo library folder relative paths are resolved against
+    pub fn library_root(&self) -> AppResult<Option<PathBuf>> {
+        let root: Option<String> = self.conn.query_row(
+            "SELECT root FROM library_settings WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(root.filter(|r| !r.is_empty()).map(PathBuf::from))
+    }
+
+    // Sets the library folder and rewrites the absolute paths of logos
+    // inside it as relative ones. Logos already stored relative follow the
+    // new root; clearing the root turns them back into absolute paths under
+    // the old one. A logo whose new path another logo already has is merged
+    // into that one. Returns how many logos were rewritten or merged.
+    pub fn set_library_root(&self, root: Option<&Path>) -> AppResult<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let old_root = self.library_root()?;
+        self.conn.execute(
+            "UPDATE library_settings SET root = ?1 WHERE id = 1",
+            [root.map(|r| r.to_string_lossy().into_owned())],
+        )?;
+
+        let rows: Vec<(String, String, bool)> = self.conn
+            .prepare("SELECT id, file_path, sync_status = 'deleted' FROM logos")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        let mut rewritten = 0;
+        for (id, path, deleted) in rows {
+            let stored = match root {
+                Some(_) if !is_relative(&path) => library::to_stored(&path, root),
+                None if is_relative(&path) => library::resolve(&path, old_root.as_deref()),
+                _ => continue,
+            };
+            if stored == path {
+                continue;
+            }
+            let holder: Option<(String, bool)> = self.conn.query_row(
+                "SELECT id, sync_status = 'deleted' FROM logos WHERE file_path = ?1",
+                [&stored],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            ).optional()?;
+            match holder {
+                None => {
+                    self.conn.execute("UPDATE logos SET file_path = ?2 WHERE id = ?1", params![id, stored])?;
+                }
+                // A deleted logo keeps its old path
+                Some(_) if deleted => continue,
+                // Both paths name the same file, so the logo already stored
+                // the new way keeps it
+                Some((other, false)) => self.merge_into(&other, std::slice::from_ref(&id))?,
+                // A live logo takes the path from a deleted one, which gets
+                // the live one's old path: both name the same file. The id
+                // holds the place in between, as paths are unique.
+                Some((other, true)) => {
+                    self.conn.execute("UPDATE logos SET file_path = id WHERE id = ?1", [&id])?;
+                    self.conn.execute("UPDATE logos SET file_path = ?2 WHERE id = ?1", params![other, path])?;
+                    self.conn.execute("UPDATE logos SET file_path = ?2 WHERE id = ?1", params![id, stored])?;
+                }
+            }
+            rewritten += 1;
+        }
+
+        tx.commit()?;
+        Ok(rewritten)
+    }
+
+    // Records what the last library scan found on disk. This is local state,
+    // so it doesn't mark the logo as modified for sync.
+    pub fn set_file_state(&self, id: &str, file_size: Option<u64>, file_modified: Option<u64>, missing: bool) -> AppResult<()> {
+        let changed = self.conn.execute(
+            "UPDATE logos SET file_size = ?2, file_modified = ?3, missing = ?4 WHERE id = ?1 AND sync_status != 'deleted'",
+            params![id, file_size, file_modified, missing],
+        )?;
+        expect_one(changed, "Logo", id)
+    }
+
+    // Flags or clears a logo's file as missing without touching the rest of
+    // its file state
+    pub fn set_missing(&self, id: &str, missing: bool) -> AppResult<()> {
+        let changed = self.conn.execute(
+            "UPDATE logos SET missing = ?2 WHERE id = ?1 AND sync_status != 'deleted'",
+            params![id, missing],
+        )?;
+        expect_one(changed, "Logo", id)
+    }
+
+    // Records a generated thumbnail and the version of the file it was made
+    // from. `thumbnail_path` is None when the file couldn't be rendered, so
+    // it isn't retried until the file changes. Local state, like
+    // `set_file_state`.
+    pub fn set_thumbnail(&self, id: &str, thumbnail_path: Option<&str>, thumbnail_key: &str) -> AppResult<()> {
+        let changed = self.conn.execute(
+            "UPDATE logos SET thumbnail_path = ?2, thumbnail_key = ?3 WHERE id = ?1 AND sync_status != 'deleted'",
+            params![id, thumbnail_path, thumbnail_key],
+        )?;
+        expect_one(changed, "Logo", id)
+    }
+
+    // Records the hashes of a logo's file and the version of the file they
+    // were computed from. Local state, like `set_file_state`.
+    pub fn set_hashes(&self, id: &str, content_hash: Option<&str>, perceptual_hash: Option<&str>, hash_key: &str) -> AppResult<()> {
+        let changed = self.conn.execute(
+            "UPDATE logos SET content_hash = ?2, perceptual_hash = ?3, hash_key = ?4 WHERE id = ?1 AND sync_status != 'deleted'",
+            params![id, content_hash, perceptual_hash, hash_key],
+        )?;
+        expect_one(changed, "Logo", id)
+    }
+
+    // Records what probing the file found (None if it couldn't be read).
+    // Like the hashes this is local state and doesn't mark the logo modified.
+    pub fn set_media(&self, id: &str, media: Option<&MediaInfo>, probe_key: &str) -> AppResult<()> {
+        let changed = self.conn.execute(
+            "UPDATE logos SET media_format = ?2, media_codec = ?3, width = ?4, height = ?5, has_alpha = ?6,
+                              frame_count = ?7, duration_ms = ?8, probe_key = ?9
+             WHERE id = ?1 AND sync_status != 'deleted'",
+            params![
+                id,
+                media.map(|m| &m.format),
+                media.and_then(|m| m.codec.as_ref()),
+                media.and_then(|m| m.width),
+                media.and_then(|m| m.height),
+                media.and_then(|m| m.has_alpha),
+                media.and_then(|m| m.frame_count),
+                media.and_then(|m| m.duration_ms),
+                probe_key,
+            ],
+        )?;
+        expect_one(changed, "Logo", id)
+    }
+
+    // Folds `duplicate_ids` into `keep_id`: their artist links, tags, cycle
+    // entries and schedule events move over to the kept logo, then they are deleted.
+    // A cycle group that already holds the kept logo just loses the
+    // duplicate's entry.
+    pub fn merge(&self, keep_id: &str, duplicate_ids: &[String]) -> AppResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.merge_into(keep_id, duplicate_ids)?;
+        Ok(tx.commit()?)
+    }
+
+    // The body of `merge`, for callers that already hold a transaction
+    pub(crate) fn merge_into(&self, keep_id: &str, duplicate_ids: &[String]) -> AppResult<()> {
+        ensure_exists(self.conn, "logos", "Logo", keep_id)?;
+        if duplicate_ids.iter().any(|id| id == keep_id) {
+            return Err(AppError::invalid("duplicate_ids", "A logo can't be merged into itself").with_entity_id(keep_id));
+        }
+        let now = current_timestamp();
+
+        for id in duplicate_ids {
+            ensure_exists(self.conn, "logos", "Logo", id)?;
+            self.conn.execute(
+                &format!(
+                    "INSERT INTO artist_logos (artist_id, logo_id, is_local_override)
+                     SELECT artist_id, ?2, is_local_override FROM artist_logos WHERE logo_id = ?1 {}",
+                    KEEP_OVERRIDE
+                ),
+                params![id, keep_id],
+            )?;
+            self.conn.execute("DELETE FROM artist_logos WHERE logo_id = ?1", [id])?;
+            self.conn.execute(
+                "INSERT OR IGNORE INTO logo_tags (logo_id, tag_id) SELECT ?2, tag_id FROM logo_tags WHERE logo_id = ?1",
+                params![id, keep_id],
+            )?;
+            self.conn.execute("DELETE FROM logo_tags WHERE logo_id = ?1", [id])?;
+            self.conn.execute(
+                "UPDATE cycle_config SET logo_id = ?2
+                 WHERE logo_id = ?1
+                   AND group_id NOT IN (SELECT group_id FROM cycle_config WHERE logo_id = ?2)",
+                params![id, keep_id],
+            )?;
+            self.conn.execute("DELETE FROM cycle_config WHERE logo_id = ?1", [id])?;
+            self.conn.execute(
+                &format!(
+                    "UPDATE schedule_events SET linked_logo_id = ?2, updated_at = ?3, {} WHERE linked_logo_id = ?1",
+                    MARK_MODIFIED
+                ),
+                params![id, keep_id, now],
+            )?;
+            self.conn.execute(
+                &format!(
+                    "UPDATE artists SET preferred_logo_id = ?2, updated_at = ?3, {} WHERE preferred_logo_id = ?1",
+                    MARK_MODIFIED
+                ),
+                params![id, keep_id, now],
+            )?;
+            self.remove(id)?;
+        }
+        Ok(())
+    }
+
+    // Deletes a logo. Synced logos stay behind as a tombstone, and the
+    // foreign key actions are applied by hand: artist links, tags and cycle
+    // entries are removed (CASCADE) and schedule events and preferred logos
+    // lose their link (SET NULL).
+    pub fn delete(&self, id: &str) -> AppResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.remove(id)?;
+        Ok(tx.commit()?)
+    }
+
+    // Logos linked to a specific artist. `linked_djs` is left empty.
+    pub fn list_for_artist(&self, artist_id: &str) -> AppResult<Vec<Logo>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM logos l
+             INNER JOIN artist_logos al ON l.id = al.logo_id
+             WHERE al.artist_id = ?1 AND l.sync_status != 'deleted'
+             ORDER BY l.name ASC",
+            LOGO_COLUMNS
+        ))?;
+        let mut logos = stmt.query_map([artist_id], map_logo)?.collect::<rusqlite::Result<Vec<Logo>>>()?;
+        self.resolve_paths(&mut logos)?;
+        Ok(logos)
+    }
+
+    // Links a logo to an artist. Linking an already linked pair only ever
+    // turns it into a local override; `set_link_override` turns that off.
+    pub fn link_artist(&self, artist_id: &str, logo_id: &str, is_local_override: bool) -> AppResult<()> {
+        ensure_exists(self.conn, "artists", "Artist", artist_id)?;
+        ensure_exists(self.conn, "logos", "Logo", logo_id)?;
+        self.conn.execute(
+            &format!("INSERT INTO artist_logos (artist_id, logo_id, is_local_override) VALUES (?1, ?2, ?3) {}", KEEP_OVERRIDE),
+            params![artist_id, logo_id, is_local_override as i32],
+        )?;
+        Ok(())
+    }
+
+    // Artist-logo links with their override flag, by artist then logo
+    // name; only those of one artist if `artist_id` is given
+    pub fn list_links(&self, artist_id: Option<&str>) -> AppResult<Vec<ArtistLogoLink>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT al.artist_id, a.name, al.logo_id, l.name, al.is_local_override
+             FROM artist_logos al
+             JOIN artists a ON a.id = al.artist_id
+             JOIN logos l ON l.id = al.logo_id
+             WHERE (?1 IS NULL OR al.artist_id = ?1) AND a.sync_status != 'deleted' AND l.sync_status != 'deleted'
+             ORDER BY a.name ASC, l.name ASC",
+        )?;
+        let links = stmt
+            .query_map([artist_id], |row| {
+                Ok(ArtistLogoLink {
+                    artist_id: row.get(0)?,
+                    artist_name: row.get(1)?,
+                    logo_id: row.get(2)?,
+                    logo_name: row.get(3)?,
+                    is_local_override: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(links)
+    }
+
+    // Marks an existing link as a local override or as synced
+    pub fn set_link_override(&self, artist_id: &str, logo_id: &str, is_local_override: bool) -> AppResult<()> {
+        let changed = self.conn.execute(
+            "UPDATE artist_logos SET is_local_override = ?3 WHERE artist_id = ?1 AND logo_id = ?2",
+            params![artist_id, logo_id, is_local_override as i32],
+        )?;
+        expect_one(changed, "Artist logo link", &format!("{}/{}", artist_id, logo_id))
+    }
+
+    // Brings an artist's synced links in line with what vj.tools says its
+    // logos are. Local overrides are authoritative: they are never removed
+    // or downgraded, whatever `logo_ids` contains. Ids of logos that aren't
+    // in the local library are skipped. Runs in one transaction.
+    pub fn apply_remote_links(&self, artist_id: &str, logo_ids: &[String]) -> AppResult<RemoteLinkChanges> {
+        let tx = self.conn.unchecked_transaction()?;
+        ensure_exists(self.conn, "artists", "Artist", artist_id)?;
+        let mut changes = RemoteLinkChanges::default();
+        for logo_id in logo_ids {
+            changes.added += self.conn.execute(
+                "INSERT OR IGNORE INTO artist_logos (artist_id, logo_id, is_local_override)
+                 SELECT ?1, id, 0 FROM logos WHERE id = ?2 AND sync_status != 'deleted'",
+                params![artist_id, logo_id],
+            )?;
+        }
+        for link in self.list_links(Some(artist_id))? {
+            if link.is_local_override {
+                changes.overrides_kept += 1;
+            } else if !logo_ids.contains(&link.logo_id) {
+                changes.removed += self.conn.execute(
+                    "DELETE FROM artist_logos WHERE artist_id = ?1 AND logo_id = ?2",
+                    params![artist_id, link.logo_id],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(changes)
+    }
+
+    pub fn unlink_artist(&self, artist_id: &str, logo_id: &str) -> AppResult<()> {
+        self.conn.execute(
+            "DELETE FROM artist_logos WHERE artist_id = ?1 AND logo_id = ?2",
+            params![artist_id, logo_id],
+        )?;
+        Ok(())
+    }
+
+    // The body of `delete`, for callers that already hold a transaction
+    fn remove(&self, id: &str) -> AppResult<()> {
+        let now = current_timestamp();
+        let removed = self.conn.execute("DELETE FROM logos WHERE id = ?1 AND sync_status = 'new'", [id])?;
+        if removed == 0 {
+            let changed = self.conn.execute(
+                "UPDATE logos SET sync_status = 'deleted', updated_at = ?2 WHERE id = ?1 AND sync_status != 'deleted'",
+                params![id, now],
+            )?;
+            expect_one(changed, "Logo", id)?;
+            self.conn.execute("DELETE FROM artist_logos WHERE logo_id = ?1", [id])?;
+            self.conn.execute("DELETE FROM logo_tags WHERE logo_id = ?1", [id])?;
+            self.conn.execute("DELETE FROM cycle_config WHERE logo_id = ?1", [id])?;
+            self.conn.execute(
+                &format!(
+                    "UPDATE schedule_events SET linked_logo_id = NULL, updated_at = ?2, {} WHERE linked_logo_id = ?1",
+                    MARK_MODIFIED
+                ),
+                params![id, now],
+            )?;
+            self.conn.execute(
+                &format!(
+                    "UPDATE artists SET preferred_logo_id = NULL, updated_at = ?2, {} WHERE preferred_logo_id = ?1",
+                    MARK_MODIFIED
+                ),
+                params![id, now],
+            )?;
+        }
+        Ok(())
+    }
+
+    // Duplicate error pointing at the logo that already owns `file_path`,
+    // or the original error if it wasn't a UNIQUE clash
+    fn duplicate_path_or(&self, e: rusqlite::Error, file_path: &str) -> AppError {
+        if !is_constraint_violation(&e) {
+            return e.into();
+        }
+        match self.find_id_by_path(file_path) {
+            Ok(Some(existing_id)) => AppError::duplicate(
+                "file_path",
+                &existing_id,
+                format!("Logo with file path '{}' already exists.", file_path),
+            ),
+            _ => e.into(),
+        }
+    }
+
+    fn to_stored(&self, file_path: &str) -> AppResult<String> {
+        Ok(library::to_stored(file_path, self.library_root()?.as_deref()))
+    }
+
+    fn resolve_paths(&self, logos: &mut [Logo]) -> AppResult<()> {
+        let root = self.library_root()?;
+        for logo in logos {
+            logo.file_path = library::resolve(&logo.file_path, root.as_deref());
+        }
+        Ok(())
+    }
+
+    // logo_id -> names of the artists linked to it
+    fn linked_artist_names(&self) -> AppResult<HashMap<String, Vec<String>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT al.logo_id, a.name FROM artist_logos al JOIN artists a ON al.artist_id = a.id ORDER BY a.name ASC",
+        )?;
+        let mut links_map: HashMap<String, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (logo_id, artist_name) = row?;
+            links_map.entry(logo_id).or_default().push(artist_name);
+        }
+        Ok(links_map)
+    }
+}
+
+pub(crate) fn map_logo(row: &Row) -> rusqlite::Result<Logo> {
+    Ok(Logo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        file_path: row.get(2)?,
+        thumbnail_path: row.get(3)?,
+        vjtools_id: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        sync_status: row.get(7)?,
+        file_size: row.get(8)?,
+        file_modified: row.get(9)?,
+        missing: row.get(10)?,
+        thumbnail_key: row.get(11)?,
+        content_hash: row.get(12)?,
+        perceptual_hash: row.get(13)?,
+        hash_key: row.get(14)?,
+        media: map_media(row)?,
+        probe_key: row.get(22)?,
+        linked_djs: Vec::new(),
+        tags: Vec::new(),
+    })
+}
+
+// Columns 15-21 of LOGO_COLUMNS
+fn map_media(row: &Row) -> rusqlite::Result<Option<MediaInfo>> {
+    let Some(format) = row.get::<_, Option<String>>(15)? else {
+        return Ok(None);
+    };
+    let (width, height): (Option<u32>, Option<u32>) = (row.get(17)?, row.get(18)?);
+    Ok(Some(MediaInfo {
+        format,
+        codec: row.get(16)?,
+        width,
+        height,
+        aspect_ratio: crate::media::aspect_ratio(width, height),
+        has_alpha: row.get(19)?,
+        frame_count: row.get(20)?,
+        duration_ms: row.get(21)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::error::ErrorKind;
+    use crate::repo::{ArtistRepo, CycleRepo, ScheduleRepo};
+    use crate::models::ScheduleEventInput;
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    fn show_time() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(23, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn add_and_list() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+
+        let id = repo.add("Kroma Intro", "/logos/kroma.png", Some("/thumbs/kroma.png")).unwrap();
+        let logos = repo.list().unwrap();
+
+        assert_eq!(logos.len(), 1);
+        assert_eq!(logos[0].id, id);
+        assert_eq!(logos[0].file_path, "/logos/kroma.png");
+        assert_eq!(logos[0].thumbnail_path.as_deref(), Some("/thumbs/kroma.png"));
+        assert_eq!(logos[0].sync_status, "new");
+        assert!(logos[0].linked_djs.is_empty());
+    }
+
+    #[test]
+    fn duplicate_file_path_points_at_existing_logo() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let existing = repo.add("A", "/logos/a.png", None).unwrap();
+
+        let err = repo.add("A again", "/logos/a.png", None).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Duplicate);
+        assert_eq!(err.field.as_deref(), Some("file_path"));
+        assert_eq!(err.entity_id, Some(existing));
+    }
+
+    #[test]
+    fn list_includes_linked_artist_names() {
+        let conn = open_in_memory().unwrap();
+        let artists = ArtistRepo::new(&conn);
+        let logos = LogoRepo::new(&conn);
+        let kroma = artists.add("KROMA").unwrap();
+        let anna = artists.add("Anna").unwrap();
+        let logo = logos.add("Shared", "/logos/shared.png", None).unwrap();
+        logos.add("Unlinked", "/logos/unlinked.png", None).unwrap();
+
+        logos.link_artist(&kroma, &logo, false).unwrap();
+        logos.link_artist(&anna, &logo, true).unwrap();
+
+        let listed = logos.list().unwrap();
+        let shared = listed.iter().find(|l| l.id == logo).unwrap();
+        assert_eq!(shared.linked_djs, vec!["Anna", "KROMA"]);
+        let unlinked = listed.iter().find(|l| l.name == "Unlinked").unwrap();
+        assert!(unlinked.linked_djs.is_empty());
+    }
+
+    #[test]
+    fn link_is_idempotent_and_unlink_removes_it() {
+        let conn = open_in_memory().unwrap();
+        let artists = ArtistRepo::new(&conn);
+        let logos = LogoRepo::new(&conn);
+        let artist = artists.add("KROMA").unwrap();
+        let logo = logos.add("Kroma", "/logos/kroma.png", None).unwrap();
+
+        logos.link_artist(&artist, &logo, false).unwrap();
+        logos.link_artist(&artist, &logo, false).unwrap();
+        assert_eq!(logos.list_for_artist(&artist).unwrap().len(), 1);
+
+        logos.unlink_artist(&artist, &logo).unwrap();
+        assert!(logos.list_for_artist(&artist).unwrap().is_empty());
+    }
+
+    #[test]
+    fn link_to_unknown_artist_is_not_found() {
+        let conn = open_in_memory().unwrap();
+        let logos = LogoRepo::new(&conn);
+        let logo = logos.add("Kroma", "/logos/kroma.png", None).unwrap();
+
+        let err = logos.link_artist("missing", &logo, false).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+        assert_eq!(err.entity_id.as_deref(), Some("missing"));
+    }
+
+    #[test]
+    fn list_for_artist_only_returns_that_artists_logos() {
+        let conn = open_in_memory().unwrap();
+        let artists = ArtistRepo::new(&conn);
+        let logos = LogoRepo::new(&conn);
+        let kroma = artists.add("KROMA").unwrap();
+        let anna = artists.add("Anna").unwrap();
+        let b = logos.add("B", "/logos/b.png", None).unwrap();
+        let a = logos.add("A", "/logos/a.png", None).unwrap();
+        let other = logos.add("Other", "/logos/other.png", None).unwrap();
+        logos.link_artist(&kroma, &b, false).unwrap();
+        logos.link_artist(&kroma, &a, false).unwrap();
+        logos.link_artist(&anna, &other, false).unwrap();
+
+        let ids: Vec<String> = logos.list_for_artist(&kroma).unwrap().into_iter().map(|l| l.id).collect();
+        assert_eq!(ids, vec![a, b]);
+    }
+
+    #[test]
+    fn relinking_upgrades_to_an_override_and_the_flag_can_be_toggled() {
+        let conn = open_in_memory().unwrap();
+        let logos = LogoRepo::new(&conn);
+        let kroma = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        let logo = logos.add("Kroma", "/logos/kroma.png", None).unwrap();
+        let flag = || logos.list_links(Some(&kroma)).unwrap()[0].is_local_override;
+
+        logos.link_artist(&kroma, &logo, false).unwrap();
+        assert!(!flag());
+        logos.link_artist(&kroma, &logo, true).unwrap();
+        assert!(flag());
+        // Linking again without the flag doesn't undo the override
+        logos.link_artist(&kroma, &logo, false).unwrap();
+        assert!(flag());
+        logos.set_link_override(&kroma, &logo, false).unwrap();
+        assert!(!flag());
+
+        let links = logos.list_links(None).unwrap();
+        assert_eq!((links[0].artist_name.as_str(), links[0].logo_name.as_str()), ("KROMA", "Kroma"));
+        assert_eq!(logos.set_link_override(&kroma, "missing", true).unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn remote_links_never_replace_local_overrides() {
+        let conn = open_in_memory().unwrap();
+        let logos = LogoRepo::new(&conn);
+        let kroma = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        let [old, local, remote, both] = ["old", "local", "remote", "both"].map(|name| logos.add(name, &format!("/logos/{}.png", name), None).unwrap());
+        logos.link_artist(&kroma, &old, false).unwrap();
+        logos.link_artist(&kroma, &local, true).unwrap();
+        logos.link_artist(&kroma, &both, true).unwrap();
+
+        // vj.tools no longer lists `old` or `local`, and lists `both` as a plain link
+        let changes = logos.apply_remote_links(&kroma, &[remote.clone(), both.clone(), "unknown".to_string()]).unwrap();
+        assert_eq!(changes, RemoteLinkChanges { added: 1, removed: 1, overrides_kept: 2 });
+
+        let links: Vec<_> = logos.list_links(Some(&kroma)).unwrap().into_iter().map(|l| (l.logo_name, l.is_local_override)).collect();
+        assert_eq!(links, [("both".to_string(), true), ("local".to_string(), true), ("remote".to_string(), false)]);
+    }
+
+    fn mark_synced(conn: &Connection, id: &str) {
+        conn.execute("UPDATE logos SET sync_status = 'synced', updated_at = 0 WHERE id = ?1", [id]).unwrap();
+    }
+
+    #[test]
+    fn update_replaces_fields_and_marks_synced_logo_modified() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let id = repo.add("Kroma", "/logos/kroma.png", Some("/thumbs/kroma.png")).unwrap();
+        mark_synced(&conn, &id);
+
+        repo.update(&id, "Kroma Intro", "/logos/kroma_intro.png", None).unwrap();
+
+        let logo = &repo.list().unwrap()[0];
+        assert_eq!(logo.name, "Kroma Intro");
+        assert_eq!(logo.file_path, "/logos/kroma_intro.png");
+        assert_eq!(logo.thumbnail_path, None);
+        assert_eq!(logo.sync_status, "modified");
+        assert!(logo.updated_at > 0);
+    }
+
+    #[test]
+    fn update_to_existing_path_is_a_duplicate() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        repo.add("A", "/logos/a.png", None).unwrap();
+        let b = repo.add("B", "/logos/b.png", None).unwrap();
+
+        let err = repo.update(&b, "B", "/logos/a.png", None).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Duplicate);
+    }
+
+    #[test]
+    fn delete_unsynced_logo_cascades() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let artist = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        let id = repo.add("Kroma", "/logos/kroma.png", None).unwrap();
+        repo.link_artist(&artist, &id, false).unwrap();
+        CycleRepo::new(&conn).set(std::slice::from_ref(&id)).unwrap();
+        ScheduleRepo::new(&conn).add(&ScheduleEventInput {
+            linked_logo_id: Some(id.clone()),
+            ..ScheduleEventInput::new(show_time(), "KROMA", "dj_set")
+        }).unwrap();
+
+        repo.delete(&id).unwrap();
+
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM logos", [], |r| r.get(0)).unwrap();
+        assert_eq!(rows, 0);
+        assert!(repo.list_for_artist(&artist).unwrap().is_empty());
+        assert!(CycleRepo::new(&conn).list().unwrap().is_empty());
+        assert_eq!(ScheduleRepo::new(&conn).list().unwrap()[0].linked_logo_id, None);
+    }
+
+    #[test]
+    fn delete_synced_logo_leaves_tombstone_and_applies_foreign_key_actions() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let artist = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        let id = repo.add("Kroma", "/logos/kroma.png", None).unwrap();
+        repo.link_artist(&artist, &id, false).unwrap();
+        CycleRepo::new(&conn).set(std::slice::from_ref(&id)).unwrap();
+        ScheduleRepo::new(&conn).add(&ScheduleEventInput {
+            linked_logo_id: Some(id.clone()),
+            ..ScheduleEventInput::new(show_time(), "KROMA", "dj_set")
+        }).unwrap();
+        mark_synced(&conn, &id);
+
+        repo.delete(&id).unwrap();
+
+        assert!(repo.list().unwrap().is_empty());
+        let status: String = conn.query_row("SELECT sync_status FROM logos WHERE id = ?1", [&id], |r| r.get(0)).unwrap();
+        assert_eq!(status, "deleted");
+        let links: i64 = conn.query_row("SELECT COUNT(*) FROM artist_logos", [], |r| r.get(0)).unwrap();
+        assert_eq!(links, 0);
+        assert!(CycleRepo::new(&conn).list().unwrap().is_empty());
+        assert_eq!(ScheduleRepo::new(&conn).list().unwrap()[0].linked_logo_id, None);
+
+        assert_eq!(repo.delete(&id).unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn merge_moves_references_to_the_kept_logo() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let artists = ArtistRepo::new(&conn);
+        let kroma = artists.add("KROMA").unwrap();
+        let anna = artists.add("Anna").unwrap();
+        let keep = repo.add("Kroma", "/logos/kroma.png", None).unwrap();
+        let copy = repo.add("Kroma copy", "/usb/kroma.png", None).unwrap();
+        repo.link_artist(&kroma, &keep, false).unwrap();
+        repo.link_artist(&kroma, &copy, false).unwrap();
+        repo.link_artist(&anna, &copy, true).unwrap();
+        CycleRepo::new(&conn).set(&[copy.clone(), keep.clone()]).unwrap();
+        let event = ScheduleRepo::new(&conn).add(&ScheduleEventInput {
+            linked_logo_id: Some(copy.clone()),
+            ..ScheduleEventInput::new(show_time(), "KROMA", "dj_set")
+        }).unwrap();
+
+        repo.merge(&keep, std::slice::from_ref(&copy)).unwrap();
+
+        let logos = repo.list().unwrap();
+        assert_eq!(logos.len(), 1);
+        assert_eq!(logos[0].linked_djs, vec!["Anna", "KROMA"]);
+        let cycle = CycleRepo::new(&conn).list().unwrap();
+        assert_eq!(cycle.iter().map(|i| i.logo_id.as_str()).collect::<Vec<_>>(), [keep.as_str()]);
+        let schedule = ScheduleRepo::new(&conn).list().unwrap();
+        assert_eq!(schedule.iter().find(|e| e.id == event).unwrap().linked_logo_id.as_deref(), Some(keep.as_str()));
+
+        assert_eq!(repo.merge(&keep, std::slice::from_ref(&keep)).unwrap_err().kind, ErrorKind::InvalidInput);
+        assert_eq!(repo.merge(&keep, &["missing".to_string()]).unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn re_adding_a_deleted_file_revives_the_tombstone() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let id = repo.add("Kroma", "/logos/kroma.png", None).unwrap();
+        mark_synced(&conn, &id);
+        repo.delete(&id).unwrap();
+
+        let again = repo.add("Kroma v2", "/logos/kroma.png", None).unwrap();
+
+        assert_eq!(again, id);
+        let logo = &repo.list().unwrap()[0];
+        assert_eq!(logo.name, "Kroma v2");
+        assert_eq!(logo.sync_status, "modified");
+    }
+
+    #[test]
+    fn tag_filters_combine_across_categories() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let tags = TagRepo::new(&conn);
+        let tag = |name: &str, category| crate::models::TagInput { name: name.to_string(), category };
+        let sponsor = repo.add("Sponsor", "/logos/sponsor.png", None).unwrap();
+        let intro = repo.add("Intro", "/logos/intro.mov", None).unwrap();
+        let loop_ = repo.add("Loop", "/logos/loop.mp4", None).unwrap();
+        let sponsor_tag = tags.add_to_logos(std::slice::from_ref(&sponsor), &[tag("sponsor", TagCategory::Type)]).unwrap().remove(0);
+        let intro_tag = tags.add_to_logos(std::slice::from_ref(&intro), &[tag("intro", TagCategory::Type)]).unwrap().remove(0);
+        let imported = tags
+            .add_to_logos(&[intro.clone(), loop_.clone()], &[tag("imported", TagCategory::Source)])
+            .unwrap()
+            .remove(0);
+
+        let ids = |logos: Vec<Logo>| logos.into_iter().map(|l| l.id).collect::<Vec<_>>();
+        // Any of the selected types...
+        assert_eq!(ids(repo.list_tagged(&[sponsor_tag.clone(), intro_tag.clone()]).unwrap()), [intro.clone(), sponsor]);
+        // ...and the selected source
+        assert_eq!(ids(repo.list_tagged(&[sponsor_tag, intro_tag, imported.clone()]).unwrap()), std::slice::from_ref(&intro));
+        assert_eq!(ids(repo.list_tagged(&[imported]).unwrap()), [intro, loop_]);
+        assert_eq!(repo.list_tagged(&[]).unwrap().len(), 3);
+        assert_eq!(repo.list_tagged(&["missing".to_string()]).unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    fn stored_path(conn: &Connection, id: &str) -> String {
+        conn.query_row("SELECT file_path FROM logos WHERE id = ?1", [id], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn paths_under_the_library_root_are_stored_relative() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let root = Path::new("/media/usb/logos");
+        repo.set_library_root(Some(root)).unwrap();
+
+        let id = repo.add("Kroma", "/media/usb/logos/kroma/kroma.png", None).unwrap();
+        let outside = repo.add("Nyx", "/elsewhere/nyx.png", None).unwrap();
+
+        assert_eq!(stored_path(&conn, &id), "kroma/kroma.png");
+        assert_eq!(stored_path(&conn, &outside), "/elsewhere/nyx.png");
+        let expected = root.join("kroma").join("kroma.png").to_string_lossy().into_owned();
+        assert_eq!(repo.find_id_by_path(&expected).unwrap().as_deref(), Some(id.as_str()));
+        let logo = repo.list().unwrap().into_iter().find(|l| l.id == id).unwrap();
+        assert_eq!(logo.file_path, expected);
+
+        // Moving the library moves every relative logo with it
+        repo.set_library_root(Some(Path::new("/mnt/nas/logos"))).unwrap();
+        let logo = repo.list().unwrap().into_iter().find(|l| l.id == id).unwrap();
+        assert_eq!(logo.file_path, Path::new("/mnt/nas/logos/kroma/kroma.png").to_string_lossy());
+    }
+
+    #[test]
+    fn setting_the_root_rewrites_existing_paths_without_marking_them_modified() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let id = repo.add("Kroma", "/media/usb/logos/kroma.png", None).unwrap();
+        repo.add("Nyx", "/elsewhere/nyx.png", None).unwrap();
+        mark_synced(&conn, &id);
+
+        assert_eq!(repo.set_library_root(Some(Path::new("/media/usb/logos"))).unwrap(), 1);
+        assert_eq!(repo.library_root().unwrap().as_deref(), Some(Path::new("/media/usb/logos")));
+        assert_eq!(stored_path(&conn, &id), "kroma.png");
+        let logo = repo.list().unwrap().into_iter().find(|l| l.id == id).unwrap();
+        assert_eq!(logo.sync_status, "synced");
+
+        assert_eq!(repo.set_library_root(None).unwrap(), 1);
+        assert_eq!(repo.library_root().unwrap(), None);
+        assert_eq!(stored_path(&conn, &id), Path::new("/media/usb/logos/kroma.png").to_string_lossy());
+    }
+
+    #[test]
+    fn moving_the_root_merges_logos_that_end_up_on_the_same_file() {
+        let conn = open_in_memory().unwrap();
+        let repo = LogoRepo::new(&conn);
+        let artist = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        repo.set_library_root(Some(Path::new("/media/usb/logos"))).unwrap();
+        let kept = repo.add("Kroma", "/media/usb/logos/kroma.png", None).unwrap();
+        let deleted = repo.add("Old", "/media/usb/logos/old.png", None).unwrap();
+        mark_synced(&conn, &deleted);
+        repo.delete(&deleted).unwrap();
+        // Outside the current root, so stored absolute
+        let duplicate = repo.add("Kroma (ssd)", "/media/ssd/logos/kroma.png", None).unwrap();
+        let old = repo.add("Old", "/media/ssd/logos/old.png", None).unwrap();
+        repo.link_artist(&artist, &duplicate, false).unwrap();
+
+        // Relative paths now point into the new root too
+        assert_eq!(repo.set_library_root(Some(Path::new("/media/ssd/logos"))).unwrap(), 2);
+        let ids: Vec<_> = repo.list().unwrap().into_iter().map(|l| l.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&kept) && ids.contains(&old));
+        assert_eq!(repo.list_for_artist(&artist).unwrap()[0].id, kept);
+        assert_eq!(stored_path(&conn, &old), "old.png");
+        assert_eq!(stored_path(&conn, &deleted), "/media/ssd/logos/old.png");
+    }
+}
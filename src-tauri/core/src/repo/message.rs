@@ -0,0 +1,266 @@
+use super::{first_text, first_timestamp};
+use crate::error::{AppError, AppResult};
+use crate::models::{ConversationSummary, MessageStatus, StoredMessage};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde_json::Value;
+use uuid::Uuid;
+
+// Where vj.tools puts a message's send time
+const SENT_AT_KEYS: &[&str] = &["sent_at", "sentAt", "created_at"];
+
+// Messages between DJs and VJs. Synced messages mirror vj.tools, so there
+// is no sync status or tombstone; queued ones are the outbox.
+pub struct MessageRepo<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> MessageRepo<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    // Saves messages as fetched from vj.tools. Storing the same message
+    // again updates it, so a sync can be repeated safely; a message already
+    // read here stays read even if vj.tools doesn't know yet. Every message
+    // needs an id, a conversation, a sender and content; if one doesn't,
+    // nothing is stored. Returns how many messages were stored.
+    pub fn store(&self, messages: &[Value]) -> AppResult<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (index, message) in messages.iter().enumerate() {
+            self.upsert(&parse_message(message).map_err(|e| e.context(&format!("Message {}", index + 1)))?)?;
+        }
+        tx.commit()?;
+        Ok(messages.len())
+    }
+
+    // Every stored message, oldest first; only those of one conversation if
+    // `conversation_id` is given
+    pub fn list(&self, conversation_id: Option<&str>) -> AppResult<Vec<StoredMessage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, conversation_id, sender_id, content, sent_at, read_at, status FROM messages
+             WHERE ?1 IS NULL OR conversation_id = ?1
+             ORDER BY sent_at ASC, id ASC",
+        )?;
+        let messages = stmt.query_map([conversation_id], map_message)?.collect::<rusqlite::Result<_>>()?;
+        Ok(messages)
+    }
+
+    // Conversations with stored messages, most recent first
+    pub fn conversations(&self) -> AppResult<Vec<ConversationSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT conversation_id, MAX(sent_at), COUNT(*),
+                    SUM(read_at IS NULL AND status = 'synced'), SUM(status = 'queued')
+             FROM messages GROUP BY conversation_id
+             ORDER BY MAX(sent_at) DESC, conversation_id ASC",
+        )?;
+        let conversations = stmt
+            .query_map([], |row| {
+                Ok(ConversationSummary {
+                    id: row.get(0)?,
+                    last_message_at: row.get(1)?,
+                    message_count: row.get(2)?,
+                    unread_count: row.get(3)?,
+                    queued_count: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(conversations)
+    }
+
+    // Keeps a message written here until it can be sent. It counts as read
+    // and has a local id until `mark_delivered`.
+    pub fn queue(&self, conversation_id: &str, sender_id: &str, content: &str) -> AppResult<StoredMessage> {
+        if content.trim().is_empty() {
+            return Err(AppError::invalid("content", "Message can't be empty"));
+        }
+        let now = Utc::now();
+        let message = StoredMessage {
+            id: format!("local-{}", Uuid::new_v4()),
+            conversation_id: conversation_id.to_string(),
+            sender_id: sender_id.to_string(),
+            content: content.to_string(),
+            sent_at: now,
+            read_at: Some(now),
+            status: MessageStatus::Queued,
+        };
+        self.upsert(&message)?;
+        Ok(message)
+    }
+
+    // Queued messages in the order they were written, for sending
+    pub fn queued(&self) -> AppResult<Vec<StoredMessage>> {
+        Ok(self.list(None)?.into_iter().filter(|m| m.status == MessageStatus::Queued).collect())
+    }
+
+    // Replaces a queued message with the one vj.tools stored when it was
+    // sent. Only the id vj.tools gave it, and the send time if there is one,
+    // are taken from `delivered`; the rest is what was queued.
+    pub fn mark_delivered(&self, local_id: &str, delivered: &Value) -> AppResult<StoredMessage> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut message = self.conn
+            .query_row(
+                "SELECT id, conversation_id, sender_id, content, sent_at, read_at, status FROM messages
+                 WHERE id = ?1 AND status = 'queued'",
+                [local_id],
+                map_message,
+            )
+            .optional()?
+            .ok_or_else(|| AppError::not_found("Queued message", local_id))?;
+        message.id = first_text(delivered, &["id"])
+            .ok_or_else(|| AppError::invalid("id", "Delivered message has no id").with_entity_id(local_id))?;
+        if let Some(sent_at) = first_timestamp(delivered, SENT_AT_KEYS) {
+            message.sent_at = sent_at;
+        }
+        message.status = MessageStatus::Synced;
+        self.conn.execute("DELETE FROM messages WHERE id = ?1", [local_id])?;
+        self.upsert(&message)?;
+        tx.commit()?;
+        Ok(message)
+    }
+
+    // Marks messages read; those already read keep their time. Returns how
+    // many were newly marked.
+    pub fn mark_read(&self, ids: &[String]) -> AppResult<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let now = Utc::now();
+        let mut marked = 0;
+        for id in ids {
+            marked += self.conn.execute("UPDATE messages SET read_at = ?2 WHERE id = ?1 AND read_at IS NULL", params![id, now])?;
+        }
+        tx.commit()?;
+        Ok(marked)
+    }
+
+    fn upsert(&self, message: &StoredMessage) -> AppResult<()> {
+        self.conn.execute(
+            "INSERT INTO messages (id, conversation_id, sender_id, content, sent_at, read_at, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (id) DO UPDATE SET
+                conversation_id = excluded.conversation_id, sender_id = excluded.sender_id,
+                content = excluded.content, sent_at = excluded.sent_at,
+                read_at = COALESCE(messages.read_at, excluded.read_at), status = excluded.status",
+            params![
+                message.id,
+                message.conversation_id,
+                message.sender_id,
+                message.content,
+                message.sent_at,
+                message.read_at,
+                message.status
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+fn map_message(row: &Row) -> rusqlite::Result<StoredMessage> {
+    Ok(StoredMessage {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        sender_id: row.get(2)?,
+        content: row.get(3)?,
+        sent_at: row.get(4)?,
+        read_at: row.get(5)?,
+        status: row.get(6)?,
+    })
+}
+
+// A message from vj.tools. Field names come in snake_case or camelCase
+// depending on the endpoint; a message without a send time is dated now.
+fn parse_message(message: &Value) -> AppResult<StoredMessage> {
+    let field = |keys: &[&str], name: &str| {
+        first_text(message, keys)
+            .ok_or_else(|| AppError::invalid(name, format!("Message has no {}", name.replace('_', " "))))
+    };
+    let time = |keys: &[&str]| first_timestamp(message, keys);
+    Ok(StoredMessage {
+        id: field(&["id"], "id")?,
+        conversation_id: field(&["conversation_id", "conversationId"], "conversation_id")?,
+        sender_id: field(&["sender_id", "senderId"], "sender_id")?,
+        content: field(&["content", "text"], "content")?,
+        sent_at: time(SENT_AT_KEYS).unwrap_or_else(Utc::now),
+        read_at: time(&["read_at", "readAt"]),
+        status: MessageStatus::Synced,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::error::ErrorKind;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    #[test]
+    fn syncing_is_idempotent_and_keeps_local_read_state() {
+        let conn = open_in_memory().unwrap();
+        let repo = MessageRepo::new(&conn);
+        let fetched = [
+            json!({"id": "m1", "conversation_id": "c1", "sender_id": "dj", "content": "Intro now?", "sent_at": "2026-10-17 23:00:00"}),
+            json!({"id": 2, "conversationId": "c1", "senderId": "vj", "content": "Ready", "sentAt": "2026-10-17T23:01:00Z"}),
+            json!({"id": "m3", "conversation_id": "c2", "sender_id": "dj", "content": "Thanks", "sent_at": 1_792_364_400_000i64}),
+        ];
+        repo.store(&fetched).unwrap();
+        repo.mark_read(&["m1".to_string()]).unwrap();
+        // The same sync again, with vj.tools unaware of the read
+        repo.store(&fetched).unwrap();
+
+        let c1 = repo.list(Some("c1")).unwrap();
+        assert_eq!(c1.len(), 2);
+        assert_eq!(c1[1].id, "2");
+        assert!(c1[0].read_at.is_some());
+        assert_eq!(c1[0].sent_at, Utc.with_ymd_and_hms(2026, 10, 17, 23, 0, 0).unwrap());
+
+        let conversations = repo.conversations().unwrap();
+        assert_eq!(conversations.len(), 2);
+        let c1 = conversations.iter().find(|c| c.id == "c1").unwrap();
+        assert_eq!((c1.message_count, c1.unread_count), (2, 1));
+
+        let err = repo.store(&[json!({"id": "m4", "conversation_id": "c1", "content": "?"})]).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("sender_id"));
+        assert_eq!(repo.list(None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn queued_messages_wait_until_delivered() {
+        let conn = open_in_memory().unwrap();
+        let repo = MessageRepo::new(&conn);
+        let queued = repo.queue("c1", "vj", "Logo is up").unwrap();
+        assert_eq!(repo.queue("c1", "vj", "  ").unwrap_err().field.as_deref(), Some("content"));
+
+        assert_eq!(repo.queued().unwrap(), std::slice::from_ref(&queued));
+        assert_eq!(repo.conversations().unwrap()[0].queued_count, 1);
+
+        // vj.tools may answer with nothing but the new id
+        let delivered = json!({"id": "m9"});
+        let stored = repo.mark_delivered(&queued.id, &delivered).unwrap();
+        assert_eq!(stored.status, MessageStatus::Synced);
+        assert_eq!((stored.conversation_id.as_str(), stored.content.as_str()), ("c1", "Logo is up"));
+        assert_eq!(stored.sent_at, queued.sent_at);
+        assert!(repo.queued().unwrap().is_empty());
+        let messages = repo.list(None).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, "m9");
+        assert!(messages[0].read_at.is_some());
+
+        assert_eq!(repo.mark_delivered(&queued.id, &delivered).unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn delivery_takes_the_id_and_send_time_from_vj_tools() {
+        let conn = open_in_memory().unwrap();
+        let repo = MessageRepo::new(&conn);
+        let queued = repo.queue("c1", "vj", "Logo is up").unwrap();
+        let err = repo.mark_delivered(&queued.id, &json!({"content": "Logo is up"})).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("id"));
+        assert_eq!(repo.queued().unwrap().len(), 1);
+
+        let delivered = json!({"id": 9, "sentAt": "2026-10-17T23:05:00Z", "content": "ignored"});
+        let stored = repo.mark_delivered(&queued.id, &delivered).unwrap();
+        assert_eq!(stored.id, "9");
+        assert_eq!(stored.sent_at, Utc.with_ymd_and_hms(2026, 10, 17, 23, 5, 0).unwrap());
+        assert_eq!(repo.list(None).unwrap(), [stored]);
+    }
+}
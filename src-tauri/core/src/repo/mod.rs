@@ -0,0 +1,125 @@
+//! Typed access to each table group. Every repo borrows a plain
+//! `rusqlite::Connection`, so callers decide how the connection is owned and
+//! locked.
+//!
+//! Rows that have been synced are never hard-deleted: they are kept as
+//! tombstones with `sync_status = 'deleted'` so the deletion can be pushed to
+//! vj.tools, and every listing skips them. Rows that never left this machine
+//! (`sync_status = 'new'`) are deleted outright.
+
+use crate::error::{AppError, AppResult};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use rusqlite::Connection;
+use serde_json::Value;
+
+mod artist;
+mod cycle;
+mod cycle_group;
+mod logo;
+mod message;
+mod schedule;
+mod tag;
+mod vjtools_event;
+
+pub use artist::ArtistRepo;
+pub use cycle::CycleRepo;
+pub use cycle_group::CycleGroupRepo;
+pub use logo::LogoRepo;
+pub use message::MessageRepo;
+pub(crate) use logo::{map_logo, LOGO_COLUMNS};
+pub use schedule::ScheduleRepo;
+pub use tag::TagRepo;
+pub use vjtools_event::VjToolsEventRepo;
+
+// SET fragment for edits: unsynced rows stay 'new', everything else becomes
+// 'modified'
+pub(crate) const MARK_MODIFIED: &str =
+    "sync_status = CASE sync_status WHEN 'new' THEN 'new' ELSE 'modified' END";
+
+// Conflict clause for inserts into artist_logos: a link that already
+// exists stays a local override if either side is one
+pub(crate) const KEEP_OVERRIDE: &str = "ON CONFLICT (artist_id, logo_id)
+     DO UPDATE SET is_local_override = MAX(is_local_override, excluded.is_local_override)";
+
+// Turns "no row was touched" into a NotFound error for a missing (or
+// already deleted) id
+pub(crate) fn expect_one(changed: usize, entity: &str, id: &str) -> AppResult<()> {
+    if changed == 0 {
+        Err(AppError::not_found(entity, id))
+    } else {
+        Ok(())
+    }
+}
+
+// NotFound unless `id` is a live (non-deleted) row of `table`
+pub(crate) fn ensure_exists(conn: &Connection, table: &str, entity: &str, id: &str) -> AppResult<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM {} WHERE id = ?1 AND sync_status != 'deleted')", table),
+        [id],
+        |row| row.get(0),
+    )?;
+    if exists {
+        Ok(())
+    } else {
+        Err(AppError::not_found(entity, id))
+    }
+}
+
+// A string or number from vj.tools JSON as trimmed text; anything else
+// (null, objects, blank strings) is None
+pub(crate) fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+// The first of `keys` that has a usable value; vj.tools endpoints and older
+// clients don't agree on field names
+pub(crate) fn first_text(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| text(value.get(key)))
+}
+
+// The first of `keys` that holds a readable time
+pub(crate) fn first_timestamp(value: &Value, keys: &[&str]) -> Option<DateTime<Utc>> {
+    keys.iter().find_map(|key| timestamp(value.get(key)?))
+}
+
+// Like `first_timestamp`, but a time without an offset is local wall-clock
+// time, as in the schedule, rather than UTC
+pub(crate) fn first_local_timestamp(value: &Value, keys: &[&str]) -> Option<DateTime<Utc>> {
+    keys.iter().find_map(|key| {
+        let value = value.get(key)?;
+        match naive_timestamp(value) {
+            Some(naive) => Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)),
+            None => timestamp(value),
+        }
+    })
+}
+
+// RFC 3339, SQLite's 'YYYY-MM-DD HH:MM:SS' (UTC), or Unix time in seconds
+// or milliseconds
+pub(crate) fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s.trim())
+            .map(|t| t.with_timezone(&Utc))
+            .ok()
+            .or_else(|| naive_timestamp(value).map(|t| t.and_utc())),
+        Value::Number(n) => {
+            let n = n.as_i64()?;
+            // Seconds stay below this until the year 5138
+            if n.abs() < 100_000_000_000 {
+                Utc.timestamp_opt(n, 0).single()
+            } else {
+                Utc.timestamp_millis_opt(n).single()
+            }
+        }
+        _ => None,
+    }
+}
+
+// 'YYYY-MM-DD HH:MM:SS', which says nothing about its time zone
+fn naive_timestamp(value: &Value) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.as_str()?.trim(), "%Y-%m-%d %H:%M:%S%.f").ok()
+}
@@ -0,0 +1,378 @@
+use super::{ensure_exists, expect_one, ArtistRepo, MARK_MODIFIED};
+use crate::artist_match::match_artist;
+use crate::current_timestamp;
+use crate::error::{AppError, AppResult};
+use crate::models::{ScheduleConflict, ScheduleEventInput, ScheduleItem, ScheduleSettings};
+use crate::schedule::{find_conflicts, validate_event_times};
+use rusqlite::{params, Connection, Row};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub struct ScheduleRepo<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> ScheduleRepo<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    // All schedule events that haven't been deleted, in start order. The
+    // stored 'YYYY-MM-DD HH:MM:SS' text sorts chronologically, so shows that
+    // run past midnight stay in order.
+    pub fn list(&self) -> AppResult<Vec<ScheduleItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, starts_at, ends_at, name, event_type, linked_logo_id, priority
+             FROM schedule_events WHERE sync_status != 'deleted' ORDER BY starts_at ASC, name ASC",
+        )?;
+        let items = stmt.query_map([], map_schedule_item)?.collect::<rusqlite::Result<_>>()?;
+        Ok(items)
+    }
+
+    // Adds an event. Without a logo, an event named after an artist gets
+    // that artist's preferred logo.
+    pub fn add(&self, event: &ScheduleEventInput) -> AppResult<String> {
+        let new_id = Uuid::new_v4().to_string();
+        let now = current_timestamp();
+        self.validate(&new_id, event)?;
+        let linked_logo_id = match &event.linked_logo_id {
+            Some(logo_id) => Some(logo_id.clone()),
+            None => self.preferred_logo(&event.name)?,
+        };
+
+        self.conn.execute(
+            "INSERT INTO schedule_events (id, starts_at, ends_at, name, event_type, linked_logo_id, priority, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                new_id,
+                event.starts_at,
+                event.ends_at,
+                event.name,
+                event.event_type,
+                linked_logo_id,
+                event.priority,
+                now,
+                now
+            ],
+        )?;
+        Ok(new_id)
+    }
+
+    // Replaces every editable field of an event
+    pub fn update(&self, id: &str, event: &ScheduleEventInput) -> AppResult<()> {
+        self.validate(id, event)?;
+        let changed = self.conn.execute(
+            &format!(
+                "UPDATE schedule_events
+                 SET starts_at = ?2, ends_at = ?3, name = ?4, event_type = ?5, linked_logo_id = ?6, priority = ?7,
+                     updated_at = ?8, {}
+                 WHERE id = ?1 AND sync_status != 'deleted'",
+                MARK_MODIFIED
+            ),
+            params![
+                id,
+                event.starts_at,
+                event.ends_at,
+                event.name,
+                event.event_type,
+                event.linked_logo_id,
+                event.priority,
+                current_timestamp()
+            ],
+        )?;
+        expect_one(changed, "Schedule event", id)
+    }
+
+    // Deletes an event, leaving a tombstone if it has been synced
+    pub fn delete(&self, id: &str) -> AppResult<()> {
+        let removed = self.conn.execute("DELETE FROM schedule_events WHERE id = ?1 AND sync_status = 'new'", [id])?;
+        if removed > 0 {
+            return Ok(());
+        }
+        let changed = self.conn.execute(
+            "UPDATE schedule_events SET sync_status = 'deleted', updated_at = ?2 WHERE id = ?1 AND sync_status != 'deleted'",
+            params![id, current_timestamp()],
+        )?;
+        expect_one(changed, "Schedule event", id)
+    }
+
+    // Ids of live events imported from a calendar, keyed by the entry's UID
+    pub fn ids_by_source_uid(&self) -> AppResult<HashMap<String, String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source_uid, id FROM schedule_events
+             WHERE source_uid IS NOT NULL AND sync_status != 'deleted'",
+        )?;
+        let ids = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(ids)
+    }
+
+    // Remembers which calendar entry an event came from
+    pub fn set_source_uid(&self, id: &str, source_uid: Option<&str>) -> AppResult<()> {
+        let changed = self.conn.execute(
+            "UPDATE schedule_events SET source_uid = ?2 WHERE id = ?1 AND sync_status != 'deleted'",
+            params![id, source_uid],
+        )?;
+        expect_one(changed, "Schedule event", id)
+    }
+
+    // Every pair of live events that overlap
+    pub fn conflicts(&self) -> AppResult<Vec<ScheduleConflict>> {
+        Ok(find_conflicts(&self.list()?))
+    }
+
+    pub fn settings(&self) -> AppResult<ScheduleSettings> {
+        let settings = self.conn.query_row(
+            "SELECT strict_conflicts FROM schedule_settings WHERE id = 1",
+            [],
+            |row| Ok(ScheduleSettings { strict_conflicts: row.get(0)? }),
+        )?;
+        Ok(settings)
+    }
+
+    // In strict mode `add` and `update` refuse events that overlap another one
+    pub fn set_strict_conflicts(&self, enabled: bool) -> AppResult<()> {
+        self.conn.execute(
+            "UPDATE schedule_settings SET strict_conflicts = ?1 WHERE id = 1",
+            [enabled],
+        )?;
+        Ok(())
+    }
+
+    // In strict mode, a Conflict error naming the first event `candidate`
+    // would overlap (ignoring the stored version of `candidate` itself)
+    fn check_strict(&self, candidate: &ScheduleItem) -> AppResult<()> {
+        if !self.settings()?.strict_conflicts {
+            return Ok(());
+        }
+        let mut schedule = self.list()?;
+        schedule.retain(|item| item.id != candidate.id);
+        schedule.push(candidate.clone());
+
+        let clash = find_conflicts(&schedule)
+            .into_iter()
+            .find(|c| c.first_id == candidate.id || c.second_id == candidate.id);
+        match clash {
+            Some(c) => {
+                let (other_id, other_name) = if c.first_id == candidate.id {
+                    (c.second_id, c.second_name)
+                } else {
+                    (c.first_id, c.first_name)
+                };
+                Err(AppError::conflict(format!("Event overlaps '{}'", other_name))
+                    .with_field("starts_at")
+                    .with_entity_id(other_id))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn validate(&self, id: &str, event: &ScheduleEventInput) -> AppResult<()> {
+        validate_event_times(event.starts_at, event.ends_at)?;
+        self.ensure_logo(event.linked_logo_id.as_deref())?;
+        self.check_strict(&event.to_item(id))
+    }
+
+    // Preferred logo of the artist an event name refers to, if any
+    fn preferred_logo(&self, name: &str) -> AppResult<Option<String>> {
+        let artists = ArtistRepo::new(self.conn).list()?;
+        Ok(match_artist(&artists, name).and_then(|(artist, _)| artist.profile.preferred_logo_id.clone()))
+    }
+
+    fn ensure_logo(&self, linked_logo_id: Option<&str>) -> AppResult<()> {
+        match linked_logo_id {
+            Some(logo_id) => ensure_exists(self.conn, "logos", "Logo", logo_id)
+                .map_err(|e| e.with_field("linked_logo_id")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn map_schedule_item(row: &Row) -> rusqlite::Result<ScheduleItem> {
+    Ok(ScheduleItem {
+        id: row.get(0)?,
+        starts_at: row.get(1)?,
+        ends_at: row.get(2)?,
+        name: row.get(3)?,
+        event_type: row.get(4)?,
+        linked_logo_id: row.get(5)?,
+        priority: row.get(6)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::error::ErrorKind;
+    use crate::models::ArtistProfile;
+    use crate::repo::LogoRepo;
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+    // 17 October 2026 at `h:m`; hours past 23 roll into the next day
+    fn at(h: i64, m: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(0, 0, 0).unwrap()
+            + Duration::hours(h)
+            + Duration::minutes(m)
+    }
+
+    fn event(
+        starts_at: NaiveDateTime,
+        ends_at: Option<NaiveDateTime>,
+        name: &str,
+        event_type: &str,
+        logo: Option<&str>,
+        priority: i32,
+    ) -> ScheduleEventInput {
+        ScheduleEventInput {
+            ends_at,
+            linked_logo_id: logo.map(str::to_string),
+            priority,
+            ..ScheduleEventInput::new(starts_at, name, event_type)
+        }
+    }
+
+    #[test]
+    fn add_and_list_ordered_by_time() {
+        let conn = open_in_memory().unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        repo.add(&event(at(23, 0), Some(at(24, 0)), "KROMA", "dj_set", None, 0)).unwrap();
+        repo.add(&event(at(21, 30), None, "Doors", "special", None, 0)).unwrap();
+
+        let items = repo.list().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].starts_at, at(21, 30));
+        assert_eq!(items[0].ends_at, None);
+        assert_eq!(items[1].name, "KROMA");
+        assert_eq!(items[1].event_type, "dj_set");
+        assert_eq!(items[1].ends_at, Some(at(24, 0)));
+    }
+
+    #[test]
+    fn events_after_midnight_sort_after_the_evening() {
+        let conn = open_in_memory().unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        repo.add(&event(at(25, 30), None, "Closing", "dj_set", None, 0)).unwrap();
+        repo.add(&event(at(22, 0), None, "Warmup", "dj_set", None, 0)).unwrap();
+        // Second day of a weekend festival
+        repo.add(&event(at(44, 0), None, "Day 2", "special", None, 0)).unwrap();
+
+        let names: Vec<String> = repo.list().unwrap().into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["Warmup", "Closing", "Day 2"]);
+    }
+
+    #[test]
+    fn end_before_start_is_rejected() {
+        let conn = open_in_memory().unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        let err = repo.add(&event(at(23, 0), Some(at(22, 0)), "Backwards", "dj_set", None, 0)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidInput);
+        assert_eq!(err.field.as_deref(), Some("ends_at"));
+        assert!(repo.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn linked_logo_is_cleared_when_logo_is_deleted() {
+        let conn = open_in_memory().unwrap();
+        let logo = LogoRepo::new(&conn).add("Sponsor", "/logos/sponsor.png", None).unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        repo.add(&event(at(22, 0), Some(at(22, 1)), "Sponsor slot", "sponsor", Some(&logo), 0)).unwrap();
+        assert_eq!(repo.list().unwrap()[0].linked_logo_id.as_deref(), Some(logo.as_str()));
+
+        conn.execute("DELETE FROM logos WHERE id = ?1", [&logo]).unwrap();
+        assert_eq!(repo.list().unwrap()[0].linked_logo_id, None);
+    }
+
+    #[test]
+    fn scheduled_artists_get_their_preferred_logo() {
+        let conn = open_in_memory().unwrap();
+        let logo = LogoRepo::new(&conn).add("Kroma", "/logos/kroma.png", None).unwrap();
+        let other = LogoRepo::new(&conn).add("Sponsor", "/logos/sponsor.png", None).unwrap();
+        let artists = ArtistRepo::new(&conn);
+        let kroma = artists.add("KROMA").unwrap();
+        let profile = ArtistProfile { preferred_logo_id: Some(logo.clone()), ..ArtistProfile::default() };
+        artists.update_profile(&kroma, &profile).unwrap();
+
+        let repo = ScheduleRepo::new(&conn);
+        repo.add(&event(at(23, 0), None, "DJ Kroma (live)", "dj_set", None, 0)).unwrap();
+        repo.add(&event(at(24, 0), None, "KROMA encore", "dj_set", Some(&other), 0)).unwrap();
+        repo.add(&event(at(22, 0), None, "Doors", "special", None, 0)).unwrap();
+
+        let linked: Vec<_> = repo.list().unwrap().into_iter().map(|e| e.linked_logo_id).collect();
+        assert_eq!(linked, [None, Some(logo), Some(other)]);
+    }
+
+    #[test]
+    fn unknown_linked_logo_is_rejected() {
+        let conn = open_in_memory().unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        let err = repo.add(&event(at(22, 0), None, "Ghost", "special", Some("missing"), 0)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+        assert_eq!(err.field.as_deref(), Some("linked_logo_id"));
+    }
+
+    #[test]
+    fn update_replaces_fields() {
+        let conn = open_in_memory().unwrap();
+        let logo = LogoRepo::new(&conn).add("Kroma", "/logos/kroma.png", None).unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        let id = repo.add(&event(at(23, 0), Some(at(24, 0)), "KROMA", "dj_set", None, 0)).unwrap();
+        conn.execute("UPDATE schedule_events SET sync_status = 'synced' WHERE id = ?1", [&id]).unwrap();
+
+        repo.update(&id, &event(at(23, 30), None, "KROMA (live)", "dj_set", Some(&logo), 0)).unwrap();
+
+        let item = &repo.list().unwrap()[0];
+        assert_eq!(item.starts_at, at(23, 30));
+        assert_eq!(item.name, "KROMA (live)");
+        assert_eq!(item.ends_at, None);
+        assert_eq!(item.linked_logo_id.as_deref(), Some(logo.as_str()));
+        let status: String = conn.query_row("SELECT sync_status FROM schedule_events WHERE id = ?1", [&id], |r| r.get(0)).unwrap();
+        assert_eq!(status, "modified");
+    }
+
+    #[test]
+    fn delete_hides_event_and_tombstones_synced_ones() {
+        let conn = open_in_memory().unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        let unsynced = repo.add(&event(at(22, 0), None, "Doors", "special", None, 0)).unwrap();
+        let synced = repo.add(&event(at(23, 0), None, "KROMA", "dj_set", None, 0)).unwrap();
+        conn.execute("UPDATE schedule_events SET sync_status = 'synced' WHERE id = ?1", [&synced]).unwrap();
+
+        repo.delete(&unsynced).unwrap();
+        repo.delete(&synced).unwrap();
+
+        assert!(repo.list().unwrap().is_empty());
+        let rows: Vec<String> = conn
+            .prepare("SELECT sync_status FROM schedule_events")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(rows, vec!["deleted"]);
+        let err = repo.update(&synced, &event(at(23, 0), None, "x", "dj_set", None, 0)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn strict_mode_rejects_overlaps() {
+        let conn = open_in_memory().unwrap();
+        let repo = ScheduleRepo::new(&conn);
+        let kroma = repo.add(&event(at(23, 0), Some(at(24, 0)), "KROMA", "dj_set", None, 0)).unwrap();
+
+        // Allowed but reported while strict mode is off
+        repo.add(&event(at(23, 30), Some(at(23, 45)), "Sponsor", "sponsor", None, 1)).unwrap();
+        assert_eq!(repo.conflicts().unwrap().len(), 1);
+
+        repo.set_strict_conflicts(true).unwrap();
+        assert!(repo.settings().unwrap().strict_conflicts);
+        let err = repo.add(&event(at(23, 50), None, "Stinger", "special", None, 0)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Conflict);
+        assert_eq!(err.entity_id.as_deref(), Some(kroma.as_str()));
+
+        // Moving an event onto itself isn't a clash; after the set it is fine
+        repo.update(&kroma, &event(at(23, 0), Some(at(23, 30)), "KROMA", "dj_set", None, 0)).unwrap();
+        repo.add(&event(at(24, 0), None, "Closing", "dj_set", None, 0)).unwrap();
+    }
+}
@@ -0,0 +1,186 @@
+use super::{ensure_exists, expect_one};
+use crate::current_timestamp;
+use crate::error::{AppError, AppResult};
+use crate::models::{Tag, TagCategory, TagInput};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Tags are local organisation like cycle groups, so they are deleted
+// outright instead of being tombstoned. Tags are created on first use by
+// `add_to_logos`; there is no separate create step.
+pub struct TagRepo<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> TagRepo<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    // Tags by category, then name; all of them if `category` is None
+    pub fn list(&self, category: Option<TagCategory>) -> AppResult<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, category FROM tags
+             WHERE ?1 IS NULL OR category = ?1
+             ORDER BY category ASC, name COLLATE NOCASE ASC",
+        )?;
+        let tags = stmt.query_map([category], map_tag)?.collect::<rusqlite::Result<_>>()?;
+        Ok(tags)
+    }
+
+    // Puts every tag in `tags` on every logo in `logo_ids`, creating tags
+    // that don't exist yet. Tags a logo already has are left alone. Returns
+    // the tag ids in the order given. Runs in one transaction.
+    pub fn add_to_logos(&self, logo_ids: &[String], tags: &[TagInput]) -> AppResult<Vec<String>> {
+        let tx = self.conn.unchecked_transaction()?;
+        for logo_id in logo_ids {
+            ensure_exists(self.conn, "logos", "Logo", logo_id)?;
+        }
+        let mut tag_ids = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let tag_id = self.find_or_create(tag)?;
+            for logo_id in logo_ids {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO logo_tags (logo_id, tag_id) VALUES (?1, ?2)",
+                    params![logo_id, tag_id],
+                )?;
+            }
+            tag_ids.push(tag_id);
+        }
+        tx.commit()?;
+        Ok(tag_ids)
+    }
+
+    // Takes the tags in `tag_ids` off the logos in `logo_ids`. Pairs that
+    // aren't tagged are skipped; returns how many links were removed.
+    pub fn remove_from_logos(&self, logo_ids: &[String], tag_ids: &[String]) -> AppResult<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut removed = 0;
+        for logo_id in logo_ids {
+            for tag_id in tag_ids {
+                removed += self.conn.execute(
+                    "DELETE FROM logo_tags WHERE logo_id = ?1 AND tag_id = ?2",
+                    params![logo_id, tag_id],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    // Deletes a tag and takes it off every logo
+    pub fn delete(&self, id: &str) -> AppResult<()> {
+        let changed = self.conn.execute("DELETE FROM tags WHERE id = ?1", [id])?;
+        expect_one(changed, "Tag", id)
+    }
+
+    // logo_id -> its tags, by category then name
+    pub(crate) fn by_logo(&self) -> AppResult<HashMap<String, Vec<Tag>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT lt.logo_id, t.id, t.name, t.category FROM logo_tags lt JOIN tags t ON lt.tag_id = t.id
+             ORDER BY t.category ASC, t.name COLLATE NOCASE ASC",
+        )?;
+        let mut tags_map: HashMap<String, Vec<Tag>> = HashMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, map_tag_at(row, 1)?)))?;
+        for row in rows {
+            let (logo_id, tag) = row?;
+            tags_map.entry(logo_id).or_default().push(tag);
+        }
+        Ok(tags_map)
+    }
+
+    // The tag with this name in this category, ignoring case, or a new one
+    fn find_or_create(&self, tag: &TagInput) -> AppResult<String> {
+        let name = tag.name.trim();
+        if name.is_empty() {
+            return Err(AppError::invalid("name", "Tag name can't be empty"));
+        }
+        let existing: Option<String> = self.conn.query_row(
+            "SELECT id FROM tags WHERE category = ?1 AND name = ?2 COLLATE NOCASE",
+            params![tag.category, name],
+            |row| row.get(0),
+        ).optional()?;
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+        let new_id = Uuid::new_v4().to_string();
+        self.conn.execute(
+            "INSERT INTO tags (id, name, category, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![new_id, name, tag.category, current_timestamp()],
+        )?;
+        Ok(new_id)
+    }
+}
+
+fn map_tag(row: &Row) -> rusqlite::Result<Tag> {
+    map_tag_at(row, 0)
+}
+
+// A tag from three columns (id, name, category) starting at `first`
+fn map_tag_at(row: &Row, first: usize) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(first)?,
+        name: row.get(first + 1)?,
+        category: row.get(first + 2)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::error::ErrorKind;
+    use crate::repo::LogoRepo;
+
+    fn input(name: &str, category: TagCategory) -> TagInput {
+        TagInput { name: name.to_string(), category }
+    }
+
+    #[test]
+    fn bulk_tagging_reuses_tags_by_category_and_name() {
+        let conn = open_in_memory().unwrap();
+        let logos = LogoRepo::new(&conn);
+        let repo = TagRepo::new(&conn);
+        let a = logos.add("Kroma", "/logos/kroma.png", None).unwrap();
+        let b = logos.add("Nyx", "/logos/nyx.png", None).unwrap();
+        let both = [a.clone(), b.clone()];
+
+        let ids = repo.add_to_logos(&both, &[input("Sponsor", TagCategory::Type), input("imported", TagCategory::Source)]).unwrap();
+        // Same name and category in another case is the same tag; the same
+        // name in another category is not
+        let again = repo.add_to_logos(std::slice::from_ref(&a), &[input(" sponsor ", TagCategory::Type), input("Sponsor", TagCategory::Custom)]).unwrap();
+        assert_eq!(again[0], ids[0]);
+        assert_ne!(again[1], ids[0]);
+
+        assert_eq!(repo.list(None).unwrap().len(), 3);
+        let types = repo.list(Some(TagCategory::Type)).unwrap();
+        assert_eq!(types.len(), 1);
+        assert_eq!(types[0].name, "Sponsor");
+
+        let listed = logos.list().unwrap();
+        let kroma = listed.iter().find(|l| l.id == a).unwrap();
+        let categories: Vec<_> = kroma.tags.iter().map(|t| t.category).collect();
+        assert_eq!(categories, [TagCategory::Custom, TagCategory::Source, TagCategory::Type]);
+        assert_eq!(listed.iter().find(|l| l.id == b).unwrap().tags.len(), 2);
+
+        assert_eq!(repo.remove_from_logos(&both, std::slice::from_ref(&ids[0])).unwrap(), 2);
+        assert_eq!(repo.remove_from_logos(&both, std::slice::from_ref(&ids[0])).unwrap(), 0);
+        repo.delete(&ids[1]).unwrap();
+        assert!(logos.list().unwrap().iter().find(|l| l.id == b).unwrap().tags.is_empty());
+    }
+
+    #[test]
+    fn tagging_is_all_or_nothing() {
+        let conn = open_in_memory().unwrap();
+        let repo = TagRepo::new(&conn);
+        let a = LogoRepo::new(&conn).add("Kroma", "/logos/kroma.png", None).unwrap();
+
+        let err = repo.add_to_logos(&[a.clone(), "missing".to_string()], &[input("intro", TagCategory::Type)]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+        let err = repo.add_to_logos(&[a], &[input("intro", TagCategory::Type), input("  ", TagCategory::Type)]).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("name"));
+        assert!(repo.list(None).unwrap().is_empty());
+        assert_eq!(repo.delete("missing").unwrap_err().kind, ErrorKind::NotFound);
+    }
+}
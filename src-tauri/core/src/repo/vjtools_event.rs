@@ -0,0 +1,148 @@
+use super::{first_local_timestamp, first_text, text};
+use crate::current_timestamp;
+use crate::error::{AppError, AppResult};
+use crate::models::StoredEvent;
+use rusqlite::{params, Connection, Row};
+use serde_json::Value;
+
+// Offline copy of the events vj.tools serves. These rows aren't ours to
+// sync back, so there is no sync status: a fetch simply overwrites them.
+pub struct VjToolsEventRepo<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> VjToolsEventRepo<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    // Saves events as fetched from vj.tools, replacing the stored copy of
+    // any event with the same id. Events already stored but missing from
+    // `events` are kept. Start and end times are stored as UTC whatever
+    // format they came in, so they sort; times without an offset are local,
+    // like the schedule's. Every event needs an id; if one
+    // doesn't, nothing is stored. Returns how many events were stored.
+    pub fn store(&self, events: &[Value]) -> AppResult<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let now = current_timestamp();
+        for (index, event) in events.iter().enumerate() {
+            let id = text(event.get("id")).ok_or_else(|| {
+                AppError::invalid("id", format!("Event {} has no id", index + 1))
+            })?;
+            self.conn.execute(
+                "INSERT INTO vjtools_events (id, title, starts_at, ends_at, artist_name, logo, payload, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (id) DO UPDATE SET
+                    title = excluded.title, starts_at = excluded.starts_at, ends_at = excluded.ends_at,
+                    artist_name = excluded.artist_name, logo = excluded.logo,
+                    payload = excluded.payload, fetched_at = excluded.fetched_at",
+                params![
+                    id,
+                    first_text(event, &["title", "name"]),
+                    first_local_timestamp(event, &["start_time", "starts_at", "start"]),
+                    first_local_timestamp(event, &["end_time", "ends_at", "end"]),
+                    artist_name(event),
+                    logo(event),
+                    event.to_string(),
+                    now
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(events.len())
+    }
+
+    // Stored events by start time; events without one come last
+    pub fn list(&self) -> AppResult<Vec<StoredEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, title, starts_at, ends_at, artist_name, logo, payload, fetched_at FROM vjtools_events
+             ORDER BY starts_at IS NULL, starts_at ASC, title ASC, id ASC",
+        )?;
+        let events = stmt.query_map([], map_stored_event)?.collect::<rusqlite::Result<_>>()?;
+        Ok(events)
+    }
+}
+
+fn map_stored_event(row: &Row) -> rusqlite::Result<StoredEvent> {
+    let payload: String = row.get(6)?;
+    Ok(StoredEvent {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        starts_at: row.get(2)?,
+        ends_at: row.get(3)?,
+        artist_name: row.get(4)?,
+        logo: row.get(5)?,
+        // Written by `store` from a parsed value, so it parses back
+        payload: serde_json::from_str(&payload).unwrap_or(Value::Null),
+        fetched_at: row.get(7)?,
+    })
+}
+
+// "artist_name", or "artist" as either a name or an object with one
+fn artist_name(event: &Value) -> Option<String> {
+    first_text(event, &["artist_name", "artist"]).or_else(|| text(event.get("artist")?.get("name")))
+}
+
+// "logo" / "logo_url", or the path or URL of the first entry of "logos"
+fn logo(event: &Value) -> Option<String> {
+    first_text(event, &["logo", "logo_url"]).or_else(|| {
+        let first = event.get("logos")?.as_array()?.first()?;
+        first_text(first, &["path", "url"])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use chrono::{Local, TimeZone, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn events_are_upserted_by_id_and_listed_by_start() {
+        let conn = open_in_memory().unwrap();
+        let repo = VjToolsEventRepo::new(&conn);
+
+        let first = [
+            json!({"id": 7, "title": "KROMA", "start_time": "2026-10-17 23:00:00", "end_time": "2026-10-18 00:00:00",
+                   "artist_name": "KROMA", "logos": [{"id": 1, "path": "/logos/kroma.png", "type": "main"}]}),
+            // Both start at 21:00 UTC
+            json!({"id": "b2", "name": "Doors", "starts_at": "2026-10-17T23:00:00+02:00", "artist": {"name": "Nyx"}}),
+            json!({"id": "w1", "title": "Warm-up", "start": 1_792_270_800, "end": 1_792_274_400_000i64}),
+            json!({"id": "tba", "title": "Surprise guest", "start_time": "later"}),
+        ];
+        assert_eq!(repo.store(&first).unwrap(), 4);
+        // Times without an offset are local, like the schedule's
+        let kroma = repo.list().unwrap().into_iter().find(|e| e.id == "7").unwrap();
+        assert_eq!(kroma.starts_at, Some(Local.with_ymd_and_hms(2026, 10, 17, 23, 0, 0).unwrap().with_timezone(&Utc)));
+        // A later fetch changes one event and leaves the others alone
+        repo.store(&[json!({"id": 7, "title": "KROMA (live)", "start_time": "2026-10-17T23:30:00Z", "set_duration": 60})])
+            .unwrap();
+
+        let events = repo.list().unwrap();
+        let titles: Vec<_> = events.iter().map(|e| e.title.as_deref().unwrap()).collect();
+        assert_eq!(titles, ["Doors", "Warm-up", "KROMA (live)", "Surprise guest"]);
+        assert_eq!(events[0].artist_name.as_deref(), Some("Nyx"));
+        assert_eq!(events[0].starts_at, Some(Utc.with_ymd_and_hms(2026, 10, 17, 21, 0, 0).unwrap()));
+        assert_eq!(events[1].ends_at, Some(Utc.with_ymd_and_hms(2026, 10, 17, 22, 0, 0).unwrap()));
+        // An unreadable time is only kept in the payload
+        assert_eq!(events[3].starts_at, None);
+        assert_eq!(events[3].payload["start_time"], "later");
+        let kroma = &events[2];
+        assert_eq!(kroma.id, "7");
+        assert_eq!(kroma.starts_at, Some(Utc.with_ymd_and_hms(2026, 10, 17, 23, 30, 0).unwrap()));
+        // Normalised fields follow the latest payload, which is kept whole
+        assert_eq!(kroma.logo, None);
+        assert_eq!(kroma.payload["set_duration"], 60);
+    }
+
+    #[test]
+    fn events_without_an_id_are_rejected() {
+        let conn = open_in_memory().unwrap();
+        let repo = VjToolsEventRepo::new(&conn);
+
+        let err = repo.store(&[json!({"id": 1, "title": "KROMA"}), json!({"title": "No id"})]).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("id"));
+        assert!(repo.list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+//! Schedule rules that don't belong to a single table: parsing the times the
+//! frontend sends, checking that an event's start and end make sense, and
+//! working out when events overlap and which one wins.
+
+use crate::error::{AppError, AppResult};
+use crate::models::{ScheduleConflict, ScheduleItem};
+use chrono::{DateTime, Local, NaiveDateTime};
+
+// Local date-time layouts accepted from the frontend, e.g. the value of an
+// `<input type="datetime-local">`
+const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+// Parses a local date-time such as "2026-10-17T23:30". Strings with a UTC
+// offset ("...Z", "+02:00") are converted to local time. `field` names the
+// argument in the error.
+pub fn parse_date_time(field: &str, value: &str) -> AppResult<NaiveDateTime> {
+    let value = value.trim();
+    for format in DATE_TIME_FORMATS {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(parsed);
+        }
+    }
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Ok(parsed.with_timezone(&Local).naive_local());
+    }
+    Err(AppError::invalid(
+        field,
+        format!("'{}' is not a valid date and time (expected YYYY-MM-DDTHH:MM)", value),
+    ))
+}
+
+// Parses a `starts_at` / optional `ends_at` pair as sent by the frontend and
+// checks that they are in order
+pub fn parse_event_times(starts_at: &str, ends_at: Option<&str>) -> AppResult<(NaiveDateTime, Option<NaiveDateTime>)> {
+    let starts_at = parse_date_time("starts_at", starts_at)?;
+    let ends_at = ends_at.map(|end| parse_date_time("ends_at", end)).transpose()?;
+    validate_event_times(starts_at, ends_at)?;
+    Ok((starts_at, ends_at))
+}
+
+// An explicit end has to come after the start
+pub fn validate_event_times(starts_at: NaiveDateTime, ends_at: Option<NaiveDateTime>) -> AppResult<()> {
+    match ends_at {
+        Some(end) if end <= starts_at => Err(AppError::invalid("ends_at", "Event must end after it starts")),
+        _ => Ok(()),
+    }
+}
+
+// A schedule event with its end resolved
+pub struct Occurrence<'a> {
+    pub item: &'a ScheduleItem,
+    pub start: NaiveDateTime,
+    pub end: Option<NaiveDateTime>,
+}
+
+impl Occurrence<'_> {
+    fn overlaps(&self, later: &Occurrence) -> bool {
+        self.end.is_none_or(|end| later.start < end)
+    }
+}
+
+// Every event sorted by start. Events without an end last until the next
+// event starts.
+pub fn occurrences(schedule: &[ScheduleItem]) -> Vec<Occurrence<'_>> {
+    let mut occurrences: Vec<Occurrence> = schedule
+        .iter()
+        .map(|item| Occurrence {
+            item,
+            start: item.starts_at,
+            end: item.ends_at,
+        })
+        .collect();
+    occurrences.sort_by_key(|o| o.start);
+
+    for i in 0..occurrences.len() {
+        if occurrences[i].end.is_none() {
+            let start = occurrences[i].start;
+            occurrences[i].end = occurrences[i + 1..].iter().map(|o| o.start).find(|s| *s > start);
+        }
+    }
+    occurrences
+}
+
+// The occurrence that has the screen when several overlap: highest
+// priority, then the one that started last
+pub fn winner<'a, 'b>(candidates: impl Iterator<Item = &'b Occurrence<'a>>) -> Option<&'b Occurrence<'a>> {
+    candidates.max_by_key(|o| (o.item.priority, o.start))
+}
+
+// Every pair of overlapping events, ordered by where the overlap starts
+pub fn find_conflicts(schedule: &[ScheduleItem]) -> Vec<ScheduleConflict> {
+    let occurrences = occurrences(schedule);
+    let mut conflicts = Vec::new();
+
+    for (i, first) in occurrences.iter().enumerate() {
+        for second in &occurrences[i + 1..] {
+            if !first.overlaps(second) {
+                continue;
+            }
+            let overlap_end = match (first.end, second.end) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let tied = first.item.priority == second.item.priority && first.start == second.start;
+            let winner_id = if tied {
+                None
+            } else {
+                winner([first, second].into_iter()).map(|o| o.item.id.clone())
+            };
+            conflicts.push(ScheduleConflict {
+                first_id: first.item.id.clone(),
+                first_name: first.item.name.clone(),
+                second_id: second.item.id.clone(),
+                second_name: second.item.name.clone(),
+                overlap_start: second.start,
+                overlap_end,
+                winner_id,
+            });
+        }
+    }
+    conflicts.sort_by_key(|c| c.overlap_start);
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use chrono::{Duration, NaiveDate};
+
+    fn at(h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn event(id: &str, start: NaiveDateTime, minutes: Option<i64>, priority: i32) -> ScheduleItem {
+        ScheduleItem {
+            id: id.to_string(),
+            starts_at: start,
+            ends_at: minutes.map(|m| start + Duration::minutes(m)),
+            name: id.to_string(),
+            event_type: "dj_set".to_string(),
+            linked_logo_id: None,
+            priority,
+        }
+    }
+
+    #[test]
+    fn parses_local_date_times() {
+        let expected = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(23, 30, 0).unwrap();
+        for value in ["2026-10-17T23:30", "2026-10-17T23:30:00", "2026-10-17 23:30", " 2026-10-17 23:30:00.000 "] {
+            assert_eq!(parse_date_time("starts_at", value).unwrap(), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_times() {
+        for value in ["23:30", "2026-10-17", "2026-13-01T10:00", "25:00", ""] {
+            let err = parse_date_time("starts_at", value).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::InvalidInput);
+            assert_eq!(err.field.as_deref(), Some("starts_at"));
+        }
+    }
+
+    #[test]
+    fn end_must_follow_start() {
+        let start = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(23, 0, 0).unwrap();
+        assert!(validate_event_times(start, None).is_ok());
+        assert!(validate_event_times(start, Some(start + chrono::Duration::hours(2))).is_ok());
+        assert_eq!(validate_event_times(start, Some(start)).unwrap_err().field.as_deref(), Some("ends_at"));
+    }
+
+    #[test]
+    fn back_to_back_events_do_not_conflict() {
+        let schedule = vec![
+            event("warmup", at(22, 0), Some(60), 0),
+            event("headliner", at(23, 0), Some(60), 0),
+            event("doors", at(21, 0), None, 0),
+        ];
+        assert!(find_conflicts(&schedule).is_empty());
+    }
+
+    #[test]
+    fn overlap_window_and_winner_are_reported() {
+        let schedule = vec![
+            event("headliner", at(23, 0), Some(90), 0),
+            event("sponsor", at(23, 30), Some(15), 5),
+            event("closing", at(0, 0), None, 0),
+        ];
+        let conflicts = find_conflicts(&schedule);
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!(conflict.first_id, "headliner");
+        assert_eq!(conflict.second_id, "sponsor");
+        assert_eq!(conflict.overlap_start, at(23, 30));
+        assert_eq!(conflict.overlap_end, Some(at(23, 45)));
+        assert_eq!(conflict.winner_id.as_deref(), Some("sponsor"));
+
+        // Lower priority: the headliner keeps the screen
+        let schedule = vec![event("headliner", at(23, 0), Some(90), 10), event("sponsor", at(23, 30), Some(15), 5)];
+        assert_eq!(find_conflicts(&schedule)[0].winner_id.as_deref(), Some("headliner"));
+    }
+
+    #[test]
+    fn same_start_and_priority_has_no_winner() {
+        let schedule = vec![event("a", at(23, 0), Some(30), 0), event("b", at(23, 0), None, 0)];
+        let conflicts = find_conflicts(&schedule);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].overlap_end, Some(at(23, 30)));
+        assert_eq!(conflicts[0].winner_id, None);
+    }
+}
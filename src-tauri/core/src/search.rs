@@ -0,0 +1,212 @@
+//! Logo library search: full-text matching through the `logo_search` FTS5
+//! table (names, file names, artist names, tags) combined with filters on
+//! how a logo is used, sorted and paged in SQL so large libraries stay fast.
+
+use crate::error::{AppError, AppResult};
+use crate::models::{LogoSearch, LogoSearchPage, LogoSort, TagCategory};
+use crate::repo::{map_logo, LogoRepo, TagRepo, LOGO_COLUMNS};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+use std::collections::HashMap;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+// Uses of a logo that make it "used"
+const IN_CYCLE: &str = "EXISTS (SELECT 1 FROM cycle_config cc WHERE cc.logo_id = l.id)";
+const SCHEDULED: &str =
+    "EXISTS (SELECT 1 FROM schedule_events se WHERE se.linked_logo_id = l.id AND se.sync_status != 'deleted')";
+const LINKED: &str = "EXISTS (SELECT 1 FROM artist_logos al WHERE al.logo_id = l.id)";
+
+pub fn search_logos(conn: &Connection, search: &LogoSearch) -> AppResult<LogoSearchPage> {
+    let limit = search.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(AppError::invalid("limit", format!("Page size must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    let mut from = String::from("logos l");
+    let mut conditions = vec!["l.sync_status != 'deleted'".to_string()];
+    let mut params: Vec<Value> = Vec::new();
+
+    let fts_query = fts_query(&search.query);
+    if let Some(query) = &fts_query {
+        // bm25 weights per column: a hit in the logo's own name counts most
+        from.push_str(
+            " JOIN (SELECT logo_id, bm25(logo_search, 0.0, 10.0, 5.0, 3.0, 3.0) AS rank
+                    FROM logo_search WHERE logo_search MATCH ?) s ON s.logo_id = l.id",
+        );
+        params.push(Value::Text(query.clone()));
+    }
+
+    if let Some(artist_id) = &search.artist_id {
+        conditions.push("EXISTS (SELECT 1 FROM artist_logos al WHERE al.logo_id = l.id AND al.artist_id = ?)".to_string());
+        params.push(Value::Text(artist_id.clone()));
+    }
+    for tag_ids in tags_by_category(conn, &search.tag_ids)? {
+        conditions.push(format!(
+            "EXISTS (SELECT 1 FROM logo_tags lt WHERE lt.logo_id = l.id AND lt.tag_id IN ({}))",
+            vec!["?"; tag_ids.len()].join(", ")
+        ));
+        params.extend(tag_ids.into_iter().map(Value::Text));
+    }
+    if let Some(in_cycle) = search.in_cycle {
+        conditions.push(negate_unless(in_cycle, IN_CYCLE));
+    }
+    if let Some(scheduled) = search.scheduled {
+        conditions.push(negate_unless(scheduled, SCHEDULED));
+    }
+    if search.unused {
+        conditions.push(format!("NOT {} AND NOT {} AND NOT {}", LINKED, IN_CYCLE, SCHEDULED));
+    }
+    if let Some(missing) = search.missing {
+        conditions.push(format!("l.missing = {}", missing as i32));
+    }
+
+    let filter = format!("FROM {} WHERE {}", from, conditions.join(" AND "));
+    let total: u32 = conn.query_row(&format!("SELECT COUNT(*) {}", filter), params_from_iter(&params), |row| row.get(0))?;
+
+    let direction = if search.descending { "DESC" } else { "ASC" };
+    let order = match search.sort {
+        LogoSort::Relevance if fts_query.is_some() => format!("s.rank {}", direction),
+        LogoSort::Relevance | LogoSort::Name => format!("l.name COLLATE NOCASE {}", direction),
+        LogoSort::Created => format!("l.created_at {}", direction),
+        LogoSort::Updated => format!("l.updated_at {}", direction),
+    };
+    params.push(Value::Integer(limit as i64));
+    params.push(Value::Integer(search.offset as i64));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} {} ORDER BY {}, l.name COLLATE NOCASE ASC, l.id ASC LIMIT ? OFFSET ?",
+        LOGO_COLUMNS, filter, order
+    ))?;
+    let mut logos = stmt.query_map(params_from_iter(&params), map_logo)?.collect::<rusqlite::Result<Vec<_>>>()?;
+    LogoRepo::new(conn).hydrate(&mut logos)?;
+
+    Ok(LogoSearchPage {
+        logos,
+        total,
+        offset: search.offset,
+    })
+}
+
+// Turns what was typed into an FTS5 query: every word quoted (so nothing
+// typed is read as FTS syntax) and matched as a prefix. None if there are
+// no words.
+fn fts_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+// The selected tag ids grouped by category; unknown ids are NotFound
+fn tags_by_category(conn: &Connection, tag_ids: &[String]) -> AppResult<Vec<Vec<String>>> {
+    if tag_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let categories: HashMap<String, TagCategory> =
+        TagRepo::new(conn).list(None)?.into_iter().map(|t| (t.id, t.category)).collect();
+    let mut groups: HashMap<TagCategory, Vec<String>> = HashMap::new();
+    for id in tag_ids {
+        let category = categories.get(id).ok_or_else(|| AppError::not_found("Tag", id))?;
+        groups.entry(*category).or_default().push(id.clone());
+    }
+    Ok(groups.into_values().collect())
+}
+
+fn negate_unless(wanted: bool, condition: &str) -> String {
+    if wanted {
+        condition.to_string()
+    } else {
+        format!("NOT {}", condition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use crate::error::ErrorKind;
+    use crate::models::{LogoSearch, ScheduleEventInput, TagInput};
+    use crate::repo::{ArtistRepo, CycleRepo, ScheduleRepo};
+    use chrono::NaiveDate;
+
+    fn names(page: &LogoSearchPage) -> Vec<&str> {
+        page.logos.iter().map(|l| l.name.as_str()).collect()
+    }
+
+    fn query(text: &str) -> LogoSearch {
+        LogoSearch {
+            query: text.to_string(),
+            ..LogoSearch::default()
+        }
+    }
+
+    #[test]
+    fn matches_names_files_artists_and_tags() {
+        let conn = open_in_memory().unwrap();
+        let logos = LogoRepo::new(&conn);
+        let kroma = logos.add("Kroma main", "/logos/kroma/main.png", None).unwrap();
+        let sponsor = logos.add("Red Bull", "C:\\Logos\\sponsors\\redbull_fireburst.png", None).unwrap();
+        logos.add("Intro", "/logos/intro.mov", None).unwrap();
+        let artist = ArtistRepo::new(&conn).add("Nyxéa").unwrap();
+        logos.link_artist(&artist, &kroma, false).unwrap();
+        TagRepo::new(&conn)
+            .add_to_logos(std::slice::from_ref(&sponsor), &[TagInput { name: "sponsor".to_string(), category: TagCategory::Type }])
+            .unwrap();
+
+        assert_eq!(names(&search_logos(&conn, &query("kro")).unwrap()), ["Kroma main"]);
+        // File name, not the folders above it
+        assert_eq!(names(&search_logos(&conn, &query("fireb")).unwrap()), ["Red Bull"]);
+        assert!(search_logos(&conn, &query("sponsors")).unwrap().logos.is_empty());
+        // Artist names, ignoring accents
+        assert_eq!(names(&search_logos(&conn, &query("nyxea")).unwrap()), ["Kroma main"]);
+        ArtistRepo::new(&conn).add_alias(&artist, "Nightshade").unwrap();
+        assert_eq!(names(&search_logos(&conn, &query("nightsh")).unwrap()), ["Kroma main"]);
+        assert_eq!(names(&search_logos(&conn, &query("SPONSOR red")).unwrap()), ["Red Bull"]);
+        // Quotes and FTS operators are just text
+        assert!(search_logos(&conn, &query("\"intro OR kroma")).unwrap().logos.is_empty());
+
+        // The index follows renames, unlinks and deletes
+        logos.update(&kroma, "Aurora", "/logos/kroma/main.png", None).unwrap();
+        assert!(search_logos(&conn, &query("kroma")).unwrap().logos.is_empty());
+        assert_eq!(names(&search_logos(&conn, &query("auro")).unwrap()), ["Aurora"]);
+        logos.unlink_artist(&artist, &kroma).unwrap();
+        assert!(search_logos(&conn, &query("nyxea")).unwrap().logos.is_empty());
+        logos.delete(&sponsor).unwrap();
+        assert!(search_logos(&conn, &query("red")).unwrap().logos.is_empty());
+    }
+
+    #[test]
+    fn filters_by_use_and_pages_results() {
+        let conn = open_in_memory().unwrap();
+        let logos = LogoRepo::new(&conn);
+        let ids: Vec<String> = ["A", "B", "C", "D", "E"]
+            .iter()
+            .map(|name| logos.add(name, &format!("/logos/{}.png", name), None).unwrap())
+            .collect();
+        let artist = ArtistRepo::new(&conn).add("KROMA").unwrap();
+        logos.link_artist(&artist, &ids[0], false).unwrap();
+        CycleRepo::new(&conn).set(std::slice::from_ref(&ids[1])).unwrap();
+        let starts_at = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap().and_hms_opt(23, 0, 0).unwrap();
+        let mut event = ScheduleEventInput::new(starts_at, "KROMA", "dj_set");
+        event.linked_logo_id = Some(ids[2].clone());
+        ScheduleRepo::new(&conn).add(&event).unwrap();
+        logos.set_missing(&ids[3], true).unwrap();
+
+        let run = |search: LogoSearch| names(&search_logos(&conn, &search).unwrap()).into_iter().map(String::from).collect::<Vec<_>>();
+        assert_eq!(run(LogoSearch { artist_id: Some(artist.clone()), ..LogoSearch::default() }), ["A"]);
+        assert_eq!(run(LogoSearch { in_cycle: Some(true), ..LogoSearch::default() }), ["B"]);
+        assert_eq!(run(LogoSearch { scheduled: Some(true), ..LogoSearch::default() }), ["C"]);
+        assert_eq!(run(LogoSearch { unused: true, ..LogoSearch::default() }), ["D", "E"]);
+        assert_eq!(run(LogoSearch { missing: Some(true), ..LogoSearch::default() }), ["D"]);
+        assert_eq!(run(LogoSearch { in_cycle: Some(false), scheduled: Some(false), ..LogoSearch::default() }), ["A", "D", "E"]);
+
+        let page = search_logos(&conn, &LogoSearch { sort: LogoSort::Name, descending: true, offset: 1, limit: Some(2), ..LogoSearch::default() }).unwrap();
+        assert_eq!(names(&page), ["D", "C"]);
+        assert_eq!(page.total, 5);
+        assert_eq!(page.logos[0].linked_djs.len(), 0);
+        assert_eq!(search_logos(&conn, &LogoSearch { limit: Some(0), ..LogoSearch::default() }).unwrap_err().field.as_deref(), Some("limit"));
+        assert_eq!(search_logos(&conn, &LogoSearch { tag_ids: vec!["missing".to_string()], ..LogoSearch::default() }).unwrap_err().kind, ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,239 @@
+//! Thumbnails for image logos (PNG, JPEG, GIF, WebP, SVG), rendered into a
+//! cache folder as fixed-size PNGs.
+//!
+//! Each generated thumbnail records a key for the version of the file it was
+//! made from, so a thumbnail is only rebuilt when its file changes. The
+//! key is part of the file name, which keeps the webview from showing a
+//! cached copy of the old thumbnail.
+
+use crate::error::{AppError, AppResult};
+use crate::library::{media_kind, LibraryFile};
+use crate::models::{Logo, MediaKind};
+use crate::repo::LogoRepo;
+use image::imageops::FilterType;
+use image::{ImageFormat, RgbaImage};
+use resvg::{tiny_skia, usvg};
+use rusqlite::Connection;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+// Longest side of a thumbnail in pixels; the aspect ratio is kept
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+// A thumbnail to (re)build
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThumbnailJob {
+    pub logo_id: String,
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub key: String,
+}
+
+// Identifies a version of a file
+pub fn source_key(file: &LibraryFile) -> String {
+    format!("{}-{}", file.size, file.modified.unwrap_or_default())
+}
+
+// Image logos whose thumbnail is missing or was made from an older version
+// of their file. Thumbnails set by hand and logos whose file is missing are
+// left alone.
+pub fn pending(conn: &Connection, cache_dir: &Path) -> AppResult<Vec<ThumbnailJob>> {
+    let logos = LogoRepo::new(conn).list()?;
+    Ok(logos.iter().filter_map(|logo| job_for(logo, cache_dir)).collect())
+}
+
+fn job_for(logo: &Logo, cache_dir: &Path) -> Option<ThumbnailJob> {
+    let source = Path::new(&logo.file_path);
+    if logo.missing || media_kind(source) != Some(MediaKind::Image) {
+        return None;
+    }
+    if logo.thumbnail_path.is_some() && logo.thumbnail_key.is_none() {
+        return None;
+    }
+    let key = source_key(&LibraryFile::read(source).ok()?);
+    // A None path with a matching key means the file couldn't be rendered
+    let up_to_date = logo.thumbnail_key.as_deref() == Some(key.as_str())
+        && logo.thumbnail_path.as_deref().is_none_or(|path| Path::new(path).exists());
+    if up_to_date {
+        return None;
+    }
+    Some(ThumbnailJob {
+        logo_id: logo.id.clone(),
+        source: source.to_path_buf(),
+        target: cache_dir.join(format!("{}-{}.png", logo.id, key)),
+        key,
+    })
+}
+
+// Renders the job's thumbnail to its target file
+pub fn generate(job: &ThumbnailJob) -> AppResult<()> {
+    let thumbnail = render(&job.source, THUMBNAIL_SIZE)?;
+    if let Some(dir) = job.target.parent() {
+        fs::create_dir_all(dir).map_err(|e| AppError::io("Failed to create thumbnail folder", e))?;
+    }
+    thumbnail
+        .save_with_format(&job.target, ImageFormat::Png)
+        .map_err(|e| AppError::internal(format!("Failed to write thumbnail '{}': {}", job.target.display(), e)))
+}
+
+// Records the outcome of a job (`generated` is false if it failed) and
+// removes the thumbnail it replaces. Returns the new thumbnail path.
+pub fn store(conn: &Connection, job: &ThumbnailJob, generated: bool) -> AppResult<Option<String>> {
+    let repo = LogoRepo::new(conn);
+    let previous = repo
+        .list()?
+        .into_iter()
+        .find(|logo| logo.id == job.logo_id)
+        .filter(|logo| logo.thumbnail_key.is_some())
+        .and_then(|logo| logo.thumbnail_path);
+
+    let path = generated.then(|| job.target.to_string_lossy().into_owned());
+    repo.set_thumbnail(&job.logo_id, path.as_deref(), &job.key)?;
+    if let Some(previous) = previous.filter(|p| Some(p) != path.as_ref()) {
+        let _ = fs::remove_file(previous);
+    }
+    Ok(path)
+}
+
+// The image scaled to fit a `size` x `size` square. Animated GIFs and WebPs
+// give their first frame.
+pub fn render(source: &Path, size: u32) -> AppResult<RgbaImage> {
+    let is_svg = source
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("svg"));
+    if is_svg {
+        return render_svg(source, size);
+    }
+    let image = image::open(source).map_err(|e| unreadable(source, e))?;
+    Ok(image.resize(size, size, FilterType::Lanczos3).to_rgba8())
+}
+
+fn render_svg(source: &Path, size: u32) -> AppResult<RgbaImage> {
+    let tree = parse_svg(source)?;
+
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let scale = size as f32 / width.max(height);
+    let (out_width, out_height) = (
+        ((width * scale).round() as u32).max(1),
+        ((height * scale).round() as u32).max(1),
+    );
+    let mut pixmap = tiny_skia::Pixmap::new(out_width, out_height)
+        .ok_or_else(|| AppError::internal("Failed to allocate thumbnail"))?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // tiny-skia works with premultiplied alpha
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(out_width, out_height, pixels).ok_or_else(|| AppError::internal("Failed to convert thumbnail"))
+}
+
+pub(crate) fn parse_svg(source: &Path) -> AppResult<usvg::Tree> {
+    let data = fs::read(source).map_err(|e| AppError::io(&format!("Failed to read {}", source.display()), e))?;
+    let options = usvg::Options {
+        fontdb: system_fonts(),
+        ..usvg::Options::default()
+    };
+    usvg::Tree::from_data(&data, &options).map_err(|e| unreadable(source, e))
+}
+
+// Loaded once; SVG text needs fonts to render
+fn system_fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = usvg::fontdb::Database::new();
+            fonts.load_system_fonts();
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
+pub(crate) fn unreadable(source: &Path, e: impl std::fmt::Display) -> AppError {
+    AppError::invalid("file_path", format!("Can't read image '{}': {}", source.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+    use image::Rgba;
+
+    fn write_png(path: &Path, width: u32, height: u32) {
+        RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255])).save(path).unwrap();
+    }
+
+    #[test]
+    fn renders_raster_and_svg_to_fit() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("wide.png");
+        write_png(&png, 1024, 512);
+        assert_eq!(render(&png, 256).unwrap().dimensions(), (256, 128));
+
+        let svg = dir.path().join("tall.svg");
+        fs::write(
+            &svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="50" height="100"><rect width="50" height="100" fill="red"/></svg>"#,
+        )
+        .unwrap();
+        let thumbnail = render(&svg, 256).unwrap();
+        assert_eq!(thumbnail.dimensions(), (128, 256));
+        assert_eq!(thumbnail.get_pixel(64, 128), &Rgba([255, 0, 0, 255]));
+
+        let broken = dir.path().join("broken.png");
+        fs::write(&broken, b"not a png").unwrap();
+        assert!(render(&broken, 256).is_err());
+    }
+
+    #[test]
+    fn regenerates_only_when_the_file_changes() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        let source = dir.path().join("kroma.png");
+        write_png(&source, 64, 64);
+        let repo = LogoRepo::new(&conn);
+        let id = repo.add("kroma", &source.to_string_lossy(), None).unwrap();
+        repo.add("clip", "/logos/clip.mp4", None).unwrap();
+
+        let jobs = pending(&conn, &cache).unwrap();
+        assert_eq!(jobs.len(), 1);
+        generate(&jobs[0]).unwrap();
+        let first = store(&conn, &jobs[0], true).unwrap().unwrap();
+        assert!(Path::new(&first).exists());
+        assert!(pending(&conn, &cache).unwrap().is_empty());
+
+        write_png(&source, 128, 64);
+        let jobs = pending(&conn, &cache).unwrap();
+        assert_eq!(jobs[0].logo_id, id);
+        generate(&jobs[0]).unwrap();
+        let second = store(&conn, &jobs[0], true).unwrap().unwrap();
+        assert_ne!(first, second);
+        assert!(!Path::new(&first).exists());
+    }
+
+    #[test]
+    fn failed_and_hand_set_thumbnails_are_not_retried() {
+        let conn = open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let broken = dir.path().join("broken.png");
+        fs::write(&broken, b"not a png").unwrap();
+        let custom = dir.path().join("custom.png");
+        write_png(&custom, 8, 8);
+        let repo = LogoRepo::new(&conn);
+        repo.add("broken", &broken.to_string_lossy(), None).unwrap();
+        repo.add("custom", &custom.to_string_lossy(), Some("/thumbs/custom.png")).unwrap();
+
+        let jobs = pending(&conn, dir.path()).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert!(generate(&jobs[0]).is_err());
+        assert_eq!(store(&conn, &jobs[0], false).unwrap(), None);
+        assert!(pending(&conn, dir.path()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,484 @@
+//! Festival running orders from CSV or spreadsheet files (XLSX, XLS, ODS).
+//!
+//! The first non-empty row holds the column names; which column is which is
+//! set by a [`ColumnMapping`]. Every other row becomes a DJ set for the
+//! import preview (see [`crate::import`]). Rows that can't be read end up in
+//! the preview's skipped list with their row number instead of failing the
+//! whole file.
+
+use crate::error::{AppError, AppResult};
+use crate::import::{self, SourceEvent};
+use crate::models::{ImportPreview, SkippedEntry};
+use calamine::{open_workbook_auto, Data, Reader};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::path::Path;
+
+// Event type of every imported set
+pub const TIMETABLE_EVENT_TYPE: &str = "dj_set";
+
+const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+];
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d.%m.%Y", "%d/%m/%Y"];
+const TIME_FORMATS: &[&str] = &["%H:%M:%S", "%H:%M", "%H.%M"];
+
+// Column names (matched case-insensitively) for each field. `end`, `date`
+// and `stage` may be missing from the file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ColumnMapping {
+    pub artist: String,
+    pub start: String,
+    pub end: Option<String>,
+    // Only needed when `start` / `end` hold times without a date
+    pub date: Option<String>,
+    pub stage: Option<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            artist: "artist".to_string(),
+            start: "start".to_string(),
+            end: Some("end".to_string()),
+            date: Some("date".to_string()),
+            stage: Some("stage".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TimetableOptions {
+    pub columns: ColumnMapping,
+    // Day for rows that give only a time and have no date column
+    pub date: Option<NaiveDate>,
+    // Only import rows of this stage. Needed when the file has more than
+    // one, as the schedule has no stages.
+    pub stage: Option<String>,
+    // Worksheet to read from spreadsheets; the first one when unset
+    pub sheet: Option<String>,
+    // Times before this hour (0-23) belong to the next day, so a 02:00 set
+    // on a Friday timetable lands on Saturday morning
+    pub day_start_hour: u32,
+}
+
+impl Default for TimetableOptions {
+    fn default() -> Self {
+        Self {
+            columns: ColumnMapping::default(),
+            date: None,
+            stage: None,
+            sheet: None,
+            day_start_hour: 6,
+        }
+    }
+}
+
+// A cell as read from the file. Spreadsheets may already give typed dates
+// and times; CSV cells are always text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    Text(String),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    DateTime(NaiveDateTime),
+}
+
+impl Cell {
+    fn text(value: &str) -> Self {
+        let value = value.trim();
+        if value.is_empty() {
+            Cell::Empty
+        } else {
+            Cell::Text(value.to_string())
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Cell::Empty
+    }
+
+    fn as_text(&self) -> Option<&str> {
+        match self {
+            Cell::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+// Reads a timetable file and previews importing it
+pub fn preview_file(conn: &Connection, path: &Path, options: &TimetableOptions) -> AppResult<ImportPreview> {
+    let rows = read_rows(path, options.sheet.as_deref())?;
+    let (events, skipped) = parse_rows(&rows, options)?;
+    import::preview(conn, events, skipped)
+}
+
+// Every row of the file, picking the reader by extension
+pub fn read_rows(path: &Path, sheet: Option<&str>) -> AppResult<Vec<Vec<Cell>>> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "csv" | "tsv" | "txt" => {
+            let data = std::fs::read(path).map_err(|e| AppError::io(&format!("Failed to read {}", path.display()), e))?;
+            read_csv(&data)
+        }
+        "xlsx" | "xlsm" | "xls" | "xlsb" | "ods" => read_spreadsheet(path, sheet),
+        _ => Err(AppError::invalid(
+            "path",
+            format!("Unsupported timetable file '{}' (use CSV, XLSX, XLS or ODS)", path.display()),
+        )),
+    }
+}
+
+// CSV with ',', ';' or tab separators, whichever the header line uses most
+pub fn read_csv(data: &[u8]) -> AppResult<Vec<Vec<Cell>>> {
+    let data = data.strip_prefix("\u{feff}".as_bytes()).unwrap_or(data);
+    let first_line = data.split(|b| *b == b'\n').next().unwrap_or_default();
+    let delimiter = [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| first_line.iter().filter(|b| *b == d).count())
+        .unwrap_or(b',');
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(data);
+
+    let mut rows = Vec::new();
+    for record in reader.byte_records() {
+        let record = record.map_err(|e| AppError::invalid("path", format!("Unreadable CSV: {}", e)))?;
+        rows.push(record.iter().map(|field| Cell::text(&String::from_utf8_lossy(field))).collect());
+    }
+    Ok(rows)
+}
+
+fn read_spreadsheet(path: &Path, sheet: Option<&str>) -> AppResult<Vec<Vec<Cell>>> {
+    let unreadable = |e: calamine::Error| AppError::invalid("path", format!("Unreadable spreadsheet: {}", e));
+    let mut workbook = open_workbook_auto(path).map_err(unreadable)?;
+    let sheet = match sheet {
+        Some(sheet) => sheet.to_string(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .ok_or_else(|| AppError::invalid("path", "Spreadsheet has no worksheets"))?,
+    };
+    let range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|e| unreadable(e).with_field("sheet"))?;
+
+    Ok(range.rows().map(|row| row.iter().map(spreadsheet_cell).collect()).collect())
+}
+
+fn spreadsheet_cell(data: &Data) -> Cell {
+    match data {
+        Data::Empty | Data::Error(_) => Cell::Empty,
+        Data::String(s) | Data::DateTimeIso(s) => Cell::text(s),
+        Data::Int(i) => Cell::Text(i.to_string()),
+        Data::Float(f) => Cell::Text(f.to_string()),
+        Data::Bool(b) => Cell::Text(b.to_string()),
+        Data::DurationIso(s) => Cell::text(s),
+        Data::DateTime(value) => {
+            let Some(date_time) = value.as_datetime() else {
+                return Cell::Empty;
+            };
+            // Excel keeps times as a fraction of a day and dates as whole days
+            let serial = value.as_f64();
+            if serial < 1.0 {
+                Cell::Time(date_time.time())
+            } else if serial.fract() == 0.0 {
+                Cell::Date(date_time.date())
+            } else {
+                Cell::DateTime(date_time)
+            }
+        }
+    }
+}
+
+// Turns the rows into events. Fails if the header row lacks a required
+// column or the file has several stages and no stage is picked; bad rows are
+// reported as skipped.
+pub fn parse_rows(rows: &[Vec<Cell>], options: &TimetableOptions) -> AppResult<(Vec<SourceEvent>, Vec<SkippedEntry>)> {
+    if options.day_start_hour > 23 {
+        return Err(AppError::invalid(
+            "day_start_hour",
+            format!("Day start hour must be between 0 and 23, not {}", options.day_start_hour),
+        ));
+    }
+    let Some(header_index) = rows.iter().position(|row| row.iter().any(|c| !c.is_empty())) else {
+        return Err(AppError::invalid("path", "Timetable is empty"));
+    };
+    let header: Vec<String> = rows[header_index]
+        .iter()
+        .map(|c| c.as_text().unwrap_or_default().to_lowercase())
+        .collect();
+    let find = |name: &str| header.iter().position(|h| *h == name.trim().to_lowercase());
+    let require = |name: &str| {
+        find(name).ok_or_else(|| {
+            AppError::invalid("columns", format!("Column '{}' not found (found: {})", name, header.join(", ")))
+        })
+    };
+
+    let columns = &options.columns;
+    let artist_col = require(&columns.artist)?;
+    let start_col = require(&columns.start)?;
+    let end_col = columns.end.as_deref().and_then(find);
+    let date_col = columns.date.as_deref().and_then(find);
+    let stage_col = columns.stage.as_deref().and_then(find);
+
+    // Sets on different stages overlap, which one schedule can't show
+    if let (None, Some(col)) = (&options.stage, stage_col) {
+        let mut stages: Vec<&str> = Vec::new();
+        for row in &rows[header_index + 1..] {
+            let Some(stage) = row.get(col).and_then(Cell::as_text) else { continue };
+            if !stages.iter().any(|s| s.eq_ignore_ascii_case(stage)) {
+                stages.push(stage);
+            }
+        }
+        if stages.len() > 1 {
+            return Err(AppError::invalid(
+                "stage",
+                format!("Timetable has several stages ({}); pick the one to import", stages.join(", ")),
+            ));
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, row) in rows.iter().enumerate().skip(header_index + 1) {
+        if row.iter().all(Cell::is_empty) {
+            continue;
+        }
+        let cell = |col: Option<usize>| col.and_then(|c| row.get(c)).unwrap_or(&Cell::Empty);
+
+        if let Some(wanted) = options.stage.as_deref() {
+            let stage = cell(stage_col).as_text().unwrap_or_default();
+            if !stage.eq_ignore_ascii_case(wanted.trim()) {
+                continue;
+            }
+        }
+
+        match parse_row(cell(Some(artist_col)), cell(Some(start_col)), cell(end_col), cell(date_col), options) {
+            Ok(event) => events.push(event),
+            Err(reason) => skipped.push(SkippedEntry {
+                // 1-based, as the spreadsheet shows it
+                location: format!("row {}", index + 1),
+                reason,
+            }),
+        }
+    }
+    Ok((events, skipped))
+}
+
+fn parse_row(artist: &Cell, start: &Cell, end: &Cell, date: &Cell, options: &TimetableOptions) -> Result<SourceEvent, String> {
+    let artist = artist.as_text().ok_or("Artist is empty")?.to_string();
+    let date = match date {
+        Cell::Empty => options.date,
+        Cell::Date(d) => Some(*d),
+        Cell::DateTime(dt) => Some(dt.date()),
+        Cell::Text(text) => Some(parse_date(text).ok_or_else(|| format!("Unreadable date '{}'", text))?),
+        Cell::Time(_) => return Err("Date column holds a time".to_string()),
+    };
+
+    let (starts_at, _) = resolve_time(start, date, options.day_start_hour)
+        .map_err(|e| format!("Start: {}", e))?
+        .ok_or("Start time is empty")?;
+    let ends_at = match resolve_time(end, date, options.day_start_hour).map_err(|e| format!("End: {}", e))? {
+        // A bare end time before the start means the set runs past midnight
+        Some((end, true)) if end <= starts_at => Some(end + Duration::days(1)),
+        Some((end, _)) => Some(end),
+        None => None,
+    };
+    if ends_at.is_some_and(|end| end <= starts_at) {
+        return Err("Set ends before it starts".to_string());
+    }
+
+    Ok(SourceEvent {
+        uid: None,
+        starts_at,
+        ends_at,
+        name: artist.clone(),
+        event_type: Some(TIMETABLE_EVENT_TYPE.to_string()),
+        artist: Some(artist),
+    })
+}
+
+// The cell as a date-time, plus whether it was only a time that got
+// combined with `date`
+fn resolve_time(cell: &Cell, date: Option<NaiveDate>, day_start_hour: u32) -> Result<Option<(NaiveDateTime, bool)>, String> {
+    let time = match cell {
+        Cell::Empty => return Ok(None),
+        Cell::DateTime(dt) => return Ok(Some((*dt, false))),
+        Cell::Date(_) => return Err("no time given".to_string()),
+        Cell::Time(time) => *time,
+        Cell::Text(text) => {
+            if let Some(dt) = DATE_TIME_FORMATS
+                .iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(text, f).ok())
+            {
+                return Ok(Some((dt, false)));
+            }
+            TIME_FORMATS
+                .iter()
+                .find_map(|f| NaiveTime::parse_from_str(text, f).ok())
+                .ok_or_else(|| format!("unreadable time '{}'", text))?
+        }
+    };
+
+    let date = date.ok_or("only a time is given; set a date column or a default date")?;
+    let mut date_time = date.and_time(time);
+    if time.hour() < day_start_hour {
+        date_time += Duration::days(1);
+    }
+    Ok(Some((date_time, true)))
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    DATE_FORMATS.iter().find_map(|f| NaiveDate::parse_from_str(text, f).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{ExcelDateTime, ExcelDateTimeType};
+
+    fn at(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap().and_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn parse_csv(csv: &str, options: &TimetableOptions) -> (Vec<SourceEvent>, Vec<SkippedEntry>) {
+        parse_rows(&read_csv(csv.as_bytes()).unwrap(), options).unwrap()
+    }
+
+    #[test]
+    fn reads_full_date_times() {
+        let (events, skipped) = parse_csv(
+            "Stage,Artist,Start,End\n\
+             Main,KROMA,2026-10-17 23:00,2026-10-18 01:00\n\
+             Main,Nyx,17.10.2026 21:30,\n",
+            &TimetableOptions::default(),
+        );
+        assert!(skipped.is_empty());
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].artist.as_deref(), Some("KROMA"));
+        assert_eq!(events[0].event_type.as_deref(), Some("dj_set"));
+        assert_eq!(events[0].starts_at, at(17, 23, 0));
+        assert_eq!(events[0].ends_at, Some(at(18, 1, 0)));
+        assert_eq!(events[1].ends_at, None);
+    }
+
+    #[test]
+    fn times_only_roll_past_midnight() {
+        let options = TimetableOptions {
+            date: NaiveDate::from_ymd_opt(2026, 10, 17),
+            ..TimetableOptions::default()
+        };
+        let (events, _) = parse_csv("artist;start;end\nKROMA;23:00;01:00\nNyx;01:00;03:30\n", &options);
+        assert_eq!(events[0].starts_at, at(17, 23, 0));
+        assert_eq!(events[0].ends_at, Some(at(18, 1, 0)));
+        assert_eq!(events[1].starts_at, at(18, 1, 0));
+        assert_eq!(events[1].ends_at, Some(at(18, 3, 30)));
+    }
+
+    #[test]
+    fn custom_columns_and_stage_filter() {
+        let options = TimetableOptions {
+            columns: ColumnMapping {
+                artist: "Act".to_string(),
+                start: "From".to_string(),
+                end: Some("Until".to_string()),
+                date: Some("Day".to_string()),
+                stage: Some("Floor".to_string()),
+            },
+            stage: Some("basement".to_string()),
+            ..TimetableOptions::default()
+        };
+        let (events, _) = parse_csv(
+            "Floor\tAct\tDay\tFrom\tUntil\n\
+             Main\tKROMA\t2026-10-17\t23:00\t00:30\n\
+             Basement\tNyx\t2026-10-17\t22:00\t23:00\n",
+            &options,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "Nyx");
+        assert_eq!(events[0].starts_at, at(17, 22, 0));
+    }
+
+    #[test]
+    fn several_stages_need_a_stage_filter() {
+        let rows = read_csv(b"stage,artist,start\nMain,KROMA,2026-10-17 23:00\nBasement,Nyx,2026-10-17 23:00\n").unwrap();
+        let err = parse_rows(&rows, &TimetableOptions::default()).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("stage"));
+
+        let options = TimetableOptions {
+            stage: Some("Main".to_string()),
+            ..TimetableOptions::default()
+        };
+        assert_eq!(parse_rows(&rows, &options).unwrap().0.len(), 1);
+    }
+
+    #[test]
+    fn day_start_hour_must_be_an_hour_of_the_day() {
+        let rows = read_csv(b"artist,start\nKROMA,2026-10-17 23:00\n").unwrap();
+        let options = TimetableOptions {
+            day_start_hour: 24,
+            ..TimetableOptions::default()
+        };
+        let err = parse_rows(&rows, &options).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("day_start_hour"));
+    }
+
+    #[test]
+    fn bad_rows_are_reported_with_their_row_number() {
+        let (events, skipped) = parse_csv(
+            "artist,start,end\n\
+             KROMA,2026-10-17 23:00,2026-10-18 00:00\n\
+             ,2026-10-17 22:00,\n\
+             Nyx,late,\n\
+             Echo,23:00,\n\
+             ,,\n",
+            &TimetableOptions::default(),
+        );
+        assert_eq!(events.len(), 1);
+        let report: Vec<(&str, &str)> = skipped.iter().map(|s| (s.location.as_str(), s.reason.as_str())).collect();
+        assert_eq!(
+            report,
+            [
+                ("row 3", "Artist is empty"),
+                ("row 4", "Start: unreadable time 'late'"),
+                ("row 5", "Start: only a time is given; set a date column or a default date"),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_required_column_fails() {
+        let rows = read_csv(b"name,start\nKROMA,23:00\n").unwrap();
+        let err = parse_rows(&rows, &TimetableOptions::default()).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("columns"));
+    }
+
+    #[test]
+    fn spreadsheet_dates_and_times_become_typed_cells() {
+        let excel = |value| Data::DateTime(ExcelDateTime::new(value, ExcelDateTimeType::DateTime, false));
+        // 46312 is 2026-10-17 in Excel's 1900 date system
+        assert_eq!(spreadsheet_cell(&excel(46312.0)), Cell::Date(NaiveDate::from_ymd_opt(2026, 10, 17).unwrap()));
+        assert_eq!(spreadsheet_cell(&excel(0.9375)), Cell::Time(NaiveTime::from_hms_opt(22, 30, 0).unwrap()));
+        assert_eq!(spreadsheet_cell(&excel(46312.9375)), Cell::DateTime(at(17, 22, 30)));
+        assert_eq!(spreadsheet_cell(&Data::String(" KROMA ".to_string())), Cell::Text("KROMA".to_string()));
+    }
+}
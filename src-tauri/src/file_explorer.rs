@@ -1,6 +1,7 @@
 use std::fs;
 use std::path::Path;
 use serde::Serialize;
+use vj_event_sync_core::error::{AppError, ErrorKind};
 
 #[derive(Debug, Serialize)]
 pub struct FileItem {
@@ -11,38 +12,36 @@ pub struct FileItem {
 }
 
 #[tauri::command]
-pub async fn list_directory_contents(path: String) -> Result<Vec<FileItem>, String> {
+pub async fn list_directory_contents(path: String) -> Result<Vec<FileItem>, AppError> {
     let path = Path::new(&path);
     
     if !path.exists() {
-        return Err("Path does not exist".to_string());
+        return Err(AppError::new(ErrorKind::NotFound, "Path does not exist").with_field("path"));
     }
     
     if !path.is_dir() {
-        return Err("Path is not a directory".to_string());
+        return Err(AppError::invalid("path", "Path is not a directory"));
     }
     
     let mut items = Vec::new();
     
     match fs::read_dir(path) {
         Ok(entries) => {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let file_type = if entry.path().is_dir() {
-                        "directory"
-                    } else {
-                        "file"
-                    };
-                    
-                    items.push(FileItem {
-                        name: entry.file_name().to_string_lossy().into_owned(),
-                        path: entry.path().to_string_lossy().into_owned(),
-                        item_type: file_type.to_string(),
-                    });
-                }
+            for entry in entries.flatten() {
+                let file_type = if entry.path().is_dir() {
+                    "directory"
+                } else {
+                    "file"
+                };
+                
+                items.push(FileItem {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    path: entry.path().to_string_lossy().into_owned(),
+                    item_type: file_type.to_string(),
+                });
             }
             Ok(items)
         },
-        Err(e) => Err(format!("Failed to read directory: {}", e))
+        Err(e) => Err(AppError::io("Failed to read directory", e).with_field("path"))
     }
 }
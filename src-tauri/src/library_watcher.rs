@@ -0,0 +1,139 @@
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use vj_event_sync_core::error::{AppError, AppResult};
+use vj_event_sync_core::library;
+
+use crate::logo_library::LogoLibraryConfig;
+use crate::{thumbnails, with_db, AppState};
+
+// Quiet time after the last filesystem event before the folder is rescanned
+const DEBOUNCE: Duration = Duration::from_millis(750);
+// Gap between the two scans that tell finished files from ones still being
+// copied in; also how often a folder with unfinished files is rechecked
+const SETTLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// The running watcher. Replacing or dropping it stops the previous one.
+pub struct LibraryWatcherState(pub Mutex<Option<RecommendedWatcher>>);
+
+// Watches the configured logo library folder, if one is set
+pub fn start(app: &AppHandle) {
+    let root = match LogoLibraryConfig::load(app) {
+        Ok(config) => config.path().map(Path::to_path_buf),
+        Err(e) => {
+            eprintln!("Library watcher: failed to load config: {}", e);
+            return;
+        }
+    };
+    let result = match root {
+        Some(root) => watch(app, root),
+        None => {
+            stop(app);
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Library watcher: {}", e);
+    }
+}
+
+// Starts watching `root`, replacing any previous watcher. Changes are
+// written to the `logos` table and emitted as `library-changed` with the
+// added, modified and removed entries.
+pub fn watch(app: &AppHandle, root: PathBuf) -> AppResult<()> {
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)
+        .map_err(|e| AppError::internal(format!("Failed to create file watcher: {}", e)))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| AppError::internal(format!("Failed to watch '{}': {}", root.display(), e)).with_field("path"))?;
+
+    // The old watcher's channel closes when it is dropped here, which ends
+    // its thread
+    *app.state::<LibraryWatcherState>().0.lock().unwrap() = Some(watcher);
+
+    let app = app.clone();
+    std::thread::spawn(move || run(app, root, events));
+    Ok(())
+}
+
+pub fn stop(app: &AppHandle) {
+    app.state::<LibraryWatcherState>().0.lock().unwrap().take();
+}
+
+fn run(app: AppHandle, root: PathBuf, events: Receiver<notify::Result<Event>>) {
+    // Catch up on anything that changed while nobody was watching
+    let mut waiting_for_copies = sync(&app, &root);
+
+    loop {
+        // Wait for a change, or recheck unfinished files after a while
+        let next = if waiting_for_copies {
+            events.recv_timeout(SETTLE_INTERVAL)
+        } else {
+            events.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        };
+        match next {
+            Ok(Ok(event)) if !is_relevant(&event) => continue,
+            Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(Err(e)) => {
+                eprintln!("Library watcher: {}", e);
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        // Let a burst of events (a folder being copied in) die down
+        loop {
+            match events.recv_timeout(DEBOUNCE) {
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        waiting_for_copies = sync(&app, &root);
+    }
+}
+
+// Scans twice, SETTLE_INTERVAL apart, and syncs everything that didn't
+// change in between. Returns whether any file was still changing.
+fn sync(app: &AppHandle, root: &Path) -> bool {
+    let result = (|| {
+        let before = library::scan_dir(root)?;
+        std::thread::sleep(SETTLE_INTERVAL);
+        let after = library::scan_dir(root)?;
+        let pending = library::unsettled(&before, &after);
+
+        let state = app.state::<AppState>();
+        let update = with_db(&state, |conn| {
+            let changes = library::reconcile(conn, root, after, &pending)?;
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            library::describe(conn, &changes).map(Some)
+        })?;
+        if let Some(update) = update {
+            let _ = app.emit_all("library-changed", &update);
+            thumbnails::request(app);
+        }
+        Ok::<_, AppError>(!pending.is_empty())
+    })();
+
+    result.unwrap_or_else(|e| {
+        eprintln!("Library watcher: failed to sync logo library: {}", e);
+        false
+    })
+}
+
+// Opening and reading files, and changes to hidden files, don't touch the
+// library
+fn is_relevant(event: &Event) -> bool {
+    let is_read = matches!(event.kind, EventKind::Access(kind) if kind != AccessKind::Close(AccessMode::Write));
+    let all_hidden = !event.paths.is_empty() && event.paths.iter().all(|path| {
+        path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    });
+    !is_read && !all_hidden
+}
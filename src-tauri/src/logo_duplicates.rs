@@ -0,0 +1,47 @@
+use tauri::State;
+use vj_event_sync_core::duplicates::{self, SIMILAR_DISTANCE};
+use vj_event_sync_core::error::AppError;
+use vj_event_sync_core::models::DuplicateGroup;
+use vj_event_sync_core::repo::LogoRepo;
+
+use crate::{with_db, AppState};
+
+// Hashes every logo whose file is new or changed. Files are read without
+// holding the database; run from the background worker and before a
+// duplicates report.
+pub fn update_hashes(state: &AppState) {
+    let jobs = match with_db(state, duplicates::pending) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            eprintln!("Hashing: failed to list logos: {}", e);
+            return;
+        }
+    };
+    for job in jobs {
+        let result = duplicates::compute(&job)
+            .and_then(|hashes| with_db(state, |conn| duplicates::store(conn, &job, &hashes)));
+        if let Err(e) = result {
+            eprintln!("Hashing: {}", e);
+        }
+    }
+}
+
+// Identical and near-identical logos. `max_distance` is how many of the 64
+// perceptual hash bits may differ (default 6; 0 = visually identical only).
+#[tauri::command]
+pub async fn find_duplicate_logos(max_distance: Option<u32>, state: State<'_, AppState>) -> Result<Vec<DuplicateGroup>, AppError> {
+    update_hashes(&state);
+    with_db(&state, |conn| {
+        duplicates::find_duplicates(conn, max_distance.unwrap_or(SIMILAR_DISTANCE))
+            .map_err(|e| e.context("Failed to find duplicate logos"))
+    })
+}
+
+// Keeps `keep_id` and folds the other logos into it
+#[tauri::command]
+pub fn merge_logos(keep_id: String, duplicate_ids: Vec<String>, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).merge(&keep_id, &duplicate_ids)
+            .map_err(|e| e.context("Failed to merge logos"))
+    })
+}
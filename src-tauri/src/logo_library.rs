@@ -1,7 +1,13 @@
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 use serde::{Serialize, Deserialize};
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager, State};
+use vj_event_sync_core::error::AppError;
+use vj_event_sync_core::{library, relink};
+use vj_event_sync_core::models::{LibraryEntry, Logo, RelinkReport};
+use vj_event_sync_core::repo::LogoRepo;
+use crate::{library_watcher, thumbnails, with_db, AppState};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LogoLibraryConfig {
@@ -9,60 +15,136 @@ pub struct LogoLibraryConfig {
 }
 
 impl LogoLibraryConfig {
-    pub fn load(app: &AppHandle) -> Result<Self, String> {
+    pub fn load(app: &AppHandle) -> Result<Self, AppError> {
         let config_path = get_config_path(app)?;
         if !config_path.exists() {
             return Ok(LogoLibraryConfig { path: String::new() });
         }
 
         let content = fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read config: {}", e))?;
+            .map_err(|e| AppError::io("Failed to read config", e))?;
             
         serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse config: {}", e))
+            .map_err(|e| AppError::config(format!("Failed to parse config: {}", e)))
     }
 
-    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+    // The configured folder, or None if none has been chosen yet
+    pub fn path(&self) -> Option<&Path> {
+        if self.path.is_empty() {
+            None
+        } else {
+            Some(Path::new(&self.path))
+        }
+    }
+
+    pub fn save(&self, app: &AppHandle) -> Result<(), AppError> {
         let config_path = get_config_path(app)?;
         
         // Ensure parent directory exists
         if let Some(parent) = config_path.parent() {
             fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+                .map_err(|e| AppError::io("Failed to create config directory", e))?;
         }
 
         let content = serde_json::to_string(self)
-            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+            .map_err(|e| AppError::config(format!("Failed to serialize config: {}", e)))?;
             
         fs::write(&config_path, content)
-            .map_err(|e| format!("Failed to write config: {}", e))
+            .map_err(|e| AppError::io("Failed to write config", e))
     }
 }
 
-fn get_config_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+// Tells the database which folder logo paths are stored relative to.
+// Called at startup and whenever the folder changes.
+pub fn sync_library_root(app: &AppHandle) -> Result<(), AppError> {
+    let config = LogoLibraryConfig::load(app)?;
+    let state = app.state::<AppState>();
+    with_db(&state, |conn| LogoRepo::new(conn).set_library_root(config.path()))?;
+    Ok(())
+}
+
+fn get_config_path(app: &AppHandle) -> Result<std::path::PathBuf, AppError> {
     app.path_resolver()
         .app_config_dir()
-        .ok_or_else(|| "Failed to get config directory".to_string())
+        .ok_or_else(|| AppError::config("Failed to get config directory"))
         .map(|p| p.join("logo_library_config.json"))
 }
 
 #[tauri::command]
-pub async fn get_logo_library_path(app: AppHandle) -> Result<String, String> {
+pub async fn get_logo_library_path(app: AppHandle) -> Result<String, AppError> {
     LogoLibraryConfig::load(&app).map(|config| config.path)
 }
 
 #[tauri::command]
-pub async fn save_logo_library_path(path: String, app: AppHandle) -> Result<(), String> {
+pub async fn save_logo_library_path(path: String, app: AppHandle) -> Result<(), AppError> {
     let config = LogoLibraryConfig { path };
-    config.save(&app)
+    config.save(&app)?;
+    sync_library_root(&app)?;
+    // Watch the new folder instead of the old one
+    library_watcher::start(&app);
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn ensure_logo_library_directory(path: String) -> Result<(), String> {
+pub async fn ensure_logo_library_directory(path: String) -> Result<(), AppError> {
     let path = Path::new(&path);
     if !path.exists() {
         fs::create_dir_all(path)
-            .map_err(|e| format!("Failed to create logo library directory: {}", e))?;
+            .map_err(|e| AppError::io("Failed to create logo library directory", e).with_field("path"))?;
     }
     Ok(())
 }
+
+// Scans the logo library folder, if one is set, so new, renamed and missing
+// files are reflected, then returns every logo for the gallery
+#[tauri::command]
+pub async fn list_logo_library(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<LibraryEntry>, AppError> {
+    let config = LogoLibraryConfig::load(&app)?;
+    // Scan before taking the database lock; big libraries take a while
+    let files = config
+        .path()
+        .map(library::scan_dir)
+        .transpose()
+        .map_err(|e| e.context("Failed to scan logo library"))?;
+    let entries = with_db(&state, |conn| {
+        if let (Some(root), Some(files)) = (config.path(), files) {
+            library::reconcile(conn, root, files, &HashSet::new())
+                .map_err(|e| e.context("Failed to scan logo library"))?;
+        }
+        library::entries(conn).map_err(|e| e.context("Failed to list logo library"))
+    });
+    thumbnails::request(&app);
+    entries
+}
+
+// Checks every logo's file and returns the logos whose file is missing
+#[tauri::command]
+pub async fn check_logo_files(state: State<'_, AppState>) -> Result<Vec<Logo>, AppError> {
+    let logos = with_db(&state, |conn| {
+        LogoRepo::new(conn).list().map_err(|e| e.context("Failed to list logos"))
+    })?;
+    // Check the files without holding the database lock
+    let checked = relink::stat_files(logos);
+    with_db(&state, |conn| {
+        relink::record_missing(conn, checked).map_err(|e| e.context("Failed to check logo files"))
+    })
+}
+
+// Rewrites logos stored under `old_root` to the same place under `new_root`,
+// e.g. after the library moved to another drive
+#[tauri::command]
+pub async fn relink_logo_root(old_root: String, new_root: String, state: State<'_, AppState>) -> Result<RelinkReport, AppError> {
+    with_db(&state, |conn| {
+        relink::relink_root(conn, &old_root, Path::new(&new_root))
+            .map_err(|e| e.context("Failed to relink logos"))
+    })
+}
+
+// Searches `folder` for the files of missing logos, by content and name
+#[tauri::command]
+pub async fn relink_logos_from_folder(folder: String, state: State<'_, AppState>) -> Result<RelinkReport, AppError> {
+    with_db(&state, |conn| {
+        relink::relink_search(conn, Path::new(&folder))
+            .map_err(|e| e.context("Failed to relink logos"))
+    })
+}
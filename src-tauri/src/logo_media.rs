@@ -0,0 +1,25 @@
+use vj_event_sync_core::media;
+
+use crate::{with_db, AppState};
+
+// Probes every logo whose file is new or changed for its dimensions,
+// transparency and timing. Files are read without holding the database;
+// run from the background worker, so logos added by hand or by a library
+// scan are picked up.
+pub fn update_media_info(state: &AppState) {
+    let jobs = match with_db(state, media::pending) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            eprintln!("Media probe: failed to list logos: {}", e);
+            return;
+        }
+    };
+    for job in jobs {
+        let probed = media::probe(&job.source)
+            .map_err(|e| eprintln!("Media probe: {}", e))
+            .ok();
+        if let Err(e) = with_db(state, |conn| media::store(conn, &job, probed.as_ref())) {
+            eprintln!("Media probe: {}", e);
+        }
+    }
+}
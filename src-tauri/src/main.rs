@@ -5,77 +5,43 @@
 
 mod logo_library;
 mod file_explorer;
+mod playback;
+mod library_watcher;
+mod thumbnails;
+mod logo_duplicates;
+mod logo_media;
 
-use tauri::{Manager, Window, WindowBuilder, WindowUrl};
+use tauri::{AppHandle, Manager, Window, WindowBuilder, WindowUrl};
 use std::sync::Mutex;
 use std::process::Command;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::collections::HashMap;
-use rusqlite::{Connection, Result};
-use uuid::Uuid;
-use std::time::{SystemTime, UNIX_EPOCH};
-use rusqlite::params;
+use std::path::Path;
+use rusqlite::Connection;
 use tauri::State;
+use vj_event_sync_core::{artist_match, db, ics, import, schedule, search, timetable};
+use vj_event_sync_core::timetable::TimetableOptions;
+use vj_event_sync_core::error::{AppError, AppResult};
+use vj_event_sync_core::models::{Artist, ArtistLogoLink, ArtistMatch, ArtistProfile, ConversationSummary, CycleGroup, CycleItem, CycleItemConfig, CycleSettings, ImportPreview, ImportSummary, ImportedEvent, Logo, LogoSearch, LogoSearchPage, RemoteLinkChanges, ScheduleConflict, ScheduleEventInput, ScheduleItem, ScheduleSettings, StoredMessage, Tag, TagCategory, TagInput};
+use vj_event_sync_core::repo::{ArtistRepo, CycleGroupRepo, CycleRepo, LogoRepo, MessageRepo, ScheduleRepo, TagRepo, VjToolsEventRepo};
 
 // Store the server process ID
 struct ServerState(Mutex<Option<u32>>);
 
-// Module settings structure
-#[derive(Debug, Serialize, Deserialize, Default)]
-struct ModuleSettings(HashMap<String, bool>);
-
-// Define the Artist struct
-#[derive(Debug, Serialize, Clone, Deserialize)]
-struct Artist {
-    id: String,
-    name: String,
-    vjtools_id: Option<String>,
-    created_at: u64,
-    updated_at: u64,
-    sync_status: String,
-}
-
-// Modified Logo struct to include linked artist names
-#[derive(Debug, Serialize, Clone, Deserialize)]
-struct Logo {
-    id: String,
-    name: String,
-    file_path: String,
-    thumbnail_path: Option<String>,
-    vjtools_id: Option<String>,
-    created_at: u64,
-    updated_at: u64,
-    sync_status: String,
-    #[serde(default)]
-    linked_djs: Vec<String>,
-}
-
-// Struct for Cycle Bar items
-#[derive(Debug, Serialize, Clone, Deserialize)]
-struct CycleItem {
-    id: String,
-    logo_id: String,
-    name: String,
-    status: String,
-    order_index: u32,
-}
-
-// Struct for Schedule Feed items
-#[derive(Debug, Serialize, Clone, Deserialize)]
-struct ScheduleItem {
-    id: String,
-    time: String,
-    name: String,
-    event_type: String,
-    duration_seconds: Option<u32>,
-    linked_logo_id: Option<String>,
-}
-
 // State to hold the database connection pool
 struct AppState {
     db: Mutex<Option<Connection>>,
 }
 
+// Runs `f` against the open database connection
+fn with_db<T>(state: &AppState, f: impl FnOnce(&Connection) -> AppResult<T>) -> AppResult<T> {
+    let maybe_conn = state.db.lock().unwrap();
+    match maybe_conn.as_ref() {
+        Some(conn) => f(conn),
+        None => Err(AppError::database_unavailable()),
+    }
+}
+
 // Function to initialize the database and tables
 fn init_database(app_handle: &tauri::AppHandle) -> Result<Connection, Box<dyn std::error::Error>> {
     let app_dir = app_handle.path_resolver().app_data_dir()
@@ -87,94 +53,19 @@ fn init_database(app_handle: &tauri::AppHandle) -> Result<Connection, Box<dyn st
     let db_path = app_dir.join("vj_local_db.sqlite");
     println!("Database path: {:?}", db_path);
 
-    let conn = Connection::open(&db_path)?;
-
-    // Enable Foreign Keys
-    conn.execute("PRAGMA foreign_keys = ON;", [])?;
-
-    // Create tables if they don't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS artists (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            vjtools_id TEXT,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL,
-            sync_status TEXT NOT NULL DEFAULT 'new'
-        )",
-        [],
-    )?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS logos (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            file_path TEXT NOT NULL UNIQUE, -- Assuming file paths should be unique locally
-            thumbnail_path TEXT,
-            vjtools_id TEXT,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL,
-            sync_status TEXT NOT NULL DEFAULT 'new'
-        )",
-        [],
-    )?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS artist_logos (
-            artist_id TEXT NOT NULL,
-            logo_id TEXT NOT NULL,
-            is_local_override INTEGER NOT NULL DEFAULT 0, -- 0=false, 1=true. Default to synced/global link.
-            FOREIGN KEY (artist_id) REFERENCES artists(id) ON DELETE CASCADE,
-            FOREIGN KEY (logo_id) REFERENCES logos(id) ON DELETE CASCADE,
-            PRIMARY KEY (artist_id, logo_id)
-        )",
-        [],
-    )?;
-
-    // NEW: Schedule Events Table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS schedule_events (
-            id TEXT PRIMARY KEY,
-            event_time TEXT NOT NULL, -- Format 'HH:MM' 
-            name TEXT NOT NULL,
-            event_type TEXT NOT NULL, 
-            duration_seconds INTEGER, -- Duration in seconds, NULL if indefinite/until next
-            linked_logo_id TEXT, -- Optional link to a logo
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL,
-            FOREIGN KEY (linked_logo_id) REFERENCES logos(id) ON DELETE SET NULL
-        )",
-        [],
-    )?;
-    // Optional: Index on time for faster lookups
-    conn.execute("CREATE INDEX IF NOT EXISTS idx_schedule_time ON schedule_events (event_time);", [])?;
-
-    // NEW: Cycle Configuration Table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS cycle_config (
-            logo_id TEXT NOT NULL PRIMARY KEY, 
-            order_index INTEGER NOT NULL UNIQUE, -- Defines the playback order
-            status TEXT NOT NULL DEFAULT 'cycle', -- Status within the cycle itself (maybe less useful here?)
-            FOREIGN KEY (logo_id) REFERENCES logos(id) ON DELETE CASCADE
-        )",
-        [],
-    )?;
-    conn.execute("CREATE INDEX IF NOT EXISTS idx_cycle_order ON cycle_config (order_index);", [])?;
+    // Opens the file and brings the schema up to date (backs up existing data first)
+    let conn = db::open(&db_path)?;
 
     println!("Database initialized successfully.");
     Ok(conn)
 }
 
-// Simple function to get current timestamp as u64
-fn current_timestamp() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs()
-}
-
-use logo_library::{get_logo_library_path, save_logo_library_path, ensure_logo_library_directory};
+use logo_library::{get_logo_library_path, save_logo_library_path, ensure_logo_library_directory, list_logo_library, check_logo_files, relink_logo_root, relink_logos_from_folder};
 use file_explorer::list_directory_contents;
+use playback::{get_now_showing, PlaybackState};
+use library_watcher::LibraryWatcherState;
+use thumbnails::ThumbnailWorker;
+use logo_duplicates::{find_duplicate_logos, merge_logos};
 
 fn main() {
     let state = AppState {
@@ -184,6 +75,9 @@ fn main() {
     tauri::Builder::default()
         .manage(ServerState(Mutex::new(None)))
         .manage(state)
+        .manage(PlaybackState(Mutex::new(None)))
+        .manage(LibraryWatcherState(Mutex::new(None)))
+        .manage(ThumbnailWorker(Mutex::new(None)))
         .invoke_handler(tauri::generate_handler![
             start_server,
             stop_server,
@@ -193,23 +87,76 @@ fn main() {
             get_module_settings,
             save_module_settings,
             greet,
+            store_events,
+            get_stored_events,
+            store_messages,
+            get_stored_messages,
+            get_message_conversations,
+            queue_message,
+            get_queued_messages,
+            mark_message_delivered,
+            mark_messages_read,
             add_artist,
             get_artists,
+            update_artist,
+            update_artist_profile,
+            delete_artist,
+            find_artist_matches,
+            add_artist_alias,
+            remove_artist_alias,
+            merge_artists,
             add_logo,
             get_logos,
+            update_logo,
+            delete_logo,
             link_logo_to_artist,
             unlink_logo_from_artist,
+            get_artist_logo_links,
+            set_artist_logo_override,
+            apply_remote_artist_logos,
             get_logos_for_artist,
+            get_tags,
+            add_logo_tags,
+            remove_logo_tags,
+            delete_tag,
+            get_logos_by_tags,
+            search_logos,
             get_cycle_items,
             get_schedule_items,
             add_schedule_event,
+            update_schedule_event,
+            delete_schedule_event,
+            get_schedule_conflicts,
+            get_schedule_settings,
+            set_schedule_strict_mode,
+            preview_ics_import,
+            preview_timetable_import,
+            apply_schedule_import,
+            export_schedule_ics,
             set_cycle_config,
+            update_cycle_item,
+            get_cycle_settings,
+            set_cycle_default_duration,
+            get_cycle_groups,
+            create_cycle_group,
+            duplicate_cycle_group,
+            rename_cycle_group,
+            delete_cycle_group,
+            activate_cycle_group,
             // Logo library commands
             get_logo_library_path,
             save_logo_library_path,
             ensure_logo_library_directory,
+            list_logo_library,
+            check_logo_files,
+            relink_logo_root,
+            relink_logos_from_folder,
+            find_duplicate_logos,
+            merge_logos,
             // File explorer commands
-            list_directory_contents
+            list_directory_contents,
+            // Playback commands
+            get_now_showing
         ])
         .setup(|app| {
             // Check if the window already exists
@@ -236,17 +183,14 @@ fn main() {
             let handle_for_event = app_handle.clone();
             
             window.on_window_event(move |event| {
-                match event {
-                    tauri::WindowEvent::CloseRequested { api, .. } => {
-                        let server_state = handle_for_event.state::<ServerState>();
-                        let server_pid = server_state.0.lock().unwrap();
-                        
-                        if server_pid.is_some() {
-                            api.prevent_close();
-                            window_clone.emit("server-running", ()).unwrap();
-                        }
-                    },
-                    _ => {}
+                if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                    let server_state = handle_for_event.state::<ServerState>();
+                    let server_pid = server_state.0.lock().unwrap();
+                    
+                    if server_pid.is_some() {
+                        api.prevent_close();
+                        window_clone.emit("server-running", ()).unwrap();
+                    }
                 }
             });
 
@@ -310,6 +254,18 @@ fn main() {
                 .expect("Failed to initialize database");
             let app_state: tauri::State<AppState> = app_handle.state();
             *app_state.db.lock().unwrap() = Some(conn);
+            // Store logo paths relative to the library folder
+            if let Err(e) = logo_library::sync_library_root(&app_handle) {
+                eprintln!("Logo library: failed to set the library root: {}", e);
+            }
+
+            // Start resolving "Now Showing" from the cycle and schedule
+            playback::start(app_handle.clone());
+            // Render thumbnails in the background; started first so the
+            // watcher's initial scan can queue work for it
+            thumbnails::start(&app_handle);
+            // Keep the logos table in step with the library folder
+            library_watcher::start(&app_handle);
             
             Ok(())
         })
@@ -318,33 +274,33 @@ fn main() {
 }
 
 #[tauri::command]
-fn start_server(window: Window, state: tauri::State<ServerState>) -> Result<(), String> {
+fn start_server(window: Window, state: tauri::State<ServerState>) -> Result<(), AppError> {
     // Check if server is already running
     let mut server_pid = state.0.lock().unwrap();
     if server_pid.is_some() {
-        return Err("Server is already running".to_string());
+        return Err(AppError::conflict("Server is already running"));
     }
     
     // Start the server process
     let output = Command::new("node")
         .arg("src/server/index.js")
         .spawn()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppError::io("Failed to start server", e))?;
     
     // Store the process ID
     *server_pid = Some(output.id());
     
     // Notify the frontend
-    window.emit("server-started", ()).map_err(|e| e.to_string())?;
+    window.emit("server-started", ()).map_err(|e| AppError::internal(e.to_string()))?;
     
     Ok(())
 }
 
 #[tauri::command]
-fn stop_server(window: Window, state: tauri::State<ServerState>) -> Result<(), String> {
+fn stop_server(window: Window, state: tauri::State<ServerState>) -> Result<(), AppError> {
     // Get the server process ID
     let mut server_pid = state.0.lock().unwrap();
-    let pid = server_pid.take().ok_or("Server is not running")?;
+    let pid = server_pid.take().ok_or_else(|| AppError::conflict("Server is not running"))?;
     
     // Kill the process
     #[cfg(target_os = "windows")]
@@ -352,7 +308,7 @@ fn stop_server(window: Window, state: tauri::State<ServerState>) -> Result<(), S
         Command::new("taskkill")
             .args(&["/F", "/PID", &pid.to_string()])
             .output()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| AppError::io("Failed to stop server", e))?;
     }
     
     #[cfg(target_os = "linux")]
@@ -361,7 +317,7 @@ fn stop_server(window: Window, state: tauri::State<ServerState>) -> Result<(), S
             .arg("-9")
             .arg(pid.to_string())
             .output()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| AppError::io("Failed to stop server", e))?;
     }
     
     #[cfg(target_os = "macos")]
@@ -370,42 +326,42 @@ fn stop_server(window: Window, state: tauri::State<ServerState>) -> Result<(), S
             .arg("-9")
             .arg(pid.to_string())
             .output()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| AppError::io("Failed to stop server", e))?;
     }
     
     // Notify the frontend
-    window.emit("server-stopped", ()).map_err(|e| e.to_string())?;
+    window.emit("server-stopped", ()).map_err(|e| AppError::internal(e.to_string()))?;
     
     Ok(())
 }
 
 #[tauri::command]
-fn minimize_window(window: Window) -> Result<(), String> {
-    window.minimize().map_err(|e| e.to_string())
+fn minimize_window(window: Window) -> Result<(), AppError> {
+    window.minimize().map_err(|e| AppError::internal(e.to_string()))
 }
 
 #[tauri::command]
-fn maximize_window(window: Window) -> Result<(), String> {
+fn maximize_window(window: Window) -> Result<(), AppError> {
     if window.is_maximized().unwrap_or(false) {
-        window.unmaximize().map_err(|e| e.to_string())
+        window.unmaximize().map_err(|e| AppError::internal(e.to_string()))
     } else {
-        window.maximize().map_err(|e| e.to_string())
+        window.maximize().map_err(|e| AppError::internal(e.to_string()))
     }
 }
 
 #[tauri::command]
-fn close_window(window: Window) -> Result<(), String> {
-    window.close().map_err(|e| e.to_string())
+fn close_window(window: Window) -> Result<(), AppError> {
+    window.close().map_err(|e| AppError::internal(e.to_string()))
 }
 
 #[tauri::command]
-async fn get_module_settings() -> Result<HashMap<String, bool>, String> {
+async fn get_module_settings() -> Result<HashMap<String, bool>, AppError> {
     // In a real app, this would load from a config file or database
     Ok(HashMap::new())
 }
 
 #[tauri::command]
-async fn save_module_settings(settings: HashMap<String, bool>) -> Result<(), String> {
+async fn save_module_settings(settings: HashMap<String, bool>) -> Result<(), AppError> {
     // In a real app, this would save to a config file or database
     println!("Saving module settings: {:?}", settings);
     Ok(())
@@ -416,338 +372,570 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-// Command to add a new artist
+// Command to keep the events fetched from vj.tools for offline use,
+// replacing the stored copies of events with the same id
+#[tauri::command]
+fn store_events(events: Vec<serde_json::Value>, state: State<AppState>) -> Result<usize, AppError> {
+    with_db(&state, |conn| {
+        VjToolsEventRepo::new(conn).store(&events)
+            .map_err(|e| e.context("Failed to store events"))
+    })
+}
+
+// Command to get the stored vj.tools events, as they were received, by
+// start time. Used when vj.tools can't be reached.
 #[tauri::command]
-fn add_artist(name: String, state: tauri::State<AppState>) -> Result<String, String> {
-    let new_id = Uuid::new_v4().to_string();
-    let now = current_timestamp();
+fn get_stored_events(state: State<AppState>) -> Result<Vec<serde_json::Value>, AppError> {
+    with_db(&state, |conn| {
+        let events = VjToolsEventRepo::new(conn).list()
+            .map_err(|e| e.context("Failed to query stored events"))?;
+        Ok(events.into_iter().map(|e| e.payload).collect())
+    })
+}
 
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        match conn.execute(
-            "INSERT INTO artists (id, name, created_at, updated_at, sync_status) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (&new_id, &name, &now, &now, "new"),
-        ) {
-            Ok(_) => Ok(new_id), // Return the new artist's ID on success
-            Err(e) => Err(format!("Failed to add artist: {}", e)),
-        }
-    } else {
-        Err("Database connection not available".to_string())
-    }
+// Command to keep the messages fetched from vj.tools for offline use.
+// Storing a message again updates it, so syncs can be repeated.
+#[tauri::command]
+fn store_messages(messages: Vec<serde_json::Value>, state: State<AppState>) -> Result<usize, AppError> {
+    with_db(&state, |conn| {
+        MessageRepo::new(conn).store(&messages)
+            .map_err(|e| e.context("Failed to store messages"))
+    })
+}
+
+// Command to get stored messages, oldest first, including queued ones; only
+// one conversation's if `conversation_id` is given
+#[tauri::command]
+fn get_stored_messages(conversation_id: Option<String>, state: State<AppState>) -> Result<Vec<StoredMessage>, AppError> {
+    with_db(&state, |conn| {
+        MessageRepo::new(conn).list(conversation_id.as_deref())
+            .map_err(|e| e.context("Failed to query stored messages"))
+    })
+}
+
+// Command to get the conversations with stored messages, most recent first
+#[tauri::command]
+fn get_message_conversations(state: State<AppState>) -> Result<Vec<ConversationSummary>, AppError> {
+    with_db(&state, |conn| {
+        MessageRepo::new(conn).conversations()
+            .map_err(|e| e.context("Failed to query conversations"))
+    })
+}
+
+// Command to keep a message written while offline until it can be sent
+#[tauri::command]
+fn queue_message(conversation_id: String, sender_id: String, content: String, state: State<AppState>) -> Result<StoredMessage, AppError> {
+    with_db(&state, |conn| {
+        MessageRepo::new(conn).queue(&conversation_id, &sender_id, &content)
+            .map_err(|e| e.context("Failed to queue message"))
+    })
+}
+
+// Command to get the messages waiting to be sent, oldest first
+#[tauri::command]
+fn get_queued_messages(state: State<AppState>) -> Result<Vec<StoredMessage>, AppError> {
+    with_db(&state, |conn| {
+        MessageRepo::new(conn).queued()
+            .map_err(|e| e.context("Failed to query queued messages"))
+    })
+}
+
+// Command to mark a queued message sent, under the id vj.tools gave it
+#[tauri::command]
+fn mark_message_delivered(local_id: String, message: serde_json::Value, state: State<AppState>) -> Result<StoredMessage, AppError> {
+    with_db(&state, |conn| {
+        MessageRepo::new(conn).mark_delivered(&local_id, &message)
+            .map_err(|e| e.context("Failed to mark message delivered"))
+    })
+}
+
+// Command to mark messages read
+#[tauri::command]
+fn mark_messages_read(ids: Vec<String>, state: State<AppState>) -> Result<usize, AppError> {
+    with_db(&state, |conn| {
+        MessageRepo::new(conn).mark_read(&ids)
+            .map_err(|e| e.context("Failed to mark messages read"))
+    })
+}
+
+// Command to add a new artist
+#[tauri::command]
+fn add_artist(name: String, state: State<AppState>) -> Result<String, AppError> {
+    with_db(&state, |conn| {
+        ArtistRepo::new(conn).add(&name)
+            .map_err(|e| e.context("Failed to add artist"))
+    })
 }
 
 // Command to get all artists
 #[tauri::command]
-fn get_artists(state: tauri::State<AppState>) -> Result<Vec<Artist>, String> {
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        let mut stmt = conn.prepare("SELECT id, name, vjtools_id, created_at, updated_at, sync_status FROM artists ORDER BY name ASC")
-            .map_err(|e| format!("Failed to prepare query: {}", e))?;
-
-        let artist_iter = stmt.query_map([], |row| {
-            Ok(Artist {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                vjtools_id: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-                sync_status: row.get(5)?,
-            })
-        }).map_err(|e| format!("Failed to query artists: {}", e))?;
-
-        let artists = artist_iter.collect::<Result<Vec<Artist>, rusqlite::Error>>()
-            .map_err(|e| format!("Failed to collect artists: {}", e))?;
-        
-        Ok(artists)
-    } else {
-        Err("Database connection not available".to_string())
-    }
+fn get_artists(state: State<AppState>) -> Result<Vec<Artist>, AppError> {
+    with_db(&state, |conn| {
+        ArtistRepo::new(conn).list()
+            .map_err(|e| e.context("Failed to query artists"))
+    })
 }
 
-// Command to add a new logo
+// Command to rename an artist
 #[tauri::command]
-fn add_logo(name: String, file_path: String, thumbnail_path: Option<String>, state: tauri::State<AppState>) -> Result<String, String> {
-    let new_id = Uuid::new_v4().to_string();
-    let now = current_timestamp();
+fn update_artist(id: String, name: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ArtistRepo::new(conn).update(&id, &name)
+            .map_err(|e| e.context("Failed to update artist"))
+    })
+}
 
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        match conn.execute(
-            "INSERT INTO logos (id, name, file_path, thumbnail_path, created_at, updated_at, sync_status) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            (
-                &new_id,
-                &name,
-                &file_path,
-                &thumbnail_path,
-                &now,
-                &now,
-                "new",
-            ),
-        ) {
-            Ok(_) => Ok(new_id), // Return the new logo's ID
-            Err(e) => {
-                 // Check for UNIQUE constraint violation on file_path
-                if let rusqlite::Error::SqliteFailure(ref err, _) = e {
-                    if err.code == rusqlite::ErrorCode::ConstraintViolation {
-                        // Consider finding the existing logo ID and returning it, or just returning an error
-                         return Err(format!("Logo with file path '{}' already exists.", file_path));
-                    }
-                }
-                Err(format!("Failed to add logo: {}", e))
-            },
-        }
-    } else {
-        Err("Database connection not available".to_string())
-    }
+// Command to replace an artist's profile: display name, pronunciation,
+// brand colour, preferred logo, social handles and notes
+#[tauri::command]
+fn update_artist_profile(id: String, profile: ArtistProfile, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ArtistRepo::new(conn).update_profile(&id, &profile)
+            .map_err(|e| e.context("Failed to update artist profile"))
+    })
+}
+
+// Command to delete an artist (tombstoned if it has been synced)
+#[tauri::command]
+fn delete_artist(id: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ArtistRepo::new(conn).delete(&id)
+            .map_err(|e| e.context("Failed to delete artist"))
+    })
+}
+
+// Command to find the artists a name could refer to, best match first
+// (10 at most unless `limit` says otherwise)
+#[tauri::command]
+fn find_artist_matches(name: String, limit: Option<usize>, state: State<AppState>) -> Result<Vec<ArtistMatch>, AppError> {
+    with_db(&state, |conn| {
+        let artists = ArtistRepo::new(conn).list()
+            .map_err(|e| e.context("Failed to query artists"))?;
+        Ok(artist_match::rank_artists(&artists, &name, limit.unwrap_or(10)))
+    })
+}
+
+// Command to record another name an artist appears under
+#[tauri::command]
+fn add_artist_alias(artist_id: String, alias: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ArtistRepo::new(conn).add_alias(&artist_id, &alias)
+            .map_err(|e| e.context("Failed to add artist alias"))
+    })
+}
+
+// Command to remove an artist alias
+#[tauri::command]
+fn remove_artist_alias(artist_id: String, alias: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ArtistRepo::new(conn).remove_alias(&artist_id, &alias)
+            .map_err(|e| e.context("Failed to remove artist alias"))
+    })
+}
+
+// Command to fold duplicate artists into one: aliases, logo links and
+// schedule event names move to `keep_id`
+#[tauri::command]
+fn merge_artists(keep_id: String, duplicate_ids: Vec<String>, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ArtistRepo::new(conn).merge(&keep_id, &duplicate_ids)
+            .map_err(|e| e.context("Failed to merge artists"))
+    })
+}
+
+// Command to add a new logo
+#[tauri::command]
+fn add_logo(name: String, file_path: String, thumbnail_path: Option<String>, app: AppHandle, state: State<AppState>) -> Result<String, AppError> {
+    let id = with_db(&state, |conn| {
+        // A UNIQUE clash on file_path comes back as a Duplicate error carrying
+        // the existing logo's id, so the UI can offer to open it
+        LogoRepo::new(conn).add(&name, &file_path, thumbnail_path.as_deref())
+    })?;
+    thumbnails::request(&app);
+    Ok(id)
 }
 
 // Command to get all logos
 #[tauri::command]
-fn get_logos(state: tauri::State<AppState>) -> Result<Vec<Logo>, String> {
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        // 1. Fetch all logos
-        let mut stmt_logos = conn.prepare("SELECT id, name, file_path, thumbnail_path, vjtools_id, created_at, updated_at, sync_status FROM logos ORDER BY name ASC")
-            .map_err(|e| format!("Failed to prepare logo query: {}", e))?;
-        
-        let logo_iter = stmt_logos.query_map([], |row| {
-            // Temporarily create struct without linked_djs
-            Ok(Logo {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                file_path: row.get(2)?,
-                thumbnail_path: row.get(3)?,
-                vjtools_id: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-                sync_status: row.get(7)?,
-                linked_djs: Vec::new(), // Initialize as empty for now
-            })
-        }).map_err(|e| format!("Failed to query logos: {}", e))?;
-
-        let mut logos = logo_iter.collect::<Result<Vec<Logo>, rusqlite::Error>>()
-            .map_err(|e| format!("Failed to collect logos: {}", e))?;
-
-        // 2. Fetch all artist links (logo_id, artist_name)
-        let mut stmt_links = conn.prepare("SELECT al.logo_id, a.name FROM artist_logos al JOIN artists a ON al.artist_id = a.id")
-             .map_err(|e| format!("Failed to prepare link query: {}", e))?;
-
-        let link_iter = stmt_links.query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-        }).map_err(|e| format!("Failed to query links: {}", e))?;
-
-        // 3. Group links by logo_id
-        let mut links_map: HashMap<String, Vec<String>> = HashMap::new();
-        for result in link_iter {
-            match result {
-                Ok((logo_id, artist_name)) => {
-                    links_map.entry(logo_id).or_default().push(artist_name);
-                }
-                Err(e) => return Err(format!("Failed to process link row: {}", e)),
-            }
-        }
+fn get_logos(state: State<AppState>) -> Result<Vec<Logo>, AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).list()
+            .map_err(|e| e.context("Failed to query logos"))
+    })
+}
 
-        // 4. Populate linked_djs in logos
-        for logo in logos.iter_mut() {
-            if let Some(djs) = links_map.get(&logo.id) {
-                logo.linked_djs = djs.clone();
-            }
-        }
-        
-        Ok(logos)
-    } else {
-        Err("Database connection not available".to_string())
-    }
+// Command to update a logo's name, file and thumbnail
+#[tauri::command]
+fn update_logo(id: String, name: String, file_path: String, thumbnail_path: Option<String>, app: AppHandle, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).update(&id, &name, &file_path, thumbnail_path.as_deref())
+    })?;
+    thumbnails::request(&app);
+    Ok(())
 }
 
-// Command to link a logo to an artist
+// Command to delete a logo (tombstoned if it has been synced)
 #[tauri::command]
-fn link_logo_to_artist(artist_id: String, logo_id: String, is_override: Option<bool>, state: tauri::State<AppState>) -> Result<(), String> {
-    let is_local_override = is_override.unwrap_or(false) as i32; // Default to false (0)
+fn delete_logo(id: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).delete(&id)
+            .map_err(|e| e.context("Failed to delete logo"))
+    })
+}
 
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        // Use INSERT OR IGNORE to avoid errors if the link already exists
-        // If it exists, we might want to update `is_local_override` in a separate step if needed, but ignore is simpler for now.
-        match conn.execute(
-            "INSERT OR IGNORE INTO artist_logos (artist_id, logo_id, is_local_override) VALUES (?1, ?2, ?3)",
-            (&artist_id, &logo_id, &is_local_override),
-        ) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("Failed to link logo to artist: {}", e)),
-        }
-    } else {
-        Err("Database connection not available".to_string())
-    }
+// Command to link a logo to an artist
+#[tauri::command]
+fn link_logo_to_artist(artist_id: String, logo_id: String, is_override: Option<bool>, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).link_artist(&artist_id, &logo_id, is_override.unwrap_or(false))
+            .map_err(|e| e.context("Failed to link logo to artist"))
+    })
 }
 
 // Command to unlink a logo from an artist
 #[tauri::command]
-fn unlink_logo_from_artist(artist_id: String, logo_id: String, state: tauri::State<AppState>) -> Result<(), String> {
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        match conn.execute(
-            "DELETE FROM artist_logos WHERE artist_id = ?1 AND logo_id = ?2",
-            (&artist_id, &logo_id),
-        ) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("Failed to unlink logo from artist: {}", e)),
-        }
-    } else {
-        Err("Database connection not available".to_string())
-    }
+fn unlink_logo_from_artist(artist_id: String, logo_id: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).unlink_artist(&artist_id, &logo_id)
+            .map_err(|e| e.context("Failed to unlink logo from artist"))
+    })
+}
+
+// Command to list artist-logo links with their local override flag, for one
+// artist or all of them
+#[tauri::command]
+fn get_artist_logo_links(artist_id: Option<String>, state: State<AppState>) -> Result<Vec<ArtistLogoLink>, AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).list_links(artist_id.as_deref())
+            .map_err(|e| e.context("Failed to query artist logo links"))
+    })
+}
+
+// Command to mark an artist-logo link as a local override or as synced
+#[tauri::command]
+fn set_artist_logo_override(artist_id: String, logo_id: String, is_override: bool, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).set_link_override(&artist_id, &logo_id, is_override)
+            .map_err(|e| e.context("Failed to update artist logo link"))
+    })
+}
+
+// Command for sync: applies the logos vj.tools lists for an artist,
+// leaving local overrides as they are
+#[tauri::command]
+fn apply_remote_artist_logos(artist_id: String, logo_ids: Vec<String>, state: State<AppState>) -> Result<RemoteLinkChanges, AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).apply_remote_links(&artist_id, &logo_ids)
+            .map_err(|e| e.context("Failed to apply synced artist logos"))
+    })
 }
 
 // Command to get all logos linked to a specific artist
 #[tauri::command]
-fn get_logos_for_artist(artist_id: String, state: tauri::State<AppState>) -> Result<Vec<Logo>, String> {
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        let mut stmt = conn.prepare(
-            "SELECT l.id, l.name, l.file_path, l.thumbnail_path, l.vjtools_id, l.created_at, l.updated_at, l.sync_status 
-             FROM logos l
-             INNER JOIN artist_logos al ON l.id = al.logo_id
-             WHERE al.artist_id = ?1
-             ORDER BY l.name ASC"
-        ).map_err(|e| format!("Failed to prepare query: {}", e))?;
-
-        let logo_iter = stmt.query_map([&artist_id], |row| {
-             Ok(Logo {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                file_path: row.get(2)?,
-                thumbnail_path: row.get(3)?,
-                vjtools_id: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-                sync_status: row.get(7)?,
-                linked_djs: Vec::new(),
-            })
-        }).map_err(|e| format!("Failed to query logos for artist: {}", e))?;
-
-        let logos = logo_iter.collect::<Result<Vec<Logo>, rusqlite::Error>>()
-            .map_err(|e| format!("Failed to collect logos: {}", e))?;
-        
-        Ok(logos)
-    } else {
-        Err("Database connection not available".to_string())
-    }
+fn get_logos_for_artist(artist_id: String, state: State<AppState>) -> Result<Vec<Logo>, AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).list_for_artist(&artist_id)
+            .map_err(|e| e.context("Failed to query logos for artist"))
+    })
 }
 
-// --- Schedule Commands ---
+// --- Tag Commands ---
 
+// All tags, or only those of one category (dj, type, source, custom)
 #[tauri::command]
-fn get_schedule_items(state: State<AppState>) -> Result<Vec<ScheduleItem>, String> {
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        let mut stmt = conn.prepare(
-                "SELECT id, event_time, name, event_type, duration_seconds, linked_logo_id 
-                 FROM schedule_events ORDER BY event_time ASC"
-            ).map_err(|e| format!("Schedule Query Prepare Failed: {}", e))?;
-
-        let item_iter = stmt.query_map([], |row| {
-            Ok(ScheduleItem {
-                id: row.get(0)?,
-                time: row.get(1)?,
-                name: row.get(2)?,
-                event_type: row.get(3)?,
-                duration_seconds: row.get(4)?,
-                linked_logo_id: row.get(5)?,
-                // created_at/updated_at omitted for brevity
-            })
-        }).map_err(|e| format!("Schedule Query Map Failed: {}", e))?;
-
-        item_iter.collect::<Result<Vec<ScheduleItem>>>()
-                 .map_err(|e| format!("Schedule Collect Failed: {}", e))
-    } else {
-        Err("Database connection not available".to_string())
-    }
+fn get_tags(category: Option<TagCategory>, state: State<AppState>) -> Result<Vec<Tag>, AppError> {
+    with_db(&state, |conn| {
+        TagRepo::new(conn).list(category)
+            .map_err(|e| e.context("Failed to query tags"))
+    })
+}
+
+// Tags every logo in `logo_ids` with every tag in `tags`, creating tags
+// that don't exist yet. Returns the tag ids.
+#[tauri::command]
+fn add_logo_tags(logo_ids: Vec<String>, tags: Vec<TagInput>, state: State<AppState>) -> Result<Vec<String>, AppError> {
+    with_db(&state, |conn| {
+        TagRepo::new(conn).add_to_logos(&logo_ids, &tags)
+            .map_err(|e| e.context("Failed to tag logos"))
+    })
+}
+
+// Returns how many tags were taken off
+#[tauri::command]
+fn remove_logo_tags(logo_ids: Vec<String>, tag_ids: Vec<String>, state: State<AppState>) -> Result<usize, AppError> {
+    with_db(&state, |conn| {
+        TagRepo::new(conn).remove_from_logos(&logo_ids, &tag_ids)
+            .map_err(|e| e.context("Failed to remove tags"))
+    })
+}
+
+#[tauri::command]
+fn delete_tag(id: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        TagRepo::new(conn).delete(&id)
+            .map_err(|e| e.context("Failed to delete tag"))
+    })
+}
+
+// Logos with any of the selected tags of each category, e.g. "sponsor or
+// intro" and "imported"
+#[tauri::command]
+fn get_logos_by_tags(tag_ids: Vec<String>, state: State<AppState>) -> Result<Vec<Logo>, AppError> {
+    with_db(&state, |conn| {
+        LogoRepo::new(conn).list_tagged(&tag_ids)
+            .map_err(|e| e.context("Failed to query logos"))
+    })
 }
 
+// Full-text search over logo, file, artist and tag names, with filters,
+// sorting and paging
 #[tauri::command]
-fn add_schedule_event(
-    event_time: String, 
-    name: String, 
-    event_type: String, 
-    duration_seconds: Option<u32>, 
+fn search_logos(search: LogoSearch, state: State<AppState>) -> Result<LogoSearchPage, AppError> {
+    with_db(&state, |conn| {
+        search::search_logos(conn, &search)
+            .map_err(|e| e.context("Logo search failed"))
+    })
+}
+
+// --- Schedule Commands ---
+
+#[tauri::command]
+fn get_schedule_items(state: State<AppState>) -> Result<Vec<ScheduleItem>, AppError> {
+    with_db(&state, |conn| {
+        ScheduleRepo::new(conn).list()
+            .map_err(|e| e.context("Schedule Query Failed"))
+    })
+}
+
+// Fields of a new or edited schedule event. `starts_at` / `ends_at` are
+// local date-times such as "2026-10-17T23:30"; leave `ends_at` out for an
+// event that runs until the next one.
+#[derive(Deserialize)]
+struct ScheduleEventPayload {
+    starts_at: String,
+    ends_at: Option<String>,
+    name: String,
+    event_type: String,
     linked_logo_id: Option<String>,
-    state: State<AppState>
-) -> Result<String, String> {
-    let new_id = Uuid::new_v4().to_string();
-    let now = current_timestamp();
-    // TODO: Validate event_time format ("HH:MM")?
+    #[serde(default)]
+    priority: i32,
+}
 
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        conn.execute(
-            "INSERT INTO schedule_events (id, event_time, name, event_type, duration_seconds, linked_logo_id, created_at, updated_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![new_id, event_time, name, event_type, duration_seconds, linked_logo_id, now, now],
-        ).map_err(|e| format!("Failed to add schedule event: {}", e))?;
-        Ok(new_id)
-    } else {
-        Err("Database connection not available".to_string())
+impl ScheduleEventPayload {
+    fn into_input(self) -> AppResult<ScheduleEventInput> {
+        let (starts_at, ends_at) = schedule::parse_event_times(&self.starts_at, self.ends_at.as_deref())?;
+        Ok(ScheduleEventInput {
+            starts_at,
+            ends_at,
+            name: self.name,
+            event_type: self.event_type,
+            linked_logo_id: self.linked_logo_id,
+            priority: self.priority,
+        })
     }
 }
 
+#[tauri::command]
+fn add_schedule_event(event: ScheduleEventPayload, state: State<AppState>) -> Result<String, AppError> {
+    with_db(&state, |conn| {
+        let event = event.into_input()?;
+        ScheduleRepo::new(conn).add(&event)
+    })
+    .map_err(|e| e.context("Failed to add schedule event"))
+}
+
+#[tauri::command]
+fn update_schedule_event(id: String, event: ScheduleEventPayload, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        let event = event.into_input()?;
+        ScheduleRepo::new(conn).update(&id, &event)
+    })
+    .map_err(|e| e.context("Failed to update schedule event"))
+}
+
+#[tauri::command]
+fn delete_schedule_event(id: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ScheduleRepo::new(conn).delete(&id)
+            .map_err(|e| e.context("Failed to delete schedule event"))
+    })
+}
+
+// Overlapping event pairs, for flagging them in the schedule view
+#[tauri::command]
+fn get_schedule_conflicts(state: State<AppState>) -> Result<Vec<ScheduleConflict>, AppError> {
+    with_db(&state, |conn| {
+        ScheduleRepo::new(conn).conflicts()
+            .map_err(|e| e.context("Schedule Conflict Query Failed"))
+    })
+}
+
+#[tauri::command]
+fn get_schedule_settings(state: State<AppState>) -> Result<ScheduleSettings, AppError> {
+    with_db(&state, |conn| {
+        ScheduleRepo::new(conn).settings()
+            .map_err(|e| e.context("Failed to load schedule settings"))
+    })
+}
+
+#[tauri::command]
+fn set_schedule_strict_mode(enabled: bool, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ScheduleRepo::new(conn).set_strict_conflicts(enabled)
+            .map_err(|e| e.context("Failed to update schedule settings"))
+    })
+}
+
+// --- Schedule Import / Export Commands ---
+
+// Reads an .ics file and shows what importing it would change, without
+// writing anything
+#[tauri::command]
+fn preview_ics_import(path: String, state: State<AppState>) -> Result<ImportPreview, AppError> {
+    with_db(&state, |conn| {
+        ics::preview_file(conn, Path::new(&path))
+            .map_err(|e| e.context("Failed to read calendar"))
+    })
+}
+
+// Reads a running order from a CSV or spreadsheet file. Every row becomes a
+// DJ set; artists not in the library yet are added when the preview is
+// applied with `apply_schedule_import`.
+#[tauri::command]
+fn preview_timetable_import(path: String, options: Option<TimetableOptions>, state: State<AppState>) -> Result<ImportPreview, AppError> {
+    let options = options.unwrap_or_default();
+    with_db(&state, |conn| {
+        timetable::preview_file(conn, Path::new(&path), &options)
+            .map_err(|e| e.context("Failed to read timetable"))
+    })
+}
+
+// Writes the (possibly filtered) events of an import preview
+#[tauri::command]
+fn apply_schedule_import(events: Vec<ImportedEvent>, state: State<AppState>) -> Result<ImportSummary, AppError> {
+    with_db(&state, |conn| {
+        import::apply(conn, &events)
+            .map_err(|e| e.context("Failed to import schedule"))
+    })
+}
+
+#[tauri::command]
+fn export_schedule_ics(path: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        ics::export_file(conn, Path::new(&path))
+            .map_err(|e| e.context("Failed to export schedule"))
+    })
+}
+
 // --- Cycle Commands ---
 
+// Commands that take an optional `group_id` work on the active group when
+// it's omitted
 #[tauri::command]
-fn get_cycle_items(state: State<AppState>) -> Result<Vec<CycleItem>, String> {
-    let maybe_conn = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn.as_ref() {
-        let mut stmt = conn.prepare(
-                "SELECT cc.logo_id, l.name, cc.status, cc.order_index 
-                 FROM cycle_config cc 
-                 JOIN logos l ON cc.logo_id = l.id 
-                 ORDER BY cc.order_index ASC"
-            ).map_err(|e| format!("Cycle Query Prepare Failed: {}", e))?;
-
-        let item_iter = stmt.query_map([], |row| {
-            let logo_id: String = row.get(0)?;
-            Ok(CycleItem {
-                id: logo_id.clone(), // Use logo_id as the item's unique ID
-                logo_id: logo_id,
-                name: row.get(1)?,
-                status: row.get(2)?,
-                order_index: row.get(3)?,
-            })
-        }).map_err(|e| format!("Cycle Query Map Failed: {}", e))?;
-
-        item_iter.collect::<Result<Vec<CycleItem>>>()
-                 .map_err(|e| format!("Cycle Collect Failed: {}", e))
-    } else {
-        Err("Database connection not available".to_string())
-    }
+fn get_cycle_items(group_id: Option<String>, state: State<AppState>) -> Result<Vec<CycleItem>, AppError> {
+    with_db(&state, |conn| {
+        CycleRepo::for_group(conn, group_id.as_deref()).list()
+            .map_err(|e| e.context("Cycle Query Failed"))
+    })
 }
 
-// Command to overwrite the entire cycle configuration
+// Command to overwrite the entire cycle configuration. `items` carries the
+// per-item settings; a bare `logo_ids` list is still accepted and gets the
+// defaults.
 #[derive(Deserialize)]
 struct CycleConfigPayload {
+    #[serde(default)]
+    group_id: Option<String>,
+    #[serde(default)]
     logo_ids: Vec<String>,
+    #[serde(default)]
+    items: Option<Vec<CycleItemConfig>>,
 }
 
 #[tauri::command]
-fn set_cycle_config(payload: CycleConfigPayload, state: State<AppState>) -> Result<(), String> {
-    let mut maybe_conn_lock = state.db.lock().unwrap();
-    if let Some(conn) = maybe_conn_lock.as_mut() {
-        // Use a transaction for atomic update
-        let tx = conn.transaction().map_err(|e| format!("Transaction Begin Failed: {}", e))?;
-        
-        // 1. Clear existing cycle config
-        tx.execute("DELETE FROM cycle_config", [])
-            .map_err(|e| format!("Failed to clear cycle config: {}", e))?;
-
-        // 2. Insert new ordered items
-        for (index, logo_id) in payload.logo_ids.iter().enumerate() {
-            tx.execute(
-                "INSERT INTO cycle_config (logo_id, order_index, status) VALUES (?1, ?2, ?3)",
-                params![logo_id, index as u32, "cycle"], // Default status to 'cycle'
-            ).map_err(|e| format!("Failed to insert cycle item {}: {}", logo_id, e))?;
+fn set_cycle_config(payload: CycleConfigPayload, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        let repo = CycleRepo::for_group(conn, payload.group_id.as_deref());
+        match &payload.items {
+            Some(items) => repo.set_items(items),
+            None => repo.set(&payload.logo_ids),
         }
+        .map_err(|e| e.context("Failed to update cycle config"))
+    })
+}
 
-        tx.commit().map_err(|e| format!("Transaction Commit Failed: {}", e))
-    } else {
-        Err("Database connection not available".to_string())
-    }
-} 
\ No newline at end of file
+// Changes one item's status, duration, transition or skip flag in place
+#[tauri::command]
+fn update_cycle_item(item: CycleItemConfig, group_id: Option<String>, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        CycleRepo::for_group(conn, group_id.as_deref()).update_item(&item)
+            .map_err(|e| e.context("Failed to update cycle item"))
+    })
+}
+
+#[tauri::command]
+fn get_cycle_settings(state: State<AppState>) -> Result<CycleSettings, AppError> {
+    with_db(&state, |conn| {
+        CycleRepo::new(conn).settings()
+            .map_err(|e| e.context("Failed to load cycle settings"))
+    })
+}
+
+#[tauri::command]
+fn set_cycle_default_duration(seconds: u32, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        CycleRepo::new(conn).set_default_duration(seconds)
+            .map_err(|e| e.context("Failed to update cycle settings"))
+    })
+}
+
+// --- Cycle Group Commands ---
+
+#[tauri::command]
+fn get_cycle_groups(state: State<AppState>) -> Result<Vec<CycleGroup>, AppError> {
+    with_db(&state, |conn| {
+        CycleGroupRepo::new(conn).list()
+            .map_err(|e| e.context("Cycle Group Query Failed"))
+    })
+}
+
+#[tauri::command]
+fn create_cycle_group(name: String, state: State<AppState>) -> Result<String, AppError> {
+    with_db(&state, |conn| {
+        CycleGroupRepo::new(conn).create(&name)
+            .map_err(|e| e.context("Failed to create cycle group"))
+    })
+}
+
+#[tauri::command]
+fn duplicate_cycle_group(id: String, name: String, state: State<AppState>) -> Result<String, AppError> {
+    with_db(&state, |conn| {
+        CycleGroupRepo::new(conn).duplicate(&id, &name)
+            .map_err(|e| e.context("Failed to duplicate cycle group"))
+    })
+}
+
+#[tauri::command]
+fn rename_cycle_group(id: String, name: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        CycleGroupRepo::new(conn).rename(&id, &name)
+            .map_err(|e| e.context("Failed to rename cycle group"))
+    })
+}
+
+#[tauri::command]
+fn delete_cycle_group(id: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        CycleGroupRepo::new(conn).delete(&id)
+            .map_err(|e| e.context("Failed to delete cycle group"))
+    })
+}
+
+// Switches playback to another group; the engine picks it up on its next tick
+#[tauri::command]
+fn activate_cycle_group(id: String, state: State<AppState>) -> Result<(), AppError> {
+    with_db(&state, |conn| {
+        CycleGroupRepo::new(conn).activate(&id)
+            .map_err(|e| e.context("Failed to activate cycle group"))
+    })
+}
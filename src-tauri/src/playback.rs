@@ -0,0 +1,65 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use vj_event_sync_core::error::AppError;
+use vj_event_sync_core::playback::{PlaybackEngine, PlaybackSnapshot, SystemClock};
+
+use crate::AppState;
+
+// How often the engine re-evaluates what's on screen
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+// Latest snapshot, so a window that opens between ticks doesn't start blank
+pub struct PlaybackState(pub Mutex<Option<PlaybackSnapshot>>);
+
+// Runs the playback engine for the lifetime of the app, emitting
+// `now-showing` and `up-next` on every tick
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut engine = PlaybackEngine::new(SystemClock);
+        let mut last_changes: Option<i64> = None;
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            reload_if_changed(&app, &mut engine, &mut last_changes);
+
+            let snapshot = engine.tick();
+            let _ = app.emit_all("now-showing", &snapshot.now_showing);
+            let _ = app.emit_all("up-next", &snapshot.up_next);
+            *app.state::<PlaybackState>().0.lock().unwrap() = Some(snapshot);
+        }
+    });
+}
+
+// Reloads the cycle and schedule when anything was written to the database
+// since the last load. `total_changes()` counts every row changed through
+// this connection, so it moves whenever a command edits either table.
+fn reload_if_changed(app: &AppHandle, engine: &mut PlaybackEngine<SystemClock>, last_changes: &mut Option<i64>) {
+    let state = app.state::<AppState>();
+    let maybe_conn = state.db.lock().unwrap();
+    let Some(conn) = maybe_conn.as_ref() else {
+        return;
+    };
+
+    let changes: i64 = match conn.query_row("SELECT total_changes()", [], |row| row.get(0)) {
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("Playback: failed to check for changes: {}", e);
+            return;
+        }
+    };
+    if *last_changes == Some(changes) {
+        return;
+    }
+
+    match engine.load(conn) {
+        Ok(()) => *last_changes = Some(changes),
+        Err(e) => eprintln!("Playback: failed to load cycle and schedule: {}", e),
+    }
+}
+
+#[tauri::command]
+pub fn get_now_showing(state: State<PlaybackState>) -> Result<Option<PlaybackSnapshot>, AppError> {
+    Ok(state.0.lock().unwrap().clone())
+}
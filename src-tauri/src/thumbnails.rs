@@ -0,0 +1,89 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use vj_event_sync_core::thumbnail::{self, ThumbnailJob};
+
+use crate::{logo_duplicates, logo_media, with_db, AppState};
+
+// Wakes the background worker, which renders thumbnails, then hashes new
+// or changed files for duplicate detection and probes their media info
+pub struct ThumbnailWorker(pub Mutex<Option<Sender<()>>>);
+
+// Payload of `thumbnail-progress`, sent after each thumbnail of a batch.
+// `thumbnail_path` is None if the file couldn't be rendered.
+#[derive(Debug, Serialize, Clone)]
+struct ThumbnailProgress {
+    logo_id: String,
+    thumbnail_path: Option<String>,
+    done: usize,
+    total: usize,
+}
+
+// Starts the background worker and has it catch up on missing thumbnails
+pub fn start(app: &AppHandle) {
+    let Some(cache_dir) = app.path_resolver().app_cache_dir() else {
+        eprintln!("Thumbnails: no cache folder available");
+        return;
+    };
+    let (sender, wake) = mpsc::channel();
+    *app.state::<ThumbnailWorker>().0.lock().unwrap() = Some(sender);
+
+    let worker_app = app.clone();
+    std::thread::spawn(move || run(worker_app, cache_dir.join("thumbnails"), wake));
+    request(app);
+}
+
+// Asks the worker to look for logos that need a (new) thumbnail, hashes or
+// media info. Cheap to call after anything that may add or change logos.
+pub fn request(app: &AppHandle) {
+    if let Some(sender) = app.state::<ThumbnailWorker>().0.lock().unwrap().as_ref() {
+        let _ = sender.send(());
+    }
+}
+
+fn run(app: AppHandle, cache_dir: PathBuf, wake: Receiver<()>) {
+    while wake.recv().is_ok() {
+        // Requests that piled up while busy are covered by this pass
+        while wake.try_recv().is_ok() {}
+
+        let state = app.state::<AppState>();
+        let jobs = match with_db(&state, |conn| thumbnail::pending(conn, &cache_dir)) {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                eprintln!("Thumbnails: failed to list logos: {}", e);
+                continue;
+            }
+        };
+        let total = jobs.len();
+        for (index, job) in jobs.iter().enumerate() {
+            let thumbnail_path = process(&app, job);
+            let _ = app.emit_all("thumbnail-progress", ThumbnailProgress {
+                logo_id: job.logo_id.clone(),
+                thumbnail_path,
+                done: index + 1,
+                total,
+            });
+        }
+
+        logo_duplicates::update_hashes(&state);
+        logo_media::update_media_info(&state);
+    }
+}
+
+// Renders without holding the database, then records the result
+fn process(app: &AppHandle, job: &ThumbnailJob) -> Option<String> {
+    let generated = match thumbnail::generate(job) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Thumbnails: {}", e);
+            false
+        }
+    };
+    let state = app.state::<AppState>();
+    with_db(&state, |conn| thumbnail::store(conn, job, generated)).unwrap_or_else(|e| {
+        eprintln!("Thumbnails: failed to save thumbnail: {}", e);
+        None
+    })
+}
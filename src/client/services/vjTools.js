@@ -52,6 +52,8 @@ class VjToolsService {
 
   async syncMessages() {
     try {
+      await this.deliverQueuedMessages();
+
       const response = await axios.get(`${this.baseUrl}/messages`, {
         headers: {
           Authorization: `Bearer ${this.token}`
@@ -79,11 +81,39 @@ class VjToolsService {
 
       return response.data;
     } catch (error) {
+      // No response at all means no network: keep the message for later
+      if (!error.response && message.conversation_id && message.content) {
+        return await invoke('queue_message', {
+          conversationId: message.conversation_id,
+          senderId: message.sender_id || localStorage.getItem('vjToolsUsername'),
+          content: message.content
+        });
+      }
       console.error('Failed to send message:', error);
       throw error;
     }
   }
 
+  // Sends messages written while offline, oldest first. Stops at the first
+  // failure so the rest stay queued in order.
+  async deliverQueuedMessages() {
+    const queued = await invoke('get_queued_messages');
+    for (const message of queued) {
+      const response = await axios.post(`${this.baseUrl}/messages`, {
+        conversation_id: message.conversation_id,
+        sender_id: message.sender_id,
+        content: message.content,
+        sent_at: message.sent_at
+      }, {
+        headers: {
+          Authorization: `Bearer ${this.token}`
+        }
+      });
+
+      await invoke('mark_message_delivered', { localId: message.id, message: response.data });
+    }
+  }
+
   async updateEvent(eventId, updates) {
     try {
       const response = await axios.put(`${this.baseUrl}/events/${eventId}`, updates, {